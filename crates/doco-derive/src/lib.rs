@@ -9,8 +9,11 @@
 //! re-exports the macros from this crate.
 
 use proc_macro::TokenStream;
-use quote::{format_ident, quote};
-use syn::{parse_macro_input, ItemFn};
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote, quote_spanned};
+use syn::{parse_macro_input, DeriveInput, Expr, ExprArray, FnArg, Ident, ItemFn, LitBool};
+
+mod page;
 
 /// Collect and run the end-to-end tests with Doco
 ///
@@ -40,32 +43,20 @@ pub fn main(_args: TokenStream, input: TokenStream) -> TokenStream {
     let main_fn = parse_macro_input!(input as ItemFn);
     let main_block = main_fn.block;
 
-    // Generate code that initializes the asynchronous runtime, the inventory for tests, and then
-    // sets up the given function as the entry point for the program
+    // Generate code that initializes the asynchronous runtime, and then sets up the given function
+    // as the entry point for the program that runs all collected tests
     let initialization_and_function = quote! {
-        #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
-        struct TestCase {
-            pub name: &'static str,
-            pub function: fn(doco::Client) -> doco::Result<()>,
-        }
-
-        doco::inventory::collect!(TestCase);
-
         #[tokio::main]
-        async fn main() {
+        async fn main() -> std::process::ExitCode {
             let doco: doco::Doco = #main_block;
 
-            let test_runner = doco::TestRunner::init(doco).await.expect("failed to initialize the test runner");
-            let tests = doco::inventory::iter::<TestCase>.into_iter().count();
-
-            println!("Running {} tests...\n", tests);
-
-            for test in doco::inventory::iter::<TestCase> {
-                // TODO: Collect results, report them, and remove the `expect` statement
-                test_runner.run(test.name, test.function).await.expect("failed to run test");
+            match doco::TestRunner::init(doco).await {
+                Ok(test_runner) => test_runner.run_all().await,
+                Err(error) => {
+                    eprintln!("error: failed to initialize the test runner: {error:?}");
+                    std::process::ExitCode::FAILURE
+                }
             }
-
-            println!("\nDone.");
         }
     };
 
@@ -78,6 +69,19 @@ pub fn main(_args: TokenStream, input: TokenStream) -> TokenStream {
 /// executed by Doco as an end-to-end test. The test function is passed a [`doco::Client`] that can
 /// be used to interact with the web application, and it should return a [`doco::Result`].
 ///
+/// Each argument of the test function is a fixture, i.e. a type that implements
+/// [`doco::Fixture`]. Doco sets up the fixtures before the test is called, which makes it possible
+/// to request e.g. the [`doco::Logs`] of the server in addition to the [`doco::Client`].
+///
+/// When the test suite has a [`doco::Setup`] function, the cookies and `localStorage` that it has
+/// captured are restored in the browser before the test. Tests that should start with an empty
+/// browser can opt out with `#[doco::test(storage_state = false)]`.
+///
+/// A test can be run for several cases with `#[doco::test(cases = [...])]`. Each case is written
+/// like a function call, e.g. `admin("admin@example.com", true)`, and runs as a separate test that
+/// is named after the test and the case, e.g. `login[admin]`. The values of the case are passed as
+/// the last arguments of the test function, after the fixtures.
+///
 /// # Example
 ///
 /// ```ignore
@@ -93,38 +97,335 @@ pub fn main(_args: TokenStream, input: TokenStream) -> TokenStream {
 ///
 ///     Ok(())
 /// }
+///
+/// #[doco::test(cases = [admin("admin@example.com", true), guest("guest@example.com", false)])]
+/// async fn login(client: Client, email: &str, can_edit: bool) -> Result<()> {
+///     client.goto("/login").await?;
+///     // ...
+///     Ok(())
+/// }
 /// ```
 #[proc_macro_attribute]
-pub fn test(_attr: TokenStream, input: TokenStream) -> TokenStream {
+pub fn test(attr: TokenStream, input: TokenStream) -> TokenStream {
+    // Parse the options of the test, e.g. `#[doco::test(storage_state = false)]`
+    let mut storage_state = true;
+    let mut cases = None;
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("storage_state") {
+            storage_state = meta.value()?.parse::<LitBool>()?.value;
+            Ok(())
+        } else if meta.path.is_ident("cases") {
+            cases = Some(parse_cases(meta.value()?.parse()?)?);
+            Ok(())
+        } else {
+            Err(meta.error("unsupported test attribute, expected `storage_state` or `cases`"))
+        }
+    });
+    parse_macro_input!(attr with parser);
+
     // Parse the function that has been annotated with the `#[doco_derive::test]` attribute
     let input_fn = parse_macro_input!(input as ItemFn);
     let input_fn_ident = &input_fn.sig.ident;
     let input_fn_name = input_fn_ident.to_string();
 
-    // Extract the function name, arguments, and body for the final test function
-    let test_fn_ident = format_ident!("{}_test", &input_fn_ident);
-    let test_args = &input_fn.sig.inputs;
+    // A test without cases is run once without any values
+    let cases = cases.unwrap_or_else(|| vec![Case::default()]);
+
+    // Extract the fixtures that are requested as arguments. The last arguments of a parameterized
+    // test receive the values of the case instead.
+    let values = cases[0].values.len();
+    let Some(fixture_count) = input_fn.sig.inputs.len().checked_sub(values) else {
+        return syn::Error::new_spanned(
+            &input_fn.sig.inputs,
+            format!("the test must take an argument for each of the {values} values of a case"),
+        )
+        .to_compile_error()
+        .into();
+    };
+    let fixtures: Vec<TokenStream2> = fixtures(&input_fn)
+        .into_iter()
+        .take(fixture_count)
+        .collect();
+
+    // Record the line of the test function's name, so that failures point to the test
+    let line = quote_spanned!(input_fn_ident.span()=> line!());
+
+    // Generate a test function for each case that sets up the fixtures and executes the test block
+    // inside doco's asynchronous runtime. The test runner calls this function on a blocking thread.
+    let test_functions = cases.iter().map(|case| {
+        let (test_fn_ident, name) = match &case.ident {
+            Some(ident) => (
+                format_ident!("{}_{}_test", input_fn_ident, ident),
+                format!("{input_fn_name}[{ident}]"),
+            ),
+            None => (
+                format_ident!("{}_test", input_fn_ident),
+                input_fn_name.clone(),
+            ),
+        };
+        let values = &case.values;
+
+        quote! {
+            fn #test_fn_ident(context: doco::TestContext) -> doco::Result<()> {
+                tokio::runtime::Handle::current().block_on(async {
+                    #input_fn_ident(#(#fixtures,)* #(#values),*).await
+                })
+            }
+
+            doco::inventory::submit!(doco::TestCase {
+                name: #name,
+                module_path: module_path!(),
+                file: file!(),
+                line: #line,
+                storage_state: #storage_state,
+                function: #test_fn_ident
+            });
+        }
+    });
 
-    // Generate a test function that executes the test block inside doco's asynchronous runtime
     let test_function = quote! {
         #input_fn
 
-        fn #test_fn_ident(#test_args) -> doco::Result<()> {
-            std::thread::spawn(move || {
-                let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+        #(#test_functions)*
+    };
+
+    test_function.into()
+}
+
+/// Case of a parameterized test
+#[derive(Default)]
+struct Case {
+    /// The name of the case, which is appended to the name of the test
+    ident: Option<Ident>,
+
+    /// The values that are passed to the test
+    values: Vec<Expr>,
+}
+
+/// Parse the cases of a parameterized test, e.g. `[admin("admin", true), guest("guest", false)]`
+///
+/// Each case is written like a function call, where the name of the function names the case and
+/// the arguments are the values that are passed to the test. All cases must have the same number
+/// of values, and their names must be unique.
+fn parse_cases(array: ExprArray) -> syn::Result<Vec<Case>> {
+    let mut cases: Vec<Case> = Vec::with_capacity(array.elems.len());
+
+    for expr in array.elems {
+        let Expr::Call(call) = expr else {
+            return Err(syn::Error::new_spanned(
+                expr,
+                "expected a case like `name(value, ...)`",
+            ));
+        };
+        let Expr::Path(path) = call.func.as_ref() else {
+            return Err(syn::Error::new_spanned(
+                call.func,
+                "expected the name of the case",
+            ));
+        };
+        let Some(ident) = path.path.get_ident().cloned() else {
+            return Err(syn::Error::new_spanned(
+                path,
+                "expected the name of the case",
+            ));
+        };
+
+        if cases.iter().any(|case| case.ident.as_ref() == Some(&ident)) {
+            return Err(syn::Error::new_spanned(ident, "duplicate case"));
+        }
+        if let Some(first) = cases.first() {
+            if first.values.len() != call.args.len() {
+                return Err(syn::Error::new_spanned(
+                    call.args,
+                    format!("expected {} values like the first case", first.values.len()),
+                ));
+            }
+        }
+
+        cases.push(Case {
+            ident: Some(ident),
+            values: call.args.into_iter().collect(),
+        });
+    }
+
+    if cases.is_empty() {
+        return Err(syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "expected at least one case",
+        ));
+    }
+
+    Ok(cases)
+}
+
+/// Run a hook once before the first test of the suite
+///
+/// The hook is an asynchronous function without arguments that returns a [`doco::Result`]. It
+/// runs outside of the environment of a test, so it cannot request fixtures. If a `before_all`
+/// hook fails, the tests are skipped and the failure is reported separately from test failures.
+///
+/// # Example
+///
+/// ```ignore
+/// use doco::Result;
+///
+/// #[doco::before_all]
+/// async fn build_assets() -> Result<()> {
+///     std::process::Command::new("npm").args(["run", "build"]).status()?;
+///     Ok(())
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn before_all(attr: TokenStream, input: TokenStream) -> TokenStream {
+    hook(attr, input, quote!(BeforeAll), false)
+}
+
+/// Run a hook once after the last test of the suite
+///
+/// The hook runs even if tests have failed. Like a `before_all` hook, it takes no arguments.
+#[proc_macro_attribute]
+pub fn after_all(attr: TokenStream, input: TokenStream) -> TokenStream {
+    hook(attr, input, quote!(AfterAll), false)
+}
+
+/// Run a hook before each test of the suite
+///
+/// The hook runs in the environment of the test and requests fixtures as its arguments, just like
+/// a test. If a `before_each` hook fails, the test is skipped and the failure is reported
+/// separately from test failures.
+///
+/// # Example
+///
+/// ```ignore
+/// use doco::{Client, Result};
+///
+/// #[doco::before_each]
+/// async fn accept_cookies(client: Client) -> Result<()> {
+///     client.goto("/").await?;
+///     client.find(doco::By::text("Accept").locator()).await?.click().await?;
+///     Ok(())
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn before_each(attr: TokenStream, input: TokenStream) -> TokenStream {
+    hook(attr, input, quote!(BeforeEach), true)
+}
+
+/// Run a hook after each test of the suite
+///
+/// The hook runs in the environment of the test, even if the test has failed, and requests
+/// fixtures as its arguments, just like a test.
+#[proc_macro_attribute]
+pub fn after_each(attr: TokenStream, input: TokenStream) -> TokenStream {
+    hook(attr, input, quote!(AfterEach), true)
+}
+
+/// Generate a hook of the given kind that is collected by the test runner
+///
+/// Hooks that run for each test set up their fixtures from the test's context, while hooks that
+/// run once for the suite must not take any arguments.
+fn hook(attr: TokenStream, input: TokenStream, kind: TokenStream2, per_test: bool) -> TokenStream {
+    let attr = TokenStream2::from(attr);
+    if !attr.is_empty() {
+        return syn::Error::new_spanned(attr, "hooks do not take any options")
+            .to_compile_error()
+            .into();
+    }
+
+    let input_fn = parse_macro_input!(input as ItemFn);
+    let input_fn_ident = &input_fn.sig.ident;
+    let input_fn_name = input_fn_ident.to_string();
+    let hook_fn_ident = format_ident!("{}_hook", &input_fn_ident);
 
-                runtime.block_on(async {
-                    #input_fn_ident(client).await
+    // Record the location of the hook, so that hooks of the same kind run in the order they are
+    // defined in
+    let line = quote_spanned!(input_fn_ident.span()=> line!());
+
+    let hook_function = if per_test {
+        let fixtures = fixtures(&input_fn);
+
+        quote! {
+            fn #hook_fn_ident(context: doco::TestContext) -> doco::Result<()> {
+                tokio::runtime::Handle::current().block_on(async {
+                    #input_fn_ident(#(#fixtures),*).await
                 })
-            })
-            .join().map_err(|_| doco::anyhow!("failed to run test in isolated thread"))?
+            }
         }
+    } else if let Some(arg) = input_fn.sig.inputs.first() {
+        syn::Error::new_spanned(arg, "hooks of the suite cannot take arguments").to_compile_error()
+    } else {
+        quote! {
+            fn #hook_fn_ident() -> doco::Result<()> {
+                tokio::runtime::Handle::current().block_on(async {
+                    #input_fn_ident().await
+                })
+            }
+        }
+    };
 
-        doco::inventory::submit!(crate::TestCase {
+    let hook = quote! {
+        #input_fn
+
+        #hook_function
+
+        doco::inventory::submit!(doco::Hook {
             name: #input_fn_name,
-            function: #test_fn_ident
+            file: file!(),
+            line: #line,
+            function: doco::HookFunction::#kind(#hook_fn_ident)
         });
     };
 
-    test_function.into()
+    hook.into()
+}
+
+/// Generate the code that sets up the fixtures that a function requests as its arguments
+fn fixtures(function: &ItemFn) -> Vec<TokenStream2> {
+    function
+        .sig
+        .inputs
+        .iter()
+        .map(|arg| match arg {
+            FnArg::Typed(arg) => {
+                let ty = &arg.ty;
+                quote! { <#ty as doco::Fixture>::setup(&context).await? }
+            }
+            FnArg::Receiver(receiver) => {
+                syn::Error::new_spanned(receiver, "tests cannot take `self` as an argument")
+                    .to_compile_error()
+            }
+        })
+        .collect()
+}
+
+/// Derive a page object for a page of the web application
+///
+/// The `#[derive(doco::Page)]` macro implements [`doco::Page`] and [`doco::Fixture`] for a struct
+/// with named fields. The path of the page is set with the `#[page(path = "...")]` attribute on the
+/// struct. Fields of type [`doco::LazyElement`] are annotated with `#[locator(...)]`, and the macro
+/// generates an accessor method for each of them, which must not be named `new` or `visit`. All
+/// other fields must be of type [`doco::Client`] and receive a clone of it.
+///
+/// # Example
+///
+/// ```ignore
+/// use doco::{LazyElement, Page};
+///
+/// #[derive(Page)]
+/// #[page(path = "/login")]
+/// struct LoginPage {
+///     #[locator(css = "input[name=email]")]
+///     email: LazyElement,
+///
+///     #[locator(test_id = "submit")]
+///     submit: LazyElement,
+/// }
+/// ```
+#[proc_macro_derive(Page, attributes(page, locator))]
+pub fn page(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    page::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
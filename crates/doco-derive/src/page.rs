@@ -0,0 +1,293 @@
+//! Derive macro for page objects
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Fields, Ident, LitStr, Result, Type};
+
+/// The methods of `doco::Page` that the accessors of the fields must not shadow
+const RESERVED_NAMES: &[&str] = &["new", "visit"];
+
+/// Expand the `#[derive(Page)]` macro for the given struct
+pub fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let path = parse_path(&input)?;
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "pages can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &data.fields,
+            "pages can only be derived for structs with named fields",
+        ));
+    };
+
+    let mut initializers = Vec::with_capacity(fields.named.len());
+    let mut accessors = Vec::new();
+
+    for field in &fields.named {
+        let name = field
+            .ident
+            .as_ref()
+            .expect("named fields have an identifier");
+
+        match parse_locator(field)? {
+            Some(_) if RESERVED_NAMES.iter().any(|reserved| name == reserved) => {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    format!("`{name}` is reserved for the methods of `doco::Page`"),
+                ));
+            }
+            Some(locator) => {
+                initializers.push(quote! { #name: client.locate(#locator) });
+                accessors.push(quote! {
+                    /// Return the lazily resolved element for this field
+                    pub fn #name(&self) -> &doco::LazyElement {
+                        &self.#name
+                    }
+                });
+            }
+            None if is_client(&field.ty) => initializers.push(quote! { #name: client.clone() }),
+            None => {
+                return Err(syn::Error::new_spanned(
+                    &field.ty,
+                    "fields of a page must either have a `#[locator(...)]` attribute or be the \
+                     `doco::Client`",
+                ));
+            }
+        }
+    }
+
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            #(#accessors)*
+        }
+
+        impl #impl_generics doco::Page for #ident #ty_generics #where_clause {
+            const PATH: &'static str = #path;
+
+            fn new(client: &doco::Client) -> Self {
+                Self {
+                    #(#initializers),*
+                }
+            }
+        }
+
+        impl #impl_generics doco::Fixture for #ident #ty_generics #where_clause {
+            async fn setup(context: &doco::TestContext) -> doco::Result<Self> {
+                <Self as doco::Page>::visit(context.client()).await
+            }
+        }
+    })
+}
+
+/// Return whether the type of a field is `Client`, e.g. `doco::Client`
+fn is_client(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Client" && segment.arguments.is_empty()),
+        _ => false,
+    }
+}
+
+/// Parse the path of the page from the `#[page(path = "...")]` attribute
+fn parse_path(input: &DeriveInput) -> Result<LitStr> {
+    let mut path = None;
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("page"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("path") {
+                path = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported page attribute, expected `path`"))
+            }
+        })?;
+    }
+
+    path.ok_or_else(|| {
+        syn::Error::new_spanned(&input.ident, "missing `#[page(path = \"...\")]` attribute")
+    })
+}
+
+/// Parse the locator of a field from its `#[locator(...)]` attribute
+///
+/// Returns `None` if the field has no such attribute.
+fn parse_locator(field: &syn::Field) -> Result<Option<TokenStream>> {
+    let Some(attr) = field
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("locator"))
+    else {
+        return Ok(None);
+    };
+
+    let mut strategy: Option<(Ident, LitStr)> = None;
+    let mut name: Option<LitStr> = None;
+
+    attr.parse_nested_meta(|meta| {
+        let ident = meta
+            .path
+            .get_ident()
+            .cloned()
+            .ok_or_else(|| meta.error("expected a locator strategy"))?;
+        let value = meta.value()?.parse::<LitStr>()?;
+
+        if ident == "name" {
+            name = Some(value);
+        } else if strategy.is_some() {
+            return Err(meta.error("only one locator strategy can be specified"));
+        } else {
+            strategy = Some((ident, value));
+        }
+
+        Ok(())
+    })?;
+
+    let Some((strategy, value)) = strategy else {
+        return Err(syn::Error::new(attr.span(), "missing locator strategy"));
+    };
+
+    if name.is_some() && strategy != "role" {
+        return Err(syn::Error::new(
+            attr.span(),
+            "`name` can only be combined with `role`",
+        ));
+    }
+
+    let locator = match strategy.to_string().as_str() {
+        "css" => quote! { doco::Locator::Css(#value) },
+        "id" => quote! { doco::Locator::Id(#value) },
+        "link_text" => quote! { doco::Locator::LinkText(#value) },
+        "xpath" => quote! { doco::Locator::XPath(#value) },
+        "test_id" => quote! { &client.test_id(#value) },
+        "text" => quote! { &doco::By::text(#value) },
+        "label" => quote! { &doco::By::label(#value) },
+        "placeholder" => quote! { &doco::By::placeholder(#value) },
+        "role" => match name {
+            Some(name) => quote! { &doco::By::role(#value).name(#name) },
+            None => quote! { &doco::By::role(#value) },
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                strategy,
+                "unsupported locator strategy, expected one of `css`, `id`, `link_text`, \
+                 `xpath`, `test_id`, `text`, `label`, `placeholder`, or `role`",
+            ))
+        }
+    };
+
+    Ok(Some(locator))
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn error(input: DeriveInput) -> String {
+        expand(input)
+            .expect_err("expansion should have failed")
+            .to_string()
+    }
+
+    #[test]
+    fn expand_locators_and_client() {
+        let input = parse_quote! {
+            #[page(path = "/login")]
+            struct LoginPage {
+                client: doco::Client,
+
+                #[locator(role = "button", name = "Sign in")]
+                submit: doco::LazyElement,
+            }
+        };
+
+        let expanded = expand(input).unwrap().to_string();
+
+        assert!(expanded.contains("const PATH : & 'static str = \"/login\""));
+        assert!(expanded.contains("client : client . clone ()"));
+        assert!(expanded.contains("pub fn submit (& self) -> & doco :: LazyElement"));
+    }
+
+    #[test]
+    fn expand_test_id_through_client() {
+        let input = parse_quote! {
+            #[page(path = "/")]
+            struct HomePage {
+                #[locator(test_id = "greeting")]
+                greeting: doco::LazyElement,
+            }
+        };
+
+        let expanded = expand(input).unwrap().to_string();
+
+        assert!(expanded.contains("client . locate (& client . test_id (\"greeting\"))"));
+    }
+
+    #[test]
+    fn unsupported_field() {
+        let input = parse_quote! {
+            #[page(path = "/")]
+            struct HomePage {
+                title: String,
+            }
+        };
+
+        assert!(error(input).contains("must either have a `#[locator(...)]` attribute"));
+    }
+
+    #[test]
+    fn reserved_name() {
+        let input = parse_quote! {
+            #[page(path = "/")]
+            struct HomePage {
+                #[locator(id = "visit")]
+                visit: doco::LazyElement,
+            }
+        };
+
+        assert_eq!(
+            "`visit` is reserved for the methods of `doco::Page`",
+            error(input)
+        );
+    }
+
+    #[test]
+    fn missing_path() {
+        let input = parse_quote! {
+            struct HomePage {
+                client: doco::Client,
+            }
+        };
+
+        assert_eq!("missing `#[page(path = \"...\")]` attribute", error(input));
+    }
+
+    #[test]
+    fn several_strategies() {
+        let input = parse_quote! {
+            #[page(path = "/")]
+            struct HomePage {
+                #[locator(id = "a", css = "#a")]
+                a: doco::LazyElement,
+            }
+        };
+
+        assert_eq!("only one locator strategy can be specified", error(input));
+    }
+}
@@ -0,0 +1,279 @@
+// Rules engine for accessibility audits
+//
+// The script is executed through WebDriver with two arguments: the element whose subtree is audited
+// (or `null` for the whole page), and a list of rule ids that are disabled. It returns the rules
+// that have been violated, together with the elements that violate them. The engine is a small
+// subset of the checks of axe-core. Its rule ids carry a `doco-` prefix, so that they are not
+// mistaken for the results of axe-core, whose rules are more thorough.
+
+const [root, disabled] = arguments;
+const scope = root || document.documentElement;
+const page = root === null;
+
+const all = (selector) =>
+  [scope, ...scope.querySelectorAll(selector)].filter((element) => element.matches(selector));
+
+const isVisible = (element) =>
+  element.getClientRects().length > 0 &&
+  getComputedStyle(element).visibility !== "hidden" &&
+  element.closest("[aria-hidden='true']") === null;
+
+const text = (element) => (element.textContent || "").replace(/\s+/g, " ").trim();
+
+const attribute = (element, name) => (element.getAttribute(name) || "").trim();
+
+const accessibleName = (element) => {
+  const labelledBy = attribute(element, "aria-labelledby")
+    .split(/\s+/)
+    .map((id) => document.getElementById(id))
+    .filter(Boolean)
+    .map(text)
+    .join(" ")
+    .trim();
+  if (labelledBy) return labelledBy;
+
+  const label = attribute(element, "aria-label");
+  if (label) return label;
+
+  if (element.labels && element.labels.length > 0) {
+    const labels = Array.from(element.labels).map(text).join(" ").trim();
+    if (labels) return labels;
+  }
+
+  const tag = element.tagName.toLowerCase();
+  const type = (element.getAttribute("type") || "").toLowerCase();
+
+  if (tag === "img" || (tag === "input" && type === "image")) {
+    return attribute(element, "alt") || attribute(element, "title");
+  }
+
+  if (tag === "input" && ["button", "submit", "reset"].includes(type)) {
+    return attribute(element, "value") || (type === "button" ? "" : type);
+  }
+
+  if (["input", "select", "textarea"].includes(tag)) {
+    return attribute(element, "title");
+  }
+
+  const images = Array.from(element.querySelectorAll("img[alt]"))
+    .map((image) => image.alt)
+    .join(" ");
+
+  return `${images} ${text(element)}`.trim() || attribute(element, "title");
+};
+
+const selector = (element) => {
+  const parts = [];
+
+  for (let node = element; node && node.nodeType === 1; node = node.parentElement) {
+    if (node.id && document.querySelectorAll(`#${CSS.escape(node.id)}`).length === 1) {
+      parts.unshift(`#${CSS.escape(node.id)}`);
+      break;
+    }
+
+    let part = node.tagName.toLowerCase();
+    const siblings = node.parentElement
+      ? Array.from(node.parentElement.children).filter((sibling) => sibling.tagName === node.tagName)
+      : [];
+    if (siblings.length > 1) part += `:nth-of-type(${siblings.indexOf(node) + 1})`;
+
+    parts.unshift(part);
+  }
+
+  return parts.join(" > ");
+};
+
+const snippet = (element) => {
+  const html = element.outerHTML;
+  const tag = html.slice(0, html.indexOf(">") + 1);
+  return tag.length > 200 ? `${tag.slice(0, 197)}...` : tag;
+};
+
+const headingLevel = (element) => {
+  const level = parseInt(element.getAttribute("aria-level"), 10);
+  if (!isNaN(level)) return level;
+  const match = element.tagName.match(/^H([1-6])$/);
+  return match ? parseInt(match[1], 10) : 2;
+};
+
+const parseColor = (color) => {
+  const [r = 0, g = 0, b = 0, a = 1] = (color.match(/[\d.]+/g) || []).map(Number);
+  return { r, g, b, a };
+};
+
+const blend = (top, bottom) => ({
+  r: top.r * top.a + bottom.r * (1 - top.a),
+  g: top.g * top.a + bottom.g * (1 - top.a),
+  b: top.b * top.a + bottom.b * (1 - top.a),
+  a: 1,
+});
+
+const background = (element) => {
+  const layers = [];
+
+  for (let node = element; node; node = node.parentElement) {
+    const style = getComputedStyle(node);
+    if (style.backgroundImage !== "none") return null;
+
+    const color = parseColor(style.backgroundColor);
+    if (color.a > 0) layers.push(color);
+    if (color.a >= 1) break;
+  }
+
+  return layers.reduceRight((color, layer) => blend(layer, color), { r: 255, g: 255, b: 255, a: 1 });
+};
+
+const luminance = ({ r, g, b }) => {
+  const channel = (value) => {
+    const c = value / 255;
+    return c <= 0.03928 ? c / 12.92 : Math.pow((c + 0.055) / 1.055, 2.4);
+  };
+  return 0.2126 * channel(r) + 0.7152 * channel(g) + 0.0722 * channel(b);
+};
+
+const contrast = (first, second) => {
+  const [light, dark] = [luminance(first), luminance(second)].sort((a, b) => b - a);
+  return (light + 0.05) / (dark + 0.05);
+};
+
+const hasInsufficientContrast = (element) => {
+  const hasText = Array.from(element.childNodes).some(
+    (node) => node.nodeType === Node.TEXT_NODE && node.textContent.trim() !== ""
+  );
+  if (!hasText || !isVisible(element) || element.disabled) return false;
+
+  const style = getComputedStyle(element);
+  const bg = background(element);
+  if (bg === null) return false;
+
+  const fg = blend(parseColor(style.color), bg);
+  const size = parseFloat(style.fontSize);
+  const large = size >= 24 || (size >= 18.66 && parseInt(style.fontWeight, 10) >= 700);
+
+  return contrast(fg, bg) < (large ? 3 : 4.5);
+};
+
+const rules = [
+  {
+    id: "doco-image-alt",
+    impact: "critical",
+    description: "Images must have alternative text",
+    check: () =>
+      all("img").filter(
+        (element) =>
+          !element.hasAttribute("alt") &&
+          !["presentation", "none"].includes(attribute(element, "role")) &&
+          accessibleName(element) === ""
+      ),
+  },
+  {
+    id: "doco-input-image-alt",
+    impact: "critical",
+    description: "Image buttons must have alternative text",
+    check: () => all("input[type='image']").filter((element) => accessibleName(element) === ""),
+  },
+  {
+    id: "doco-button-name",
+    impact: "critical",
+    description: "Buttons must have discernible text",
+    check: () =>
+      all("button, [role='button']").filter(
+        (element) => isVisible(element) && accessibleName(element) === ""
+      ),
+  },
+  {
+    id: "doco-link-name",
+    impact: "serious",
+    description: "Links must have discernible text",
+    check: () =>
+      all("a[href]").filter((element) => isVisible(element) && accessibleName(element) === ""),
+  },
+  {
+    id: "doco-label",
+    impact: "critical",
+    description: "Form elements must have labels",
+    check: () =>
+      all("input, select, textarea").filter(
+        (element) =>
+          !["hidden", "submit", "reset", "button", "image"].includes(
+            (element.getAttribute("type") || "").toLowerCase()
+          ) &&
+          isVisible(element) &&
+          accessibleName(element) === ""
+      ),
+  },
+  {
+    id: "doco-html-has-lang",
+    impact: "serious",
+    description: "The <html> element must have a lang attribute",
+    check: () =>
+      page && attribute(document.documentElement, "lang") === "" ? [document.documentElement] : [],
+  },
+  {
+    id: "doco-document-title",
+    impact: "serious",
+    description: "Documents must have a non-empty <title> element",
+    check: () => (page && document.title.trim() === "" ? [document.documentElement] : []),
+  },
+  {
+    id: "doco-duplicate-id",
+    impact: "minor",
+    description: "IDs of elements must be unique",
+    check: () =>
+      all("[id]").filter(
+        (element) =>
+          element.id !== "" && document.querySelectorAll(`#${CSS.escape(element.id)}`).length > 1
+      ),
+  },
+  {
+    id: "doco-empty-heading",
+    impact: "minor",
+    description: "Headings should not be empty",
+    check: () =>
+      all("h1, h2, h3, h4, h5, h6, [role='heading']").filter(
+        (element) => isVisible(element) && accessibleName(element) === ""
+      ),
+  },
+  {
+    id: "doco-heading-order",
+    impact: "moderate",
+    description: "Heading levels should only increase by one",
+    check: () => {
+      let previous = null;
+
+      return all("h1, h2, h3, h4, h5, h6, [role='heading']")
+        .filter(isVisible)
+        .filter((element) => {
+          const level = headingLevel(element);
+          const skipped = previous !== null && level > previous + 1;
+          previous = level;
+          return skipped;
+        });
+    },
+  },
+  {
+    id: "doco-frame-title",
+    impact: "serious",
+    description: "Frames must have an accessible name",
+    check: () =>
+      all("iframe, frame").filter(
+        (element) => attribute(element, "title") === "" && attribute(element, "aria-label") === ""
+      ),
+  },
+  {
+    id: "doco-color-contrast",
+    impact: "serious",
+    description: "Elements must meet minimum color contrast ratio thresholds",
+    check: () => all("*").filter(hasInsufficientContrast),
+  },
+];
+
+return rules
+  .filter((rule) => !disabled.includes(rule.id))
+  .map((rule) => ({
+    id: rule.id,
+    impact: rule.impact,
+    description: rule.description,
+    nodes: rule.check().map((element) => ({ target: selector(element), html: snippet(element) })),
+  }))
+  .filter((violation) => violation.nodes.length > 0);
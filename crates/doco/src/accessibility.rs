@@ -0,0 +1,352 @@
+//! Accessibility audits of pages and elements
+
+use std::fmt::{Display, Formatter};
+
+use anyhow::bail;
+use fantoccini::Locator;
+use getset::{CopyGetters, Getters};
+use serde::Deserialize;
+use serde_json::Value;
+use typed_builder::TypedBuilder;
+
+use crate::{Client, Result};
+
+/// Script that audits the page or a subtree and returns the violations
+const ENGINE: &str = include_str!("accessibility.js");
+
+/// Impact of an accessibility violation on users, from minor to critical
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Impact {
+    /// The violation is a nuisance, but does not prevent access to content
+    #[default]
+    Minor,
+
+    /// The violation makes it harder to access content
+    Moderate,
+
+    /// The violation makes it very hard to access content
+    Serious,
+
+    /// The violation blocks access to content
+    Critical,
+}
+
+impl Display for Impact {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let impact = match self {
+            Impact::Minor => "minor",
+            Impact::Moderate => "moderate",
+            Impact::Serious => "serious",
+            Impact::Critical => "critical",
+        };
+
+        write!(f, "{impact}")
+    }
+}
+
+/// Element that violates an accessibility rule
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Getters)]
+pub struct ViolationNode {
+    /// A CSS selector that identifies the element, e.g. `#login > button`
+    #[getset(get = "pub")]
+    target: String,
+
+    /// The opening tag of the element, e.g. `<button class="icon">`
+    #[getset(get = "pub")]
+    html: String,
+}
+
+/// Accessibility rule that is violated by one or more elements
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, CopyGetters, Getters)]
+pub struct Violation {
+    /// The id of the rule, e.g. `doco-image-alt`
+    #[serde(rename = "id")]
+    #[getset(get = "pub")]
+    rule: String,
+
+    /// The impact of the violation on users
+    #[getset(get_copy = "pub")]
+    impact: Impact,
+
+    /// A description of the rule, e.g. `Images must have alternative text`
+    #[getset(get = "pub")]
+    description: String,
+
+    /// The elements that violate the rule
+    #[getset(get = "pub")]
+    nodes: Vec<ViolationNode>,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} ({}): {}", self.rule, self.impact, self.description)?;
+
+        for node in &self.nodes {
+            writeln!(f, "  - {}: {}", node.target, node.html)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Options for an accessibility audit
+///
+/// Audits check the page against a set of rules that detect common accessibility problems, like
+/// images without alternative text, form fields without labels, or text with too little contrast.
+/// Rules can be disabled entirely, and known violations can be allowed for single elements until
+/// they have been fixed. Violations with an impact below `min_impact` are ignored.
+///
+/// # Example
+///
+/// ```no_run
+/// use doco::{AuditOptions, Client, Impact, Result};
+///
+/// async fn home_is_accessible(client: Client) -> Result<()> {
+///     client.goto("/").await?;
+///
+///     let options = AuditOptions::builder()
+///         .disable_rule("doco-heading-order")
+///         .allow("doco-image-alt", "#legacy-banner")
+///         .min_impact(Impact::Serious)
+///         .build();
+///
+///     client.assert_accessible(&options).await
+/// }
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug, CopyGetters, Getters, TypedBuilder)]
+pub struct AuditOptions {
+    /// The rules that are not checked, e.g. `doco-color-contrast`
+    #[builder(via_mutators(init = Vec::new()), mutators(
+        pub fn disable_rule(mut self, rule: impl Into<String>) {
+            self.disabled_rules.push(rule.into());
+        }
+    ))]
+    #[getset(get = "pub")]
+    disabled_rules: Vec<String>,
+
+    /// The violations that are allowed, as pairs of a rule and the target of an element
+    #[builder(via_mutators(init = Vec::new()), mutators(
+        pub fn allow(mut self, rule: impl Into<String>, target: impl Into<String>) {
+            self.allowed.push((rule.into(), target.into()));
+        }
+    ))]
+    #[getset(get = "pub")]
+    allowed: Vec<(String, String)>,
+
+    /// The lowest impact of violations that are reported, e.g. `Impact::Minor`
+    #[builder(default)]
+    #[getset(get_copy = "pub")]
+    min_impact: Impact,
+}
+
+impl AuditOptions {
+    /// Remove the violations that these options ignore or allow
+    fn filter(&self, violations: Vec<Violation>) -> Vec<Violation> {
+        violations
+            .into_iter()
+            .filter(|violation| violation.impact >= self.min_impact)
+            .filter_map(|mut violation| {
+                violation.nodes.retain(|node| {
+                    !self
+                        .allowed
+                        .iter()
+                        .any(|(rule, target)| *rule == violation.rule && *target == node.target)
+                });
+
+                (!violation.nodes.is_empty()).then_some(violation)
+            })
+            .collect()
+    }
+}
+
+impl Default for AuditOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl Client {
+    /// Audit the current page for accessibility problems and return the violations
+    ///
+    /// Doco injects a rules engine into the page that checks for common problems like missing
+    /// alternative texts, unlabeled form fields and buttons, a missing language or title, skipped
+    /// heading levels, duplicate ids, and insufficient color contrast. The rules approximate a
+    /// subset of the rules of [axe-core], and their ids carry a `doco-` prefix to tell them apart,
+    /// e.g. `doco-image-alt` for `image-alt`.
+    ///
+    /// [axe-core]: https://github.com/dequelabs/axe-core/blob/develop/doc/rule-descriptions.md
+    pub async fn audit_accessibility(&self, options: &AuditOptions) -> Result<Vec<Violation>> {
+        self.run_audit(Value::Null, options).await
+    }
+
+    /// Audit the subtree of an element for accessibility problems and return the violations
+    ///
+    /// The element is waited for like with [`Client::wait_for_element`]. Rules that apply to the
+    /// whole document, like the language and title of the page, are skipped.
+    pub async fn audit_element_accessibility<'l>(
+        &self,
+        locator: impl Into<Locator<'l>>,
+        options: &AuditOptions,
+    ) -> Result<Vec<Violation>> {
+        let element = self.wait_for_element(locator).await?;
+        self.run_audit(serde_json::to_value(&element)?, options)
+            .await
+    }
+
+    /// Assert that the current page has no accessibility violations
+    ///
+    /// The assertion fails with a report that lists each violated rule and the elements that
+    /// violate it.
+    pub async fn assert_accessible(&self, options: &AuditOptions) -> Result<()> {
+        report(&self.audit_accessibility(options).await?)
+    }
+
+    /// Assert that the subtree of an element has no accessibility violations
+    pub async fn assert_element_accessible<'l>(
+        &self,
+        locator: impl Into<Locator<'l>>,
+        options: &AuditOptions,
+    ) -> Result<()> {
+        report(&self.audit_element_accessibility(locator, options).await?)
+    }
+
+    /// Run the rules engine on the given root element, or on the whole page for `null`
+    async fn run_audit(&self, root: Value, options: &AuditOptions) -> Result<Vec<Violation>> {
+        let disabled = serde_json::to_value(options.disabled_rules())?;
+        let violations = self.execute(ENGINE, vec![root, disabled]).await?;
+
+        Ok(options.filter(serde_json::from_value(violations)?))
+    }
+}
+
+/// Fail with a readable report if there are any violations
+fn report(violations: &[Violation]) -> Result<()> {
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    let elements: usize = violations
+        .iter()
+        .map(|violation| violation.nodes.len())
+        .sum();
+    let mut report = format!(
+        "found {} accessibility violations in {elements} elements\n",
+        violations.len()
+    );
+
+    for violation in violations {
+        report.push('\n');
+        report.push_str(&violation.to_string());
+    }
+
+    bail!(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::test_utils::*;
+
+    use super::*;
+
+    fn violations() -> Vec<Violation> {
+        serde_json::from_value(json!([
+            {
+                "id": "doco-image-alt",
+                "impact": "critical",
+                "description": "Images must have alternative text",
+                "nodes": [
+                    { "target": "#logo", "html": "<img id=\"logo\" src=\"logo.png\">" },
+                    { "target": "main > img", "html": "<img src=\"hero.png\">" }
+                ]
+            },
+            {
+                "id": "doco-duplicate-id",
+                "impact": "minor",
+                "description": "IDs of elements must be unique",
+                "nodes": [{ "target": "div:nth-of-type(2)", "html": "<div id=\"a\">" }]
+            }
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn filter_keeps_everything_by_default() {
+        assert_eq!(violations(), AuditOptions::default().filter(violations()));
+    }
+
+    #[test]
+    fn filter_removes_allowed_nodes() {
+        let options = AuditOptions::builder()
+            .allow("doco-image-alt", "#logo")
+            .build();
+
+        let violations = options.filter(violations());
+
+        assert_eq!(1, violations[0].nodes().len());
+        assert_eq!("main > img", violations[0].nodes()[0].target());
+    }
+
+    #[test]
+    fn filter_removes_violations_without_nodes() {
+        let options = AuditOptions::builder()
+            .allow("doco-duplicate-id", "div:nth-of-type(2)")
+            .build();
+
+        let violations = options.filter(violations());
+
+        assert_eq!(1, violations.len());
+        assert_eq!("doco-image-alt", violations[0].rule());
+    }
+
+    #[test]
+    fn filter_removes_violations_below_min_impact() {
+        let options = AuditOptions::builder().min_impact(Impact::Serious).build();
+
+        let violations = options.filter(violations());
+
+        assert_eq!(1, violations.len());
+        assert_eq!(Impact::Critical, violations[0].impact());
+    }
+
+    #[test]
+    fn report_without_violations() {
+        assert!(report(&[]).is_ok());
+    }
+
+    #[test]
+    fn report_lists_violations() {
+        let error = report(&violations()).unwrap_err().to_string();
+
+        assert!(error.starts_with("found 2 accessibility violations in 3 elements\n"));
+        assert!(error.contains(
+            "doco-image-alt (critical): Images must have alternative text\n  - #logo: <img id=\"logo\" src=\"logo.png\">\n"
+        ));
+    }
+
+    #[test]
+    fn impact_is_ordered() {
+        assert!(Impact::Critical > Impact::Serious);
+        assert!(Impact::Moderate > Impact::Minor);
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<AuditOptions>();
+        assert_send::<Violation>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<AuditOptions>();
+        assert_sync::<Violation>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<AuditOptions>();
+        assert_unpin::<Violation>();
+    }
+}
@@ -0,0 +1,288 @@
+//! Semantic locators that find elements the way users see them
+
+use fantoccini::Locator;
+
+/// The attribute that identifies elements for tests, unless configured otherwise
+pub(crate) const DEFAULT_TEST_ID_ATTRIBUTE: &str = "data-testid";
+
+/// Elements that are matched by their role, e.g. `button` for `<button>` and `role="button"`
+///
+/// Each role is mapped to the HTML elements that have the role implicitly. Roles that are not
+/// listed here only match elements with an explicit `role` attribute.
+const IMPLICIT_ROLES: &[(&str, &str)] = &[
+    (
+        "button",
+        "self::button or (self::input and (@type='button' or @type='submit' or @type='reset'))",
+    ),
+    ("checkbox", "self::input and @type='checkbox'"),
+    ("combobox", "self::select and not(@multiple)"),
+    (
+        "heading",
+        "self::h1 or self::h2 or self::h3 or self::h4 or self::h5 or self::h6",
+    ),
+    ("img", "self::img"),
+    ("link", "self::a and @href"),
+    ("list", "self::ul or self::ol"),
+    ("listbox", "self::select and @multiple"),
+    ("listitem", "self::li"),
+    ("main", "self::main"),
+    ("navigation", "self::nav"),
+    ("radio", "self::input and @type='radio'"),
+    ("table", "self::table"),
+    (
+        "textbox",
+        "self::textarea or (self::input and (not(@type) or @type='text' or @type='email' \
+         or @type='tel' or @type='url' or @type='password'))",
+    ),
+];
+
+/// Semantic locator that finds elements the way users see them
+///
+/// Locators based on the structure of the DOM, e.g. `/html/body/main/button`, break whenever the
+/// layout of the page changes. `By` finds elements by what users see and interact with instead: a
+/// dedicated test id, their visible text, their ARIA role and accessible name, their label, or
+/// their placeholder.
+///
+/// Each locator is translated into an XPath expression and can be used with every method that
+/// accepts a [`Locator`], either by passing a reference to it or by calling [`By::locator`].
+///
+/// # Example
+///
+/// ```no_run
+/// use doco::{By, Client, Result};
+///
+/// async fn signs_in(client: Client) -> Result<()> {
+///     client.goto("/login").await?;
+///
+///     client.find(By::label("Email").locator()).await?.send_keys("user@example.com").await?;
+///     client.find(By::placeholder("Password").locator()).await?.send_keys("secret").await?;
+///     client.find(By::role("button").name("Sign in").locator()).await?.click().await?;
+///
+///     client.wait_for_visible(&By::test_id("dashboard")).await?;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct By {
+    /// The XPath expression that the locator has been translated to
+    xpath: String,
+}
+
+impl By {
+    /// Find elements by their test id in the `data-testid` attribute
+    ///
+    /// Use [`Client::test_id`](crate::Client::test_id) to find elements by the attribute that has
+    /// been configured with [`Doco::test_id_attribute`](crate::Doco::test_id_attribute).
+    pub fn test_id(id: &str) -> Self {
+        Self::test_id_in(DEFAULT_TEST_ID_ATTRIBUTE, id)
+    }
+
+    /// Find elements by their test id in the given attribute
+    pub fn test_id_in(attribute: &str, id: &str) -> Self {
+        Self::new(format!("//*[@{attribute}={}]", literal(id)))
+    }
+
+    /// Find the innermost elements whose visible text is exactly the given text
+    ///
+    /// Whitespace at the start and end of the text is ignored, and runs of whitespace are treated
+    /// as a single space.
+    pub fn text(text: &str) -> Self {
+        let condition = format!("normalize-space(.)={}", literal(text));
+        Self::innermost(&condition)
+    }
+
+    /// Find the innermost elements whose visible text contains the given text
+    pub fn text_containing(text: &str) -> Self {
+        let condition = format!("contains(normalize-space(.), {})", literal(text));
+        Self::innermost(&condition)
+    }
+
+    /// Find elements by their ARIA role
+    ///
+    /// Elements match if they have the role explicitly in their `role` attribute, or implicitly
+    /// like `<button>` for the `button` role. Use [`By::name`] to narrow the locator down to
+    /// elements with a certain accessible name.
+    pub fn role(role: &str) -> Self {
+        let explicit = format!("@role={}", literal(role));
+
+        let condition = match IMPLICIT_ROLES.iter().find(|(name, _)| *name == role) {
+            Some((_, implicit)) => format!("{explicit} or (not(@role) and ({implicit}))"),
+            None => explicit,
+        };
+
+        Self::new(format!("//*[{condition}]"))
+    }
+
+    /// Narrow the locator down to elements with the given accessible name
+    ///
+    /// The accessible name is approximated by the element's `aria-label`, the text of the element
+    /// referenced by `aria-labelledby`, its visible text, its `value` for buttons in forms, its
+    /// `alt` text for images, and its `title`.
+    pub fn name(self, name: &str) -> Self {
+        let name = literal(name);
+
+        Self::new(format!(
+            "({})[@aria-label={name} or @aria-labelledby=//*[normalize-space(.)={name}]/@id \
+             or normalize-space(.)={name} or @value={name} or @alt={name} or @title={name}]",
+            self.xpath
+        ))
+    }
+
+    /// Find form fields by the text of their label
+    ///
+    /// Fields match if they are referenced by the `for` attribute of a `<label>` with the text, if
+    /// they are nested inside such a label, or if their `aria-label` has the text.
+    pub fn label(text: &str) -> Self {
+        let label = format!("//label[normalize-space(.)={}]", literal(text));
+
+        Self::new(format!(
+            "//*[@id={label}/@for] | {label}//*[self::input or self::select or self::textarea] \
+             | //*[@aria-label={}]",
+            literal(text)
+        ))
+    }
+
+    /// Find form fields by their placeholder
+    pub fn placeholder(text: &str) -> Self {
+        Self::new(format!("//*[@placeholder={}]", literal(text)))
+    }
+
+    /// Return the locator that can be passed to the methods of the [`Client`](crate::Client)
+    pub fn locator(&self) -> Locator<'_> {
+        Locator::XPath(&self.xpath)
+    }
+
+    /// Return the XPath expression that the locator has been translated to
+    pub fn xpath(&self) -> &str {
+        &self.xpath
+    }
+
+    /// Create a new locator from an XPath expression
+    fn new(xpath: String) -> Self {
+        Self { xpath }
+    }
+
+    /// Find the innermost elements that match the condition
+    ///
+    /// Text conditions also match every ancestor of an element, e.g. `<body>`. Only elements
+    /// without a descendant that matches the condition are selected to avoid this.
+    fn innermost(condition: &str) -> Self {
+        Self::new(format!(
+            "//body//*[not(self::script or self::style) and {condition} and not(.//*[{condition}])]"
+        ))
+    }
+}
+
+impl<'a> From<&'a By> for Locator<'a> {
+    fn from(by: &'a By) -> Self {
+        by.locator()
+    }
+}
+
+/// Quote a string as an XPath literal
+///
+/// XPath 1.0 has no escape sequences, so strings with both single and double quotes are split into
+/// parts and joined with `concat()`.
+pub(crate) fn literal(value: &str) -> String {
+    if !value.contains('\'') {
+        format!("'{value}'")
+    } else if !value.contains('"') {
+        format!("\"{value}\"")
+    } else {
+        let parts = value
+            .split('\'')
+            .map(|part| format!("'{part}'"))
+            .collect::<Vec<_>>()
+            .join(", \"'\", ");
+
+        format!("concat({parts})")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    use super::*;
+
+    #[test]
+    fn literal_without_quotes() {
+        assert_eq!("'Save'", literal("Save"));
+    }
+
+    #[test]
+    fn literal_with_single_quote() {
+        assert_eq!("\"Don't\"", literal("Don't"));
+    }
+
+    #[test]
+    fn literal_with_both_quotes() {
+        assert_eq!(
+            "concat('Say \"hi\" and don', \"'\", 't')",
+            literal("Say \"hi\" and don't")
+        );
+    }
+
+    #[test]
+    fn test_id_in_default_attribute() {
+        assert_eq!("//*[@data-testid='save']", By::test_id("save").xpath());
+    }
+
+    #[test]
+    fn test_id_in_attribute() {
+        assert_eq!(
+            "//*[@data-qa='save']",
+            By::test_id_in("data-qa", "save").xpath()
+        );
+    }
+
+    #[test]
+    fn placeholder() {
+        assert_eq!(
+            "//*[@placeholder='Search']",
+            By::placeholder("Search").xpath()
+        );
+    }
+
+    #[test]
+    fn role_with_implicit_elements() {
+        let xpath = By::role("heading").xpath().to_string();
+
+        assert!(xpath.starts_with("//*[@role='heading' or (not(@role) and (self::h1"));
+    }
+
+    #[test]
+    fn role_without_implicit_elements() {
+        assert_eq!("//*[@role='tab']", By::role("tab").xpath());
+    }
+
+    #[test]
+    fn role_with_name() {
+        let xpath = By::role("tab").name("Settings").xpath().to_string();
+
+        assert!(xpath.starts_with("(//*[@role='tab'])[@aria-label='Settings'"));
+    }
+
+    #[test]
+    fn locator_is_xpath() {
+        let by = By::placeholder("Search");
+
+        assert_eq!(Locator::XPath(by.xpath()), Locator::from(&by));
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<By>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<By>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<By>();
+    }
+}
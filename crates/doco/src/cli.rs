@@ -0,0 +1,259 @@
+//! Command-line arguments of the test binary
+
+use getset::{CopyGetters, Getters};
+
+/// The environment variable that enables `--update-snapshots`, e.g. `DOCO_UPDATE_SNAPSHOTS=1`
+const UPDATE_SNAPSHOTS_ENV: &str = "DOCO_UPDATE_SNAPSHOTS";
+
+/// The environment variable that sets the target of `--events`, e.g. `DOCO_EVENTS=events.ndjson`
+const EVENTS_ENV: &str = "DOCO_EVENTS";
+
+/// The flags of libtest that take a value, which must not be mistaken for a filter
+const VALUE_FLAGS: [&str; 5] = ["--test-threads", "--format", "--color", "--logfile", "-Z"];
+
+/// Command-line arguments of the test binary
+///
+/// The binary that is generated by [`doco::main`](crate::main) accepts a few flags that change how
+/// the tests are run. Arguments that Doco does not know are ignored, since `cargo test` passes its
+/// own arguments to test binaries. Some flags can also be set with an environment variable, which
+/// is easier in CI pipelines.
+///
+/// Arguments that are not flags filter the tests by name, e.g. `cargo test -- login` runs only the
+/// tests whose name contains `login`, including all cases of a parameterized test. Like libtest,
+/// `--skip <filter>` excludes the tests that match a filter, and `--exact` matches the whole name
+/// instead of a part of it. The values of the other libtest flags that take one, for example
+/// `--test-threads 1`, are ignored.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, CopyGetters, Getters)]
+pub(crate) struct Cli {
+    /// Whether snapshots rewrite their baselines instead of comparing against them
+    #[getset(get_copy = "pub(crate)")]
+    update_snapshots: bool,
+
+    /// The file to which the event stream is written, or `-` for stdout
+    #[getset(get = "pub(crate)")]
+    events: Option<String>,
+
+    /// The filters that select the tests to run, or none to run all tests
+    #[getset(get = "pub(crate)")]
+    filters: Vec<String>,
+
+    /// The filters that exclude tests from the run
+    #[getset(get = "pub(crate)")]
+    skips: Vec<String>,
+
+    /// Whether the filters must match the whole name of a test instead of a part of it
+    #[getset(get_copy = "pub(crate)")]
+    exact: bool,
+}
+
+impl Cli {
+    /// Parse the arguments of the current process
+    pub(crate) fn parse() -> Self {
+        let mut cli = Self::parse_from(std::env::args().skip(1));
+
+        if let Ok(value) = std::env::var(UPDATE_SNAPSHOTS_ENV) {
+            cli.update_snapshots |= is_enabled(&value);
+        }
+
+        if cli.events.is_none() {
+            cli.events = std::env::var(EVENTS_ENV)
+                .ok()
+                .filter(|value| !value.is_empty());
+        }
+
+        cli
+    }
+
+    /// Parse the given arguments
+    pub(crate) fn parse_from(args: impl IntoIterator<Item = String>) -> Self {
+        let mut cli = Self::default();
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if arg == "--update-snapshots" {
+                cli.update_snapshots = true;
+            } else if arg == "--events" {
+                cli.events = args.next();
+            } else if let Some(target) = arg.strip_prefix("--events=") {
+                cli.events = Some(target.to_string());
+            } else if arg == "--skip" {
+                cli.skips.extend(args.next());
+            } else if let Some(skip) = arg.strip_prefix("--skip=") {
+                cli.skips.push(skip.to_string());
+            } else if arg == "--exact" {
+                cli.exact = true;
+            } else if VALUE_FLAGS.contains(&arg.as_str()) {
+                args.next();
+            } else if !arg.starts_with('-') {
+                cli.filters.push(arg);
+            }
+        }
+
+        cli
+    }
+
+    /// Check if the test with the given name matches any of the filters and none of the skips
+    pub(crate) fn matches(&self, name: &str) -> bool {
+        let matches = |filter: &String| {
+            if self.exact {
+                name == filter
+            } else {
+                name.contains(filter.as_str())
+            }
+        };
+
+        (self.filters.is_empty() || self.filters.iter().any(matches))
+            && !self.skips.iter().any(matches)
+    }
+}
+
+/// Check if the value of an environment variable enables a flag
+fn is_enabled(value: &str) -> bool {
+    !matches!(
+        value.trim().to_lowercase().as_str(),
+        "" | "0" | "false" | "no"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_without_arguments() {
+        assert_eq!(Cli::default(), Cli::parse_from(args(&[])));
+    }
+
+    #[test]
+    fn parse_update_snapshots() {
+        let cli = Cli::parse_from(args(&["--update-snapshots"]));
+
+        assert!(cli.update_snapshots());
+    }
+
+    #[test]
+    fn parse_ignores_unknown_arguments() {
+        let cli = Cli::parse_from(args(&["--nocapture", "--update-snapshots"]));
+
+        assert!(cli.update_snapshots());
+    }
+
+    #[test]
+    fn parse_events() {
+        let cli = Cli::parse_from(args(&["--events", "events.ndjson", "login"]));
+
+        assert_eq!(&Some(String::from("events.ndjson")), cli.events());
+        assert_eq!(&vec![String::from("login")], cli.filters());
+    }
+
+    #[test]
+    fn parse_events_with_equals_sign() {
+        let cli = Cli::parse_from(args(&["--events=-"]));
+
+        assert_eq!(&Some(String::from("-")), cli.events());
+    }
+
+    #[test]
+    fn parse_filters() {
+        let cli = Cli::parse_from(args(&["login", "--nocapture", "logout"]));
+
+        assert_eq!(
+            &vec![String::from("login"), String::from("logout")],
+            cli.filters()
+        );
+    }
+
+    #[test]
+    fn parse_ignores_values_of_libtest_flags() {
+        let cli = Cli::parse_from(args(&[
+            "--test-threads",
+            "1",
+            "--format",
+            "terse",
+            "--color",
+            "never",
+            "--logfile",
+            "out.log",
+            "-Z",
+            "unstable-options",
+            "login",
+        ]));
+
+        assert_eq!(&vec![String::from("login")], cli.filters());
+    }
+
+    #[test]
+    fn parse_skip() {
+        let cli = Cli::parse_from(args(&["--skip", "login", "--skip=logout"]));
+
+        assert!(cli.filters().is_empty());
+        assert_eq!(
+            &vec![String::from("login"), String::from("logout")],
+            cli.skips()
+        );
+    }
+
+    #[test]
+    fn matches_all_tests_without_filters() {
+        assert!(Cli::default().matches("login[admin]"));
+    }
+
+    #[test]
+    fn matches_tests_that_contain_a_filter() {
+        let cli = Cli::parse_from(args(&["login[admin]"]));
+
+        assert!(cli.matches("login[admin]"));
+        assert!(!cli.matches("login[guest]"));
+    }
+
+    #[test]
+    fn matches_no_tests_that_are_skipped() {
+        let cli = Cli::parse_from(args(&["auth", "--skip", "login"]));
+
+        assert!(cli.matches("auth::logout"));
+        assert!(!cli.matches("auth::login"));
+        assert!(!cli.matches("checkout"));
+    }
+
+    #[test]
+    fn matches_exact_names() {
+        let cli = Cli::parse_from(args(&["--exact", "auth::login"]));
+
+        assert!(cli.matches("auth::login"));
+        assert!(!cli.matches("auth::login_twice"));
+    }
+
+    #[test]
+    fn is_enabled_by_truthy_values() {
+        assert!(is_enabled("1"));
+        assert!(is_enabled("true"));
+    }
+
+    #[test]
+    fn is_enabled_not_by_falsy_values() {
+        assert!(!is_enabled(""));
+        assert!(!is_enabled("0"));
+        assert!(!is_enabled("false"));
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<Cli>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<Cli>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<Cli>();
+    }
+}
@@ -1,11 +1,19 @@
 //! WebDriver client that interacts with the web application
 
+use anyhow::anyhow;
 use fantoccini::error::CmdError;
 use fantoccini::Client as WebDriverClient;
+use getset::{CopyGetters, Getters};
 use reqwest::Url;
 use std::ops::Deref;
+use std::time::Duration;
 use typed_builder::TypedBuilder;
 
+use crate::by::{By, DEFAULT_TEST_ID_ATTRIBUTE};
+use crate::console::{Console, ConsoleMessage};
+use crate::snapshot::Snapshots;
+use crate::wait::DEFAULT_TIMEOUT;
+
 /// WebDriver client that interacts with the web application
 ///
 /// The `Client` implements the [WebDriver] protocol to interact with the web application. It is
@@ -48,13 +56,33 @@ use typed_builder::TypedBuilder;
 ///
 /// [fantoccini]: https://crates.io/crates/fantoccini
 /// [webdriver]: https://developer.mozilla.org/en-US/docs/Web/WebDriver
-#[derive(Clone, Debug, TypedBuilder)]
+#[derive(Clone, Debug, CopyGetters, Getters, TypedBuilder)]
 pub struct Client {
     /// The base URL of the server
+    #[getset(get = "pub")]
     base_url: Url,
 
     /// The WebDriver client that is used internally
     client: WebDriverClient,
+
+    /// The collector for messages in the browser console
+    #[builder(default)]
+    console: Console,
+
+    /// The time that locators wait for an element before they fail
+    #[builder(default = DEFAULT_TIMEOUT)]
+    #[getset(get_copy = "pub")]
+    timeout: Duration,
+
+    /// The attribute that identifies elements for [`Client::test_id`]
+    #[builder(default = String::from(DEFAULT_TEST_ID_ATTRIBUTE), setter(into))]
+    #[getset(get = "pub")]
+    test_id_attribute: String,
+
+    /// The storage for the snapshots of the current test
+    #[builder(default)]
+    #[getset(get = "pub(crate)")]
+    snapshots: Snapshots,
 }
 
 impl Client {
@@ -77,7 +105,135 @@ impl Client {
     /// }
     /// ```
     pub async fn goto(&self, path: &str) -> Result<(), CmdError> {
-        self.client.goto(self.base_url.join(path)?.as_str()).await
+        self.console.before_navigation(&self.client).await?;
+        self.client.goto(self.base_url.join(path)?.as_str()).await?;
+        self.console.after_navigation(&self.client).await
+    }
+
+    /// Return a copy of the client that waits for the given timeout
+    ///
+    /// The waiting locators like [`Client::wait_for_element`] use the timeout that has been
+    /// configured for the test suite. This method can be used to override it for a single call.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use doco::{Client, Locator, Result};
+    ///
+    /// async fn loads_report(client: Client) -> Result<()> {
+    ///     client.goto("/report").await?;
+    ///
+    ///     client
+    ///         .with_timeout(Duration::from_secs(30))
+    ///         .wait_for_visible(Locator::Id("report"))
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        Self {
+            timeout,
+            ..self.clone()
+        }
+    }
+
+    /// Find elements by their test id in the attribute that has been configured for the suite
+    ///
+    /// The attribute is set with [`Doco::test_id_attribute`](crate::Doco::test_id_attribute) and
+    /// defaults to `data-testid`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use doco::{Client, Result};
+    ///
+    /// async fn shows_dashboard(client: Client) -> Result<()> {
+    ///     client.goto("/").await?;
+    ///
+    ///     client.wait_for_visible(&client.test_id("dashboard")).await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn test_id(&self, id: &str) -> By {
+        By::test_id_in(&self.test_id_attribute, id)
+    }
+
+    /// Return the messages that have been logged to the browser console
+    ///
+    /// This method returns all console messages and uncaught JavaScript exceptions since the start
+    /// of the test. Doco uses [WebDriver BiDi] to capture the console when the browser supports it.
+    /// Otherwise, it injects a script into each page that is opened with [`Client::goto`], which
+    /// cannot capture messages that are logged while the page is loading.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use doco::{Client, Result};
+    ///
+    /// async fn logs_greeting(client: Client) -> Result<()> {
+    ///     client.goto("/").await?;
+    ///
+    ///     let messages = client.console_messages().await?;
+    ///
+    ///     assert!(messages.iter().any(|message| message.text() == "Hello World"));
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// [WebDriver BiDi]: https://w3c.github.io/webdriver-bidi/
+    pub async fn console_messages(&self) -> Result<Vec<ConsoleMessage>, CmdError> {
+        self.console.messages(&self.client).await
+    }
+
+    /// Return the errors that have been logged to the browser console
+    ///
+    /// This method returns the subset of [`Client::console_messages`] with the error level, which
+    /// includes uncaught JavaScript exceptions.
+    pub async fn console_errors(&self) -> Result<Vec<ConsoleMessage>, CmdError> {
+        let messages = self.console_messages().await?;
+
+        Ok(messages
+            .into_iter()
+            .filter(ConsoleMessage::is_error)
+            .collect())
+    }
+
+    /// Assert that no errors have been logged to the browser console
+    ///
+    /// This method returns an error that lists all console errors and uncaught exceptions if there
+    /// are any. Use [`Doco::fail_on_console_errors`](crate::Doco::fail_on_console_errors) to run
+    /// this assertion automatically after every test.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use doco::{Client, Result};
+    ///
+    /// async fn renders_without_errors(client: Client) -> Result<()> {
+    ///     client.goto("/").await?;
+    ///
+    ///     client.assert_no_console_errors().await
+    /// }
+    /// ```
+    pub async fn assert_no_console_errors(&self) -> crate::Result<()> {
+        let errors = self.console_errors().await?;
+
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        let errors = errors
+            .iter()
+            .map(|error| format!("  {error}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Err(anyhow!("browser console contains errors:\n{errors}"))
     }
 }
 
@@ -0,0 +1,452 @@
+//! Messages that the web application logs to the browser console
+
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use fantoccini::error::CmdError;
+use fantoccini::Client as WebDriverClient;
+use futures_util::{SinkExt, StreamExt};
+use getset::{CopyGetters, Getters};
+use reqwest::Url;
+use serde_json::{json, Value};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::Result;
+
+/// The id of the command that subscribes to log events with WebDriver BiDi
+const SUBSCRIBE_ID: u64 = 1;
+
+/// The time to wait for the browser to acknowledge the subscription to log events
+const SUBSCRIBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Script that records console messages and uncaught exceptions in the page
+///
+/// The script is used as a fallback when the browser does not support WebDriver BiDi. It wraps the
+/// methods of the `console` object and listens for uncaught errors and unhandled promise
+/// rejections. The messages are buffered in the page until Doco drains them.
+const SHIM: &str = r#"
+if (!window.__doco_console) {
+    window.__doco_console = [];
+
+    const text = (args) => args.map((arg) => {
+        if (arg instanceof Error) return arg.toString();
+        if (typeof arg === "object") {
+            try { return JSON.stringify(arg); } catch (_) { return String(arg); }
+        }
+        return String(arg);
+    }).join(" ");
+
+    for (const level of ["debug", "info", "log", "warn", "error"]) {
+        const original = console[level].bind(console);
+        console[level] = (...args) => {
+            window.__doco_console.push({ kind: "console", level: level, text: text(args) });
+            original(...args);
+        };
+    }
+
+    window.addEventListener("error", (event) => {
+        window.__doco_console.push({ kind: "exception", level: "error", text: event.message });
+    });
+    window.addEventListener("unhandledrejection", (event) => {
+        window.__doco_console.push({ kind: "exception", level: "error", text: text([event.reason]) });
+    });
+}
+"#;
+
+/// Script that drains the messages that the shim has buffered in the page
+const DRAIN: &str = r#"return window.__doco_console ? window.__doco_console.splice(0) : [];"#;
+
+/// Severity of a console message
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum ConsoleLevel {
+    /// Debug output, e.g. from `console.debug`
+    Debug,
+
+    /// Informational output, e.g. from `console.log` or `console.info`
+    Info,
+
+    /// Warnings, e.g. from `console.warn`
+    Warning,
+
+    /// Errors, e.g. from `console.error` or uncaught exceptions
+    Error,
+}
+
+impl ConsoleLevel {
+    /// Parse the level that the browser or the shim reports for a message
+    fn parse(level: &str) -> Self {
+        match level {
+            "debug" | "trace" => ConsoleLevel::Debug,
+            "warn" | "warning" => ConsoleLevel::Warning,
+            "error" => ConsoleLevel::Error,
+            _ => ConsoleLevel::Info,
+        }
+    }
+}
+
+/// Origin of a console message
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum ConsoleMessageKind {
+    /// A message that the web application has logged with the `console` API
+    Console,
+
+    /// An uncaught JavaScript exception or an unhandled promise rejection
+    Exception,
+}
+
+/// Message that has been logged to the browser console
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, CopyGetters, Getters)]
+pub struct ConsoleMessage {
+    /// Whether the message was logged or raised as an exception
+    #[getset(get_copy = "pub")]
+    kind: ConsoleMessageKind,
+
+    /// The severity of the message
+    #[getset(get_copy = "pub")]
+    level: ConsoleLevel,
+
+    /// The text of the message
+    #[getset(get = "pub")]
+    text: String,
+}
+
+impl ConsoleMessage {
+    /// Parse a message from the JSON object that the shim records
+    fn from_shim(value: &Value) -> Option<Self> {
+        let kind = match value.get("kind")?.as_str()? {
+            "exception" => ConsoleMessageKind::Exception,
+            _ => ConsoleMessageKind::Console,
+        };
+
+        Some(Self {
+            kind,
+            level: ConsoleLevel::parse(value.get("level")?.as_str()?),
+            text: value.get("text")?.as_str()?.to_string(),
+        })
+    }
+
+    /// Parse a message from the parameters of a `log.entryAdded` event in WebDriver BiDi
+    fn from_bidi(params: &Value) -> Option<Self> {
+        let kind = match params.get("type")?.as_str()? {
+            "javascript" => ConsoleMessageKind::Exception,
+            _ => ConsoleMessageKind::Console,
+        };
+
+        Some(Self {
+            kind,
+            level: ConsoleLevel::parse(params.get("level")?.as_str()?),
+            text: params.get("text")?.as_str().unwrap_or_default().to_string(),
+        })
+    }
+
+    /// Check if the message is an error
+    pub fn is_error(&self) -> bool {
+        self.level == ConsoleLevel::Error
+    }
+}
+
+impl Display for ConsoleMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let level = match (self.kind, self.level) {
+            (ConsoleMessageKind::Exception, _) => "exception",
+            (_, ConsoleLevel::Debug) => "debug",
+            (_, ConsoleLevel::Info) => "info",
+            (_, ConsoleLevel::Warning) => "warning",
+            (_, ConsoleLevel::Error) => "error",
+        };
+
+        write!(f, "[{level}] {}", self.text)
+    }
+}
+
+/// Collector for the messages in the browser console
+///
+/// The console is captured with [WebDriver BiDi] when the browser supports it. Doco subscribes to
+/// log events and records every message and uncaught exception in the background. Otherwise, Doco
+/// falls back to a script that it injects into every page that is opened with [`Client::goto`].
+/// The script can only capture messages that are logged after the page has loaded and until the
+/// browser navigates away from the page without going through [`Client::goto`].
+///
+/// [`Client::goto`]: crate::Client::goto
+/// [WebDriver BiDi]: https://w3c.github.io/webdriver-bidi/
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Console {
+    /// The messages that have been collected so far
+    messages: Arc<Mutex<Vec<ConsoleMessage>>>,
+
+    /// Whether the messages are collected with WebDriver BiDi instead of the injected script
+    bidi: bool,
+}
+
+impl Console {
+    /// Capture the console of the given WebDriver session
+    ///
+    /// This method subscribes to log events with WebDriver BiDi. Selenium reports its WebSocket URL
+    /// with the host and port inside its container, which is why the publicly reachable
+    /// `webdriver` URL must be passed.
+    ///
+    /// If the browser does not support WebDriver BiDi, the caller falls back to the injected script
+    /// of the [default](Console::default) console and warns about it, since the script misses
+    /// messages that are logged while a page is loading.
+    pub(crate) async fn capture(client: &WebDriverClient, webdriver: &Url) -> Result<Self> {
+        let mut url: Url = client
+            .capabilities()
+            .and_then(|capabilities| capabilities.get("webSocketUrl"))
+            .and_then(Value::as_str)
+            .context("browser does not support WebDriver BiDi")?
+            .parse()?;
+
+        url.set_host(webdriver.host_str())?;
+        url.set_port(webdriver.port())
+            .map_err(|_| anyhow!("failed to set port of WebSocket URL"))?;
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(url.as_str())
+            .await
+            .context("failed to connect to WebDriver BiDi")?;
+
+        let subscribe = json!({
+            "id": SUBSCRIBE_ID,
+            "method": "session.subscribe",
+            "params": { "events": ["log.entryAdded"] },
+        });
+        socket
+            .send(Message::text(subscribe.to_string()))
+            .await
+            .context("failed to subscribe to log events")?;
+
+        // Wait for the browser to accept the subscription, which it rejects e.g. when the session
+        // does not support log events
+        let acknowledged = tokio::time::timeout(SUBSCRIBE_TIMEOUT, async {
+            while let Some(message) = socket.next().await {
+                let message = message.context("failed to read from WebDriver BiDi")?;
+                let Ok(response) = serde_json::from_str::<Value>(message.to_text()?) else {
+                    continue;
+                };
+
+                if let Some(result) = acknowledgement(&response, SUBSCRIBE_ID) {
+                    return result;
+                }
+            }
+
+            Err(anyhow!("WebDriver BiDi closed the connection"))
+        })
+        .await;
+        match acknowledged {
+            Ok(result) => result.context("failed to subscribe to log events")?,
+            Err(_) => {
+                return Err(anyhow!(
+                    "timed out waiting for the subscription to log events"
+                ))
+            }
+        }
+
+        let console = Self {
+            messages: Arc::default(),
+            bidi: true,
+        };
+
+        let messages = console.messages.clone();
+        tokio::spawn(async move {
+            while let Some(Ok(message)) = socket.next().await {
+                let Ok(text) = message.to_text() else {
+                    continue;
+                };
+                let Ok(event) = serde_json::from_str::<Value>(text) else {
+                    continue;
+                };
+
+                if event.get("method").and_then(Value::as_str) != Some("log.entryAdded") {
+                    continue;
+                }
+
+                if let Some(message) = event.get("params").and_then(ConsoleMessage::from_bidi) {
+                    messages
+                        .lock()
+                        .expect("console messages have been poisoned")
+                        .push(message);
+                }
+            }
+        });
+
+        Ok(console)
+    }
+
+    /// Prepare the page for navigation
+    ///
+    /// When the injected script is used, the messages in the current page must be drained before
+    /// navigating away from it. Otherwise, they would be lost.
+    pub(crate) async fn before_navigation(&self, client: &WebDriverClient) -> Result<(), CmdError> {
+        if !self.bidi {
+            self.drain(client).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Inject the script into a page that has just been loaded
+    pub(crate) async fn after_navigation(&self, client: &WebDriverClient) -> Result<(), CmdError> {
+        if !self.bidi {
+            client.execute(SHIM, Vec::new()).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Return all messages that have been collected so far
+    pub(crate) async fn messages(
+        &self,
+        client: &WebDriverClient,
+    ) -> Result<Vec<ConsoleMessage>, CmdError> {
+        if !self.bidi {
+            self.drain(client).await?;
+        }
+
+        Ok(self
+            .messages
+            .lock()
+            .expect("console messages have been poisoned")
+            .clone())
+    }
+
+    /// Move the messages that the injected script has buffered in the page into the collector
+    async fn drain(&self, client: &WebDriverClient) -> Result<(), CmdError> {
+        let drained = client.execute(DRAIN, Vec::new()).await?;
+
+        let drained = drained
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(ConsoleMessage::from_shim);
+
+        self.messages
+            .lock()
+            .expect("console messages have been poisoned")
+            .extend(drained);
+
+        Ok(())
+    }
+}
+
+/// Check whether a message from WebDriver BiDi acknowledges the command with the given id
+///
+/// Returns `None` if the message is not the response to the command, e.g. an event.
+fn acknowledgement(response: &Value, id: u64) -> Option<Result<()>> {
+    if response.get("id").and_then(Value::as_u64) != Some(id) {
+        return None;
+    }
+
+    // Older browsers omit the type of the response, but always include the result on success
+    match response.get("type").and_then(Value::as_str) {
+        Some("success") => Some(Ok(())),
+        None if response.get("result").is_some() => Some(Ok(())),
+        _ => {
+            let error = response
+                .get("error")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown error");
+            let message = response
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+
+            Some(Err(anyhow!("{error}: {message}")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    use super::*;
+
+    #[test]
+    fn level_parse() {
+        assert_eq!(ConsoleLevel::Debug, ConsoleLevel::parse("debug"));
+        assert_eq!(ConsoleLevel::Info, ConsoleLevel::parse("log"));
+        assert_eq!(ConsoleLevel::Warning, ConsoleLevel::parse("warn"));
+        assert_eq!(ConsoleLevel::Error, ConsoleLevel::parse("error"));
+    }
+
+    #[test]
+    fn message_from_shim() {
+        let value = json!({ "kind": "exception", "level": "error", "text": "boom" });
+
+        let message = ConsoleMessage::from_shim(&value).unwrap();
+
+        assert_eq!(ConsoleMessageKind::Exception, message.kind());
+        assert!(message.is_error());
+        assert_eq!("boom", message.text());
+    }
+
+    #[test]
+    fn message_from_bidi() {
+        let params = json!({
+            "type": "console",
+            "method": "warn",
+            "level": "warn",
+            "text": "deprecated",
+            "timestamp": 1_700_000_000_000u64,
+        });
+
+        let message = ConsoleMessage::from_bidi(&params).unwrap();
+
+        assert_eq!(ConsoleMessageKind::Console, message.kind());
+        assert_eq!(ConsoleLevel::Warning, message.level());
+        assert_eq!("[warning] deprecated", message.to_string());
+    }
+
+    #[test]
+    fn acknowledgement_success() {
+        let response = json!({ "id": 1, "type": "success", "result": {} });
+
+        assert!(matches!(acknowledgement(&response, 1), Some(Ok(()))));
+    }
+
+    #[test]
+    fn acknowledgement_error() {
+        let response = json!({
+            "id": 1,
+            "type": "error",
+            "error": "invalid argument",
+            "message": "unknown event",
+        });
+
+        assert_eq!(
+            "invalid argument: unknown event",
+            acknowledgement(&response, 1)
+                .unwrap()
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn acknowledgement_ignores_other_messages() {
+        let event = json!({ "type": "event", "method": "log.entryAdded", "params": {} });
+        let response = json!({ "id": 2, "type": "success", "result": {} });
+
+        assert!(acknowledgement(&event, 1).is_none());
+        assert!(acknowledgement(&response, 1).is_none());
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<ConsoleMessage>();
+        assert_send::<Console>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<ConsoleMessage>();
+        assert_sync::<Console>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<ConsoleMessage>();
+        assert_unpin::<Console>();
+    }
+}
@@ -0,0 +1,358 @@
+//! Containers of the server and its services that tests can run commands in
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use getset::{CopyGetters, Getters};
+use testcontainers::core::ExecCommand;
+use testcontainers::{ContainerAsync, GenericImage};
+
+use crate::fs::write;
+use crate::wait::POLL_INTERVAL;
+use crate::{Fixture, Result, TestContext};
+
+/// The number of bytes that are written into a container with a single command
+///
+/// The contents are passed as an argument to the command, which Linux limits to 128 KiB. Encoded
+/// as base64, a chunk of 48 KiB takes up 64 KiB and leaves enough room for the rest of the command.
+const CHUNK_SIZE: usize = 48 * 1024;
+
+/// Output of a command that has been executed in a container
+#[derive(Clone, Eq, PartialEq, Debug, CopyGetters, Getters)]
+pub struct ExecOutput {
+    /// The exit code of the command, e.g. `0` for success
+    #[getset(get_copy = "pub")]
+    exit_code: i64,
+
+    /// The output that the command has written to stdout
+    #[getset(get = "pub")]
+    stdout: String,
+
+    /// The output that the command has written to stderr
+    #[getset(get = "pub")]
+    stderr: String,
+}
+
+impl ExecOutput {
+    /// Check if the command has exited with the exit code `0`
+    pub fn success(&self) -> bool {
+        self.exit_code == 0
+    }
+}
+
+/// Container of the server or a service
+///
+/// A `Container` runs commands inside the container of the server or one of its services, for
+/// example to seed data, run a migration, or inspect the state of a database. Files can be copied
+/// into and out of the container as well. Copying uses `sh`, `cat`, `mkdir`, and `base64` inside
+/// the container, which are available in most images.
+#[derive(Clone, Debug, Getters)]
+pub struct Container {
+    /// The name of the container, which is the hostname of a service or `server`
+    #[getset(get = "pub")]
+    name: String,
+
+    /// The running container
+    container: Arc<ContainerAsync<GenericImage>>,
+
+    /// The time to wait for Docker to report the exit code of a command
+    timeout: Duration,
+}
+
+impl Container {
+    /// Execute a command in the container and wait for it to exit
+    ///
+    /// The command is not run in a shell. To use pipes or redirects, run the command with
+    /// `sh -c`, e.g. `["sh", "-c", "echo hello > /tmp/hello"]`. A command that exits with an
+    /// exit code other than `0` does not return an error, so that its output can be inspected. If
+    /// Docker does not report the exit code within the timeout of the test suite, an error is
+    /// returned.
+    pub async fn exec<I, S>(&self, cmd: I) -> Result<ExecOutput>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let (exit_code, stdout, stderr) = self.run(cmd).await?;
+
+        Ok(ExecOutput {
+            exit_code,
+            stdout: String::from_utf8_lossy(&stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        })
+    }
+
+    /// Write the given contents to a file in the container
+    ///
+    /// Missing parent directories are created, and an existing file is overwritten.
+    pub async fn copy_to(&self, path: &str, contents: impl AsRef<[u8]>) -> Result<()> {
+        for command in upload_commands(path, contents.as_ref()) {
+            let (exit_code, _, stderr) = self.run(["sh", "-c", command.as_str()]).await?;
+
+            if exit_code != 0 {
+                bail!(
+                    "failed to copy to '{path}' in container '{}': {}",
+                    self.name,
+                    String::from_utf8_lossy(&stderr).trim()
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Copy a local file into the container
+    pub async fn copy_file_to(&self, source: impl AsRef<Path>, path: &str) -> Result<()> {
+        let source = source.as_ref();
+        let contents = std::fs::read(source)
+            .with_context(|| format!("failed to read '{}'", source.display()))?;
+
+        self.copy_to(path, contents).await
+    }
+
+    /// Read the contents of a file in the container
+    pub async fn copy_from(&self, path: &str) -> Result<Vec<u8>> {
+        let (exit_code, stdout, stderr) = self.run(["cat", "--", path]).await?;
+
+        if exit_code != 0 {
+            bail!(
+                "failed to copy from '{path}' in container '{}': {}",
+                self.name,
+                String::from_utf8_lossy(&stderr).trim()
+            );
+        }
+
+        Ok(stdout)
+    }
+
+    /// Copy a file from the container to a local path
+    ///
+    /// Missing parent directories of the local path are created.
+    pub async fn copy_file_from(&self, path: &str, destination: impl AsRef<Path>) -> Result<()> {
+        let contents = self.copy_from(path).await?;
+
+        write(destination.as_ref(), &contents)
+    }
+
+    /// Execute a command and return its exit code together with the raw output
+    async fn run<I, S>(&self, cmd: I) -> Result<(i64, Vec<u8>, Vec<u8>)>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let cmd: Vec<String> = cmd.into_iter().map(Into::into).collect();
+
+        let mut result = self
+            .container
+            .exec(ExecCommand::new(cmd.clone()))
+            .await
+            .with_context(|| format!("failed to execute {cmd:?} in container '{}'", self.name))?;
+
+        let stdout = result.stdout_to_vec().await?;
+        let stderr = result.stderr_to_vec().await?;
+
+        // The output ends when the command exits, but Docker might report the exit code slightly
+        // later. A command that has detached from its output might never exit, though.
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            if let Some(exit_code) = result.exit_code().await? {
+                return Ok((exit_code, stdout, stderr));
+            }
+
+            if Instant::now() >= deadline {
+                return Err(anyhow!(
+                    "timed out after {:?} waiting for {cmd:?} to exit in container '{}'",
+                    self.timeout,
+                    self.name
+                ));
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Containers of the server and its services
+///
+/// The `Containers` fixture gives tests access to the containers that have been started for them.
+/// Services are identified by their hostname, which is the name of their image unless a hostname
+/// has been set.
+///
+/// # Example
+///
+/// ```no_run
+/// use doco::{Client, Containers, Result};
+///
+/// #[doco::test]
+/// async fn shows_seeded_todo(client: Client, containers: Containers) -> Result<()> {
+///     let postgres = containers.service("postgres")?;
+///     postgres.copy_to("/tmp/seed.sql", "INSERT INTO todos (title) VALUES ('Seeded');").await?;
+///
+///     let output = postgres
+///         .exec(["psql", "-U", "postgres", "-f", "/tmp/seed.sql"])
+///         .await?;
+///     assert!(output.success(), "{}", output.stderr());
+///
+///     client.goto("/").await?;
+///     assert!(client.source().await?.contains("Seeded"));
+///
+///     Ok(())
+/// }
+/// #
+/// # use doco::services::Postgres;
+/// # use doco::{Doco, Server};
+/// #
+/// # #[doco::main]
+/// # async fn main() -> Doco {
+/// #    let server = Server::builder()
+/// #        .image("crccheck/hello-world")
+/// #        .tag("v1.0.0")
+/// #        .port(8000)
+/// #        .build();
+/// #
+/// #    Doco::builder().server(server).service(Postgres::new()).build()
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Containers {
+    /// The container of the server
+    server: Container,
+
+    /// The containers of the services
+    services: Vec<Container>,
+}
+
+impl Containers {
+    /// Return the container of the server
+    pub fn server(&self) -> &Container {
+        &self.server
+    }
+
+    /// Return the container of the service with the given hostname
+    pub fn service(&self, hostname: &str) -> Result<&Container> {
+        self.services
+            .iter()
+            .find(|service| service.name == hostname)
+            .with_context(|| format!("failed to find service '{hostname}'"))
+    }
+}
+
+impl Fixture for Containers {
+    async fn setup(context: &TestContext) -> Result<Self> {
+        let timeout = context.client().timeout();
+
+        let server = Container {
+            name: String::from("server"),
+            container: context.server().clone(),
+            timeout,
+        };
+
+        let services = context
+            .services()
+            .iter()
+            .map(|(name, container)| Container {
+                name: name.clone(),
+                container: container.clone(),
+                timeout,
+            })
+            .collect();
+
+        Ok(Self { server, services })
+    }
+}
+
+/// Build the shell commands that write the contents to a file in chunks
+fn upload_commands(path: &str, contents: &[u8]) -> Vec<String> {
+    let path = quote(path);
+
+    let mut commands = vec![format!("mkdir -p \"$(dirname {path})\" && : > {path}")];
+
+    for chunk in contents.chunks(CHUNK_SIZE) {
+        commands.push(format!(
+            "printf '%s' '{}' | base64 -d >> {path}",
+            BASE64.encode(chunk)
+        ));
+    }
+
+    commands
+}
+
+/// Quote a string for the shell by wrapping it in single quotes
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    use super::*;
+
+    #[test]
+    fn quote_escapes_single_quotes() {
+        assert_eq!(r"'/tmp/it'\''s.txt'", quote("/tmp/it's.txt"));
+    }
+
+    #[test]
+    fn upload_commands_for_empty_file() {
+        assert_eq!(
+            vec![String::from(
+                "mkdir -p \"$(dirname '/tmp/empty')\" && : > '/tmp/empty'"
+            )],
+            upload_commands("/tmp/empty", b"")
+        );
+    }
+
+    #[test]
+    fn upload_commands_encode_contents() {
+        let commands = upload_commands("/tmp/seed.sql", b"SELECT 1;");
+
+        assert_eq!(2, commands.len());
+        assert_eq!(
+            "printf '%s' 'U0VMRUNUIDE7' | base64 -d >> '/tmp/seed.sql'",
+            commands[1]
+        );
+    }
+
+    #[test]
+    fn upload_commands_split_large_contents() {
+        let contents = vec![0; CHUNK_SIZE * 2 + 1];
+
+        assert_eq!(4, upload_commands("/tmp/large", &contents).len());
+    }
+
+    #[test]
+    fn exec_output_success() {
+        let output = ExecOutput {
+            exit_code: 0,
+            stdout: String::from("ok"),
+            stderr: String::new(),
+        };
+
+        assert!(output.success());
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<Container>();
+        assert_send::<Containers>();
+        assert_send::<ExecOutput>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<Container>();
+        assert_sync::<Containers>();
+        assert_sync::<ExecOutput>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<Container>();
+        assert_unpin::<Containers>();
+        assert_unpin::<ExecOutput>();
+    }
+}
@@ -0,0 +1,99 @@
+//! Context in which a single test is executed
+
+use std::sync::Arc;
+
+use getset::Getters;
+use reqwest::Url;
+use testcontainers::{ContainerAsync, GenericImage};
+
+use crate::{Client, Doco, Network};
+
+/// Context in which a single test is executed
+///
+/// The `TestContext` holds everything that the [`TestRunner`](crate::TestRunner) has set up for a
+/// test, most importantly the WebDriver [`Client`] and the containers for the server and its
+/// services. It is passed to the test function, which uses it to set up the [`Fixture`]s that the
+/// test has requested as its arguments.
+///
+/// It should not be necessary to use this struct directly. Instead, declare the fixtures that a
+/// test needs as arguments of a function annotated with [`doco::test`](crate::test).
+///
+/// [`Fixture`]: crate::Fixture
+#[derive(Clone, Debug, Getters)]
+pub struct TestContext {
+    /// The name of the test
+    #[getset(get = "pub")]
+    name: String,
+
+    /// The configuration of the test suite
+    #[getset(get = "pub")]
+    doco: Doco,
+
+    /// The WebDriver client that interacts with the web application
+    #[getset(get = "pub")]
+    client: Client,
+
+    /// The URL under which the test process reaches the server
+    ///
+    /// The browser runs inside a container and reaches the server under a different address, which
+    /// is the base URL of the [`Client`].
+    #[getset(get = "pub")]
+    server_url: Url,
+
+    /// The container that runs the server
+    #[getset(get = "pub(crate)")]
+    server: Arc<ContainerAsync<GenericImage>>,
+
+    /// The containers that run the services, keyed by their hostname
+    #[getset(get = "pub(crate)")]
+    services: Vec<(String, Arc<ContainerAsync<GenericImage>>)>,
+
+    /// The recording of the browser's network traffic, if enabled
+    #[getset(get = "pub(crate)")]
+    network: Option<Network>,
+}
+
+impl TestContext {
+    /// Create a new context for the given test
+    pub(crate) fn new(
+        name: impl Into<String>,
+        doco: Doco,
+        client: Client,
+        server_url: Url,
+        server: Arc<ContainerAsync<GenericImage>>,
+        services: Vec<(String, Arc<ContainerAsync<GenericImage>>)>,
+        network: Option<Network>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            doco,
+            client,
+            server_url,
+            server,
+            services,
+            network,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    use super::*;
+
+    #[test]
+    fn trait_send() {
+        assert_send::<TestContext>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<TestContext>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<TestContext>();
+    }
+}
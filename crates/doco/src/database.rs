@@ -0,0 +1,231 @@
+//! Database connections that tests use to query and seed the services of the server
+//!
+//! The connections are provided as fixtures behind the `postgres` and `mysql` features. They
+//! connect to the port under which the test process reaches the database container of the current
+//! test, with the credentials that have been configured for the service.
+
+use anyhow::{anyhow, Context};
+use reqwest::Url;
+
+#[cfg(feature = "mysql")]
+use crate::services::MySql;
+#[cfg(feature = "postgres")]
+use crate::services::Postgres;
+use crate::{Fixture, Result, Service, TestContext};
+
+/// Find the database service with the given name
+///
+/// The service whose hostname is the name is preferred, which is the default hostname of the
+/// presets. Otherwise, the first service whose image is named like the database is used, which
+/// also matches images from other registries or with a suffix, e.g. `bitnami/postgresql`.
+fn find_database<'a>(services: &'a [Service], name: &str) -> Option<&'a Service> {
+    services
+        .iter()
+        .find(|service| service.host() == name)
+        .or_else(|| {
+            services.iter().find(|service| {
+                let image = service.image().rsplit('/').next().unwrap_or_default();
+                image.starts_with(name)
+            })
+        })
+}
+
+/// Build the URL under which the test process reaches the database service with the given name
+///
+/// The name is also used as the scheme of the URL. The user, password, and database are
+/// percent-encoded, so that they can contain any character.
+async fn database_url(
+    context: &TestContext,
+    name: &str,
+    port: u16,
+    credentials: impl Fn(&Service) -> (String, String, String),
+) -> Result<Url> {
+    let service = find_database(context.doco().services(), name)
+        .with_context(|| format!("no service with the hostname or image '{name}'"))?;
+
+    let (_, container) = context
+        .services()
+        .iter()
+        .find(|(host, _)| host == service.host())
+        .with_context(|| format!("failed to find service '{}'", service.host()))?;
+
+    let (user, password, database) = credentials(service);
+
+    let mut url: Url = format!(
+        "{name}://{}:{}",
+        container.get_host().await?,
+        container.get_host_port_ipv4(port).await?
+    )
+    .parse()?;
+
+    url.set_username(&user)
+        .map_err(|_| anyhow!("invalid user '{user}'"))?;
+    url.set_password(Some(&password))
+        .map_err(|_| anyhow!("invalid password for user '{user}'"))?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow!("invalid database '{database}'"))?
+        .push(&database);
+
+    Ok(url)
+}
+
+/// Return the user, password, and database that have been configured for a PostgreSQL service
+#[cfg(feature = "postgres")]
+fn postgres_credentials(service: &Service) -> (String, String, String) {
+    let user = service.env("POSTGRES_USER").unwrap_or("postgres");
+    let password = service.env("POSTGRES_PASSWORD").unwrap_or_default();
+    let database = service.env("POSTGRES_DB").unwrap_or(user);
+
+    (user.into(), password.into(), database.into())
+}
+
+/// Return the user, password, and database that have been configured for a MySQL service
+#[cfg(feature = "mysql")]
+fn mysql_credentials(service: &Service) -> (String, String, String) {
+    let (user, password) = match service.env("MYSQL_USER") {
+        Some(user) => (user, service.env("MYSQL_PASSWORD").unwrap_or_default()),
+        None => (
+            "root",
+            service.env("MYSQL_ROOT_PASSWORD").unwrap_or_default(),
+        ),
+    };
+    let database = service.env("MYSQL_DATABASE").unwrap_or_default();
+
+    (user.into(), password.into(), database.into())
+}
+
+/// Connection pool for the PostgreSQL service of a test
+///
+/// The pool connects to the service with the hostname `postgres`, which is the default of the
+/// [`Postgres`] preset, or else to the first service with a `postgres` image.
+#[cfg(feature = "postgres")]
+impl Fixture for sqlx::PgPool {
+    async fn setup(context: &TestContext) -> Result<Self> {
+        let url = database_url(context, "postgres", Postgres::PORT, postgres_credentials).await?;
+
+        sqlx::postgres::PgPoolOptions::new()
+            .max_connections(2)
+            .connect(url.as_str())
+            .await
+            .context("failed to connect to PostgreSQL")
+    }
+}
+
+/// Connection pool for the MySQL service of a test
+///
+/// The pool connects to the service with the hostname `mysql`, which is the default of the
+/// [`MySql`] preset, or else to the first service with a `mysql` image.
+#[cfg(feature = "mysql")]
+impl Fixture for sqlx::MySqlPool {
+    async fn setup(context: &TestContext) -> Result<Self> {
+        let url = database_url(context, "mysql", MySql::PORT, mysql_credentials).await?;
+
+        sqlx::mysql::MySqlPoolOptions::new()
+            .max_connections(2)
+            .connect(url.as_str())
+            .await
+            .context("failed to connect to MySQL")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn service(image: &str, hostname: Option<&str>) -> Service {
+        let service = Service::builder().image(image).tag("latest");
+
+        match hostname {
+            Some(hostname) => service.hostname(hostname).build(),
+            None => service.build(),
+        }
+    }
+
+    #[test]
+    fn find_database_by_hostname() {
+        let services = [
+            service("postgres", Some("analytics")),
+            service("postgis/postgis", Some("postgres")),
+        ];
+
+        assert_eq!(
+            "postgis/postgis",
+            find_database(&services, "postgres").unwrap().image()
+        );
+    }
+
+    #[test]
+    fn find_database_by_image() {
+        let services = [
+            service("redis", None),
+            service("bitnami/postgresql", Some("db")),
+        ];
+
+        assert_eq!("db", find_database(&services, "postgres").unwrap().host());
+    }
+
+    #[test]
+    fn find_database_missing() {
+        let services = [service("redis", None)];
+
+        assert!(find_database(&services, "mysql").is_none());
+    }
+
+    #[cfg(feature = "postgres")]
+    #[test]
+    fn postgres_credentials_from_preset() {
+        let service = Service::from(Postgres::new().user("app").password("secret"));
+
+        assert_eq!(
+            (
+                String::from("app"),
+                String::from("secret"),
+                String::from("postgres")
+            ),
+            postgres_credentials(&service)
+        );
+    }
+
+    #[cfg(feature = "postgres")]
+    #[test]
+    fn postgres_credentials_default_database_to_user() {
+        let service = Service::builder()
+            .image("postgres")
+            .tag("latest")
+            .env("POSTGRES_USER", "app")
+            .env("POSTGRES_PASSWORD", "secret")
+            .build();
+
+        assert_eq!("app", postgres_credentials(&service).2);
+    }
+
+    #[cfg(feature = "mysql")]
+    #[test]
+    fn mysql_credentials_as_root() {
+        let service = Service::from(MySql::new().password("secret"));
+
+        assert_eq!(
+            (
+                String::from("root"),
+                String::from("secret"),
+                String::from("app")
+            ),
+            mysql_credentials(&service)
+        );
+    }
+
+    #[cfg(feature = "mysql")]
+    #[test]
+    fn mysql_credentials_with_user() {
+        let service = Service::from(MySql::new().user("app").password("secret"));
+
+        assert_eq!(
+            (
+                String::from("app"),
+                String::from("secret"),
+                String::from("app")
+            ),
+            mysql_credentials(&service)
+        );
+    }
+}
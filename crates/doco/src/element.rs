@@ -0,0 +1,161 @@
+//! Elements that are looked up lazily when they are used
+
+use fantoccini::elements::Element;
+use fantoccini::error::CmdError;
+use fantoccini::Locator;
+
+use crate::wait::describe;
+use crate::{Client, Result};
+
+/// Locator that owns its selector
+///
+/// Fantoccini's [`Locator`] borrows its selector, which makes it impossible to store it for later.
+/// This enum mirrors its variants, but owns the selector.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) enum OwnedLocator {
+    /// Find an element matching the given CSS selector
+    Css(String),
+
+    /// Find an element using the given `id`
+    Id(String),
+
+    /// Find a link element with the given link text
+    LinkText(String),
+
+    /// Find an element using the given XPath expression
+    XPath(String),
+}
+
+impl OwnedLocator {
+    /// Borrow the selector as a fantoccini [`Locator`]
+    pub(crate) fn as_locator(&self) -> Locator<'_> {
+        match self {
+            OwnedLocator::Css(selector) => Locator::Css(selector),
+            OwnedLocator::Id(id) => Locator::Id(id),
+            OwnedLocator::LinkText(text) => Locator::LinkText(text),
+            OwnedLocator::XPath(xpath) => Locator::XPath(xpath),
+        }
+    }
+}
+
+impl From<Locator<'_>> for OwnedLocator {
+    fn from(locator: Locator<'_>) -> Self {
+        match locator {
+            Locator::Css(selector) => OwnedLocator::Css(selector.into()),
+            Locator::Id(id) => OwnedLocator::Id(id.into()),
+            Locator::LinkText(text) => OwnedLocator::LinkText(text.into()),
+            Locator::XPath(xpath) => OwnedLocator::XPath(xpath.into()),
+        }
+    }
+}
+
+/// Element that is looked up lazily when it is used
+///
+/// A `LazyElement` combines a [`Client`] with a locator, but does not look up the element until it
+/// is needed. This makes it possible to keep a reference to an element across changes to the DOM,
+/// for example to pass it to [`expect`](crate::expect) which checks the element repeatedly until an
+/// assertion holds.
+///
+/// # Example
+///
+/// ```no_run
+/// use doco::{Client, Locator, Result};
+///
+/// async fn clicks_button(client: Client) -> Result<()> {
+///     client.goto("/").await?;
+///
+///     let button = client.locate(Locator::Css("button"));
+///     button.resolve().await?.click().await?;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct LazyElement {
+    /// The client that is used to look up the element
+    client: Client,
+
+    /// The locator for the element
+    locator: OwnedLocator,
+}
+
+impl LazyElement {
+    /// Create a new lazy element
+    pub(crate) fn new(client: Client, locator: OwnedLocator) -> Self {
+        Self { client, locator }
+    }
+
+    /// Return the client that is used to look up the element
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Return the locator for the element
+    pub fn locator(&self) -> Locator<'_> {
+        self.locator.as_locator()
+    }
+
+    /// Look up the element, waiting until it exists
+    pub async fn resolve(&self) -> Result<Element> {
+        self.client.wait_for_element(self.locator()).await
+    }
+
+    /// Look up the element without waiting, returning `None` if it does not exist
+    pub async fn try_resolve(&self) -> Result<Option<Element>, CmdError> {
+        match self.client.find(self.locator()).await {
+            Ok(element) => Ok(Some(element)),
+            Err(error) if error.is_no_such_element() => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Look up all elements that match the locator without waiting
+    pub async fn resolve_all(&self) -> Result<Vec<Element>, CmdError> {
+        self.client.find_all(self.locator()).await
+    }
+
+    /// Describe the locator in a human-readable way
+    pub(crate) fn describe(&self) -> String {
+        describe(self.locator())
+    }
+}
+
+impl Client {
+    /// Return an element that is looked up lazily when it is used
+    ///
+    /// See [`LazyElement`] for more information.
+    pub fn locate<'l>(&self, locator: impl Into<Locator<'l>>) -> LazyElement {
+        LazyElement::new(self.clone(), locator.into().into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    use super::*;
+
+    #[test]
+    fn owned_locator_roundtrip() {
+        let locator = Locator::XPath("//main/button");
+
+        let owned = OwnedLocator::from(locator);
+
+        assert_eq!(locator, owned.as_locator());
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<LazyElement>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<LazyElement>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<LazyElement>();
+    }
+}
@@ -0,0 +1,455 @@
+//! Machine-readable stream of events about the test suite
+//!
+//! Besides the human-readable output, Doco can write a stream of events as newline-delimited JSON
+//! (NDJSON) with the [`Json`](crate::reporters::Json) reporter. Each line is a JSON object with the
+//! `event` that has happened, a `timestamp` in milliseconds since the Unix epoch, and the fields of
+//! the event. Dashboards and other tools can follow the stream while the tests are running, since
+//! every event is flushed immediately.
+//!
+//! The steps of a test are reported with their status and duration, but without the output of the
+//! server. The logs of the server and its services are saved in the artifacts directory instead,
+//! which is reported with an `artifact` event. When a test fails, the logs of each container are
+//! also written as a `test_logs` event.
+
+use std::fmt::{Debug, Formatter};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use serde::Serialize;
+
+use crate::reporter::warn;
+use crate::{ArtifactKind, Log, Provisioning, Result, Status, Summary, TestOutcome};
+
+/// Event that is written to the event stream
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub(crate) enum Event {
+    /// The test suite has started to run its tests
+    SuiteStarted {
+        /// The number of tests that will be run
+        tests: usize,
+
+        /// The number of tests that have been filtered out
+        filtered: usize,
+    },
+
+    /// The containers of an environment are being started
+    ProvisioningStarted {
+        /// The test for which the environment is started
+        test: String,
+    },
+
+    /// The environment has been started
+    ProvisioningFinished {
+        /// The test for which the environment has been started
+        test: String,
+
+        /// The time it took to start the whole environment in milliseconds
+        duration_ms: u64,
+
+        /// The time it took to start each container
+        containers: Vec<ContainerDuration>,
+
+        /// The time it took to create the WebDriver session in milliseconds
+        session_ms: u64,
+    },
+
+    /// A test has started
+    TestStarted {
+        /// The qualified name of the test
+        test: String,
+
+        /// The location of the test in the source code
+        location: String,
+    },
+
+    /// A step of a test has finished, i.e. a hook or the test function itself
+    Step {
+        /// The qualified name of the test
+        test: String,
+
+        /// The name of the step, e.g. `test` or ``before_each hook `reset` ``
+        step: String,
+
+        /// The status of the step
+        status: String,
+
+        /// The time it took to run the step in milliseconds
+        duration_ms: u64,
+    },
+
+    /// The logs of a container after a test or one of its hooks has failed
+    TestLogs {
+        /// The qualified name of the test
+        test: String,
+
+        /// The hostname of the service, or `server` for the server
+        container: String,
+
+        /// The output that the container has written to stdout
+        stdout: String,
+
+        /// The output that the container has written to stderr
+        stderr: String,
+    },
+
+    /// A test has produced an artifact
+    Artifact {
+        /// The qualified name of the test
+        test: String,
+
+        /// The kind of artifact, e.g. `logs`, `har`, `video`, `screenshot`, or `html_snapshot`
+        kind: String,
+
+        /// The path of the artifact
+        path: PathBuf,
+    },
+
+    /// Something has gone wrong that does not fail the test suite
+    Warning {
+        /// The qualified name of the test, or `None` for warnings about the whole test suite
+        test: Option<String>,
+
+        /// The description of what has gone wrong
+        message: String,
+    },
+
+    /// A test has finished
+    TestFinished {
+        /// The qualified name of the test
+        test: String,
+
+        /// The status of the test
+        status: String,
+
+        /// The time it took to run the test including its environment in milliseconds
+        duration_ms: u64,
+
+        /// The error of a failed test
+        error: Option<String>,
+    },
+
+    /// All tests and hooks have been run
+    SuiteFinished {
+        /// The number of tests that have passed
+        passed: usize,
+
+        /// The number of tests that have failed
+        failed: usize,
+
+        /// The number of tests that have been skipped
+        skipped: usize,
+
+        /// The number of hooks that have failed
+        hooks_failed: usize,
+
+        /// The time it took to run the test suite in milliseconds
+        duration_ms: u64,
+    },
+}
+
+impl Event {
+    /// Create a step event with the given status
+    pub(crate) fn step(
+        test: &str,
+        step: impl Into<String>,
+        status: Status,
+        duration: Duration,
+    ) -> Self {
+        Event::Step {
+            test: test.into(),
+            step: step.into(),
+            status: status.to_string(),
+            duration_ms: millis(duration),
+        }
+    }
+
+    /// Create a provisioning event from the timings of an environment
+    pub(crate) fn provisioning_finished(test: &str, provisioning: &Provisioning) -> Self {
+        Event::ProvisioningFinished {
+            test: test.into(),
+            duration_ms: millis(provisioning.duration()),
+            containers: provisioning
+                .containers()
+                .iter()
+                .map(|container| ContainerDuration {
+                    name: container.name().clone(),
+                    duration_ms: millis(container.duration()),
+                    pull_ms: millis(container.pull()),
+                    start_ms: millis(container.start()),
+                    ready_ms: millis(container.ready()),
+                })
+                .collect(),
+            session_ms: millis(provisioning.session()),
+        }
+    }
+
+    /// Create an event for a test that has finished
+    pub(crate) fn test_finished(outcome: &TestOutcome) -> Self {
+        Event::TestFinished {
+            test: outcome.name().clone(),
+            status: outcome.status().to_string(),
+            duration_ms: millis(outcome.duration()),
+            error: outcome.error().as_ref().map(|error| format!("{error:?}")),
+        }
+    }
+
+    /// Create an event for the test suite that has finished
+    pub(crate) fn suite_finished(summary: &Summary) -> Self {
+        Event::SuiteFinished {
+            passed: summary.count(Status::Passed),
+            failed: summary.count(Status::Failed),
+            skipped: summary.count(Status::Skipped),
+            hooks_failed: summary.hook_failures().len(),
+            duration_ms: millis(summary.duration()),
+        }
+    }
+
+    /// Create an event with the logs of a container
+    pub(crate) fn test_logs(test: &str, container: &str, log: &Log) -> Self {
+        Event::TestLogs {
+            test: test.into(),
+            container: container.into(),
+            stdout: log.stdout().clone(),
+            stderr: log.stderr().clone(),
+        }
+    }
+
+    /// Create an artifact event for the file or directory at the given path
+    pub(crate) fn artifact(test: &str, kind: ArtifactKind, path: &Path) -> Self {
+        Event::Artifact {
+            test: test.into(),
+            kind: kind.to_string(),
+            path: path.to_path_buf(),
+        }
+    }
+}
+
+/// Time it took to start a container
+#[derive(Clone, Eq, PartialEq, Debug, Serialize)]
+pub(crate) struct ContainerDuration {
+    /// The hostname of the service, or `server` for the server
+    name: String,
+
+    /// The time it took to start the container in milliseconds
+    duration_ms: u64,
+
+    /// The time it took to pull the image in milliseconds
+    pull_ms: u64,
+
+    /// The time it took to create and start the container in milliseconds
+    start_ms: u64,
+
+    /// The time it took for the container to become ready in milliseconds
+    ready_ms: u64,
+}
+
+/// Event together with the time at which it has happened
+#[derive(Serialize)]
+struct Record<'a> {
+    /// The time of the event in milliseconds since the Unix epoch
+    timestamp: u64,
+
+    /// The event
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+/// Stream to which the events of the test suite are written
+pub(crate) struct Events {
+    /// The writer for the events
+    writer: Mutex<Box<dyn Write + Send>>,
+}
+
+impl Events {
+    /// Create an event stream that writes to the given writer
+    pub(crate) fn new(writer: Box<dyn Write + Send>) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+
+    /// Write an event to the stream
+    ///
+    /// Failing to write an event does not fail the test suite. Instead, a warning is passed on to
+    /// the other reporters.
+    pub(crate) fn emit(&self, event: Event) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let record = Record {
+            timestamp: millis(timestamp),
+            event: &event,
+        };
+
+        // A panic while writing an event leaves at most a partial line behind, which is better
+        // than losing all following events
+        let mut writer = self
+            .writer
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        let result = serde_json::to_writer(&mut *writer, &record)
+            .map_err(std::io::Error::from)
+            .and_then(|()| writer.write_all(b"\n"))
+            .and_then(|()| writer.flush());
+
+        if let Err(error) = result {
+            warn(
+                None,
+                &format!("failed to write event to the event stream: {error}"),
+            );
+        }
+    }
+}
+
+impl Debug for Events {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Events").finish_non_exhaustive()
+    }
+}
+
+/// Create the file for the event stream and its parent directories
+pub(crate) fn create(path: &Path) -> Result<File> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create directory {}", parent.display()))?;
+    }
+
+    File::create(path).with_context(|| format!("failed to create event stream {}", path.display()))
+}
+
+/// Convert a duration to whole milliseconds
+pub(crate) fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::{json, Value};
+
+    use crate::ContainerTiming;
+
+    use crate::test_utils::*;
+
+    use super::*;
+
+    /// Writer that shares its buffer with the test
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn lines(buffer: &Buffer) -> Vec<Value> {
+        String::from_utf8(buffer.0.lock().unwrap().clone())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn emit_writes_one_line_per_event() {
+        let buffer = Buffer::default();
+        let events = Events::new(Box::new(buffer.clone()));
+
+        events.emit(Event::SuiteStarted {
+            tests: 2,
+            filtered: 1,
+        });
+        events.emit(Event::step(
+            "auth::login",
+            "test",
+            Status::Passed,
+            Duration::from_millis(1500),
+        ));
+
+        let lines = lines(&buffer);
+        assert_eq!(2, lines.len());
+        assert_eq!("suite_started", lines[0]["event"]);
+        assert_eq!(2, lines[0]["tests"]);
+        assert!(lines[0]["timestamp"].as_u64().unwrap() > 0);
+        assert_eq!("step", lines[1]["event"]);
+        assert_eq!("ok", lines[1]["status"]);
+        assert_eq!(1500, lines[1]["duration_ms"]);
+    }
+
+    #[test]
+    fn provisioning_finished_lists_containers() {
+        let provisioning = Provisioning::new(
+            Duration::from_secs(3),
+            vec![ContainerTiming::new(
+                "postgres",
+                Duration::from_millis(500),
+                Duration::from_millis(300),
+                Duration::from_millis(1200),
+            )],
+            Duration::from_millis(800),
+        );
+        let event = Event::provisioning_finished("login", &provisioning);
+
+        assert_eq!(
+            json!({
+                "event": "provisioning_finished",
+                "test": "login",
+                "duration_ms": 3000,
+                "containers": [{
+                    "name": "postgres",
+                    "duration_ms": 2000,
+                    "pull_ms": 500,
+                    "start_ms": 300,
+                    "ready_ms": 1200
+                }],
+                "session_ms": 800
+            }),
+            serde_json::to_value(&event).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_logs_names_container() {
+        let event = Event::test_logs("auth::login", "server", &Log::default());
+
+        assert_eq!(
+            json!({
+                "event": "test_logs",
+                "test": "auth::login",
+                "container": "server",
+                "stdout": "",
+                "stderr": ""
+            }),
+            serde_json::to_value(&event).unwrap()
+        );
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<Event>();
+        assert_send::<Events>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<Event>();
+        assert_sync::<Events>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<Event>();
+        assert_unpin::<Events>();
+    }
+}
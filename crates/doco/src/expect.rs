@@ -0,0 +1,464 @@
+//! Fluent, retrying assertions for pages and elements
+
+use std::borrow::Borrow;
+use std::fmt::Debug;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use fantoccini::error::CmdError;
+
+use crate::wait::POLL_INTERVAL;
+use crate::{Client, LazyElement, Result};
+
+/// Start an assertion on a page or an element
+///
+/// Assertions are checked repeatedly until they hold or the timeout of the [`Client`] is reached.
+/// This makes them robust against web applications that update the DOM asynchronously, e.g.
+/// after a click. When an assertion fails, the error contains the expected and the actual values
+/// as well as the locator of the element.
+///
+/// Pass a [`Client`] to make assertions about the current page, or a [`LazyElement`] to make
+/// assertions about an element on the page.
+///
+/// # Example
+///
+/// ```no_run
+/// use doco::{expect, Client, Locator, Result};
+///
+/// async fn increments_counter(client: Client) -> Result<()> {
+///     client.goto("/").await?;
+///
+///     expect(&client).to_have_title("Counter").await?;
+///
+///     let button = client.locate(Locator::Css("button"));
+///     button.resolve().await?.click().await?;
+///
+///     expect(&button).to_contain_text("Clicks: 1").await?;
+///     expect(client.locate(Locator::Css(".error"))).to_have_count(0).await?;
+///
+///     Ok(())
+/// }
+/// ```
+pub fn expect<T>(target: T) -> Expect<T> {
+    Expect { target }
+}
+
+/// Assertions on a page or an element
+///
+/// See [`expect`] for more information.
+#[derive(Clone, Debug)]
+pub struct Expect<T> {
+    /// The page or element that the assertions are made about
+    target: T,
+}
+
+/// Observation of the target during a single attempt of an assertion
+struct Observation {
+    /// Whether the target matches the expectation
+    matches: bool,
+
+    /// The actual value, formatted for the error message
+    actual: String,
+}
+
+impl Observation {
+    /// Compare an expected and an actual value
+    fn new(matches: bool, actual: impl Debug) -> Self {
+        Self {
+            matches,
+            actual: format!("{actual:?}"),
+        }
+    }
+}
+
+/// Assertion that is checked repeatedly until it holds or the timeout is reached
+struct Assertion<'a> {
+    /// The page or element that the assertion is about, e.g. "page"
+    subject: String,
+
+    /// The property that is asserted, e.g. "to have title"
+    property: &'a str,
+
+    /// The expected value, formatted for the error message
+    expected: String,
+
+    /// Whether the assertion is negated
+    negated: bool,
+
+    /// The time to wait for the assertion to hold
+    timeout: Duration,
+}
+
+impl Assertion<'_> {
+    /// Observe the target until the assertion holds or the timeout is reached
+    ///
+    /// When the web application re-renders the target while it is observed, WebDriver reports
+    /// that the element is stale or does not exist anymore. Such an observation is inconclusive
+    /// and the target is observed again, both for normal and negated assertions.
+    async fn check<F, Fut>(self, mut observe: F) -> Result<()>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<Observation>>,
+    {
+        let start = Instant::now();
+
+        loop {
+            let actual = match observe().await {
+                Ok(observation) if observation.matches != self.negated => return Ok(()),
+                Ok(observation) => observation.actual,
+                Err(error) if is_detached(&error) => format!("<{error}>"),
+                Err(error) => return Err(error),
+            };
+
+            if start.elapsed() >= self.timeout {
+                return Err(self.failure(&actual));
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Create the error for an assertion that did not hold
+    fn failure(&self, actual: &str) -> anyhow::Error {
+        let not = if self.negated { "not " } else { "" };
+
+        anyhow!(
+            "expected {} {not}{} (waited {:?})\n  expected: {not}{}\n  actual:   {actual}",
+            self.subject,
+            self.property,
+            self.timeout,
+            self.expected,
+        )
+    }
+}
+
+/// Check if an error means that the element has been removed from the DOM while it was observed
+fn is_detached(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<CmdError>()
+        .is_some_and(|error| error.is_stale_element_reference() || error.is_no_such_element())
+}
+
+impl<C: Borrow<Client>> Expect<C> {
+    /// Assert that the page has the given title
+    pub async fn to_have_title(&self, title: &str) -> Result<()> {
+        self.title(title, false).await
+    }
+
+    /// Assert that the page does not have the given title
+    pub async fn not_to_have_title(&self, title: &str) -> Result<()> {
+        self.title(title, true).await
+    }
+
+    /// Assert that the page is at the given path
+    ///
+    /// The path is resolved relative to the base URL of the server, just like in
+    /// [`Client::goto`].
+    pub async fn to_have_url(&self, path: &str) -> Result<()> {
+        self.url(path, false).await
+    }
+
+    /// Assert that the page is not at the given path
+    pub async fn not_to_have_url(&self, path: &str) -> Result<()> {
+        self.url(path, true).await
+    }
+
+    /// Check the title of the page
+    async fn title(&self, title: &str, negated: bool) -> Result<()> {
+        let client = self.target.borrow();
+
+        self.page_assertion("to have title", title, negated)
+            .check(|| async {
+                let actual = client.title().await?;
+                Ok(Observation::new(actual == title, actual))
+            })
+            .await
+    }
+
+    /// Check the URL of the page
+    async fn url(&self, path: &str, negated: bool) -> Result<()> {
+        let client = self.target.borrow();
+        let expected = client.base_url().join(path)?;
+
+        self.page_assertion("to have URL", expected.as_str(), negated)
+            .check(|| async {
+                let actual = client.current_url().await?;
+                Ok(Observation::new(
+                    actual.as_str() == expected.as_str(),
+                    actual.as_str(),
+                ))
+            })
+            .await
+    }
+
+    /// Create an assertion about the page
+    fn page_assertion<'a>(
+        &self,
+        property: &'a str,
+        expected: impl Debug,
+        negated: bool,
+    ) -> Assertion<'a> {
+        Assertion {
+            subject: String::from("page"),
+            property,
+            expected: format!("{expected:?}"),
+            negated,
+            timeout: self.target.borrow().timeout(),
+        }
+    }
+}
+
+impl<E: Borrow<LazyElement>> Expect<E> {
+    /// Assert that the element contains the given text
+    pub async fn to_contain_text(&self, text: &str) -> Result<()> {
+        self.text(text, false).await
+    }
+
+    /// Assert that the element does not contain the given text
+    pub async fn not_to_contain_text(&self, text: &str) -> Result<()> {
+        self.text(text, true).await
+    }
+
+    /// Assert that the element is visible
+    pub async fn to_be_visible(&self) -> Result<()> {
+        self.visible(false).await
+    }
+
+    /// Assert that the element is not visible or does not exist
+    pub async fn not_to_be_visible(&self) -> Result<()> {
+        self.visible(true).await
+    }
+
+    /// Assert that the element has an attribute with the given value
+    pub async fn to_have_attribute(&self, name: &str, value: &str) -> Result<()> {
+        self.attribute(name, value, false).await
+    }
+
+    /// Assert that the element does not have an attribute with the given value
+    pub async fn not_to_have_attribute(&self, name: &str, value: &str) -> Result<()> {
+        self.attribute(name, value, true).await
+    }
+
+    /// Assert that the locator matches the given number of elements
+    pub async fn to_have_count(&self, count: usize) -> Result<()> {
+        self.count(count, false).await
+    }
+
+    /// Assert that the locator does not match the given number of elements
+    pub async fn not_to_have_count(&self, count: usize) -> Result<()> {
+        self.count(count, true).await
+    }
+
+    /// Check the text of the element
+    async fn text(&self, text: &str, negated: bool) -> Result<()> {
+        let element = self.target.borrow();
+
+        self.element_assertion("to contain text", text, negated)
+            .check(|| async {
+                let actual = match element.try_resolve().await? {
+                    Some(element) => Some(element.text().await?),
+                    None => None,
+                };
+
+                let matches = actual.as_ref().is_some_and(|actual| actual.contains(text));
+                Ok(Observation::new(matches, actual))
+            })
+            .await
+    }
+
+    /// Check the visibility of the element
+    async fn visible(&self, negated: bool) -> Result<()> {
+        let element = self.target.borrow();
+
+        self.element_assertion("to be visible", true, negated)
+            .check(|| async {
+                let visible = match element.try_resolve().await? {
+                    Some(element) => element.is_displayed().await?,
+                    None => false,
+                };
+
+                Ok(Observation::new(visible, visible))
+            })
+            .await
+    }
+
+    /// Check an attribute of the element
+    async fn attribute(&self, name: &str, value: &str, negated: bool) -> Result<()> {
+        let element = self.target.borrow();
+        let property = format!("to have attribute `{name}`");
+
+        self.element_assertion(&property, value, negated)
+            .check(|| async {
+                let actual = match element.try_resolve().await? {
+                    Some(element) => element.attr(name).await?,
+                    None => None,
+                };
+
+                let matches = actual.as_deref() == Some(value);
+                Ok(Observation::new(matches, actual))
+            })
+            .await
+    }
+
+    /// Check the number of elements that match the locator
+    async fn count(&self, count: usize, negated: bool) -> Result<()> {
+        let element = self.target.borrow();
+
+        self.element_assertion("to have count", count, negated)
+            .check(|| async {
+                let actual = element.resolve_all().await?.len();
+                Ok(Observation::new(actual == count, actual))
+            })
+            .await
+    }
+
+    /// Create an assertion about the element
+    fn element_assertion<'a>(
+        &self,
+        property: &'a str,
+        expected: impl Debug,
+        negated: bool,
+    ) -> Assertion<'a> {
+        let element = self.target.borrow();
+
+        Assertion {
+            subject: format!("element with {}", element.describe()),
+            property,
+            expected: format!("{expected:?}"),
+            negated,
+            timeout: element.client().timeout(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use fantoccini::error::{ErrorStatus, WebDriver};
+
+    use super::*;
+
+    fn assertion(negated: bool) -> Assertion<'static> {
+        Assertion {
+            subject: String::from("element with css `h1`"),
+            property: "to contain text",
+            expected: format!("{:?}", "Hello"),
+            negated,
+            timeout: Duration::from_millis(0),
+        }
+    }
+
+    fn webdriver_error(status: ErrorStatus) -> anyhow::Error {
+        CmdError::Standard(WebDriver::new(status, "element is gone")).into()
+    }
+
+    /// Observe an element that is removed and re-rendered during the first attempts
+    async fn re_rendered(attempts: &AtomicUsize, text: &str) -> Result<Observation> {
+        match attempts.fetch_add(1, Ordering::SeqCst) {
+            0 => Err(webdriver_error(ErrorStatus::StaleElementReference)),
+            1 => Err(webdriver_error(ErrorStatus::NoSuchElement)),
+            _ => Ok(Observation::new(text == "Hello", text)),
+        }
+    }
+
+    #[tokio::test]
+    async fn check_passes_when_observation_matches() -> Result<()> {
+        assertion(false)
+            .check(|| async { Ok(Observation::new(true, "Hello")) })
+            .await
+    }
+
+    #[tokio::test]
+    async fn check_passes_when_negated_observation_does_not_match() -> Result<()> {
+        assertion(true)
+            .check(|| async { Ok(Observation::new(false, "Goodbye")) })
+            .await
+    }
+
+    #[tokio::test]
+    async fn check_fails_with_expected_and_actual_values() {
+        let error = assertion(false)
+            .check(|| async { Ok(Observation::new(false, "Goodbye")) })
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            "expected element with css `h1` to contain text (waited 0ns)\n  expected: \"Hello\"\n  actual:   \"Goodbye\"",
+            error.to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn check_retries_while_element_is_re_rendered() -> Result<()> {
+        let attempts = AtomicUsize::new(0);
+
+        Assertion {
+            timeout: Duration::from_secs(5),
+            ..assertion(false)
+        }
+        .check(|| re_rendered(&attempts, "Hello"))
+        .await?;
+
+        assert_eq!(3, attempts.load(Ordering::SeqCst));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn check_retries_negated_assertion_while_element_is_re_rendered() -> Result<()> {
+        let attempts = AtomicUsize::new(0);
+
+        Assertion {
+            timeout: Duration::from_secs(5),
+            ..assertion(true)
+        }
+        .check(|| re_rendered(&attempts, "Goodbye"))
+        .await?;
+
+        assert_eq!(3, attempts.load(Ordering::SeqCst));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn check_fails_when_element_stays_stale() {
+        let error = assertion(false)
+            .check(|| async { Err(webdriver_error(ErrorStatus::StaleElementReference)) })
+            .await
+            .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .ends_with("actual:   <webdriver returned error: element is gone>"));
+    }
+
+    #[tokio::test]
+    async fn check_fails_on_other_errors() {
+        let error = Assertion {
+            timeout: Duration::from_secs(5),
+            ..assertion(false)
+        }
+        .check(|| async { Err(webdriver_error(ErrorStatus::InvalidSelector)) })
+        .await
+        .unwrap_err();
+
+        assert_eq!(
+            "webdriver returned error: element is gone",
+            error.to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn check_fails_with_negated_message() {
+        let error = assertion(true)
+            .check(|| async { Ok(Observation::new(true, "Hello")) })
+            .await
+            .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .starts_with("expected element with css `h1` not to contain text"));
+    }
+}
@@ -0,0 +1,60 @@
+//! Fixtures that are passed to tests as arguments
+
+use std::future::Future;
+
+use crate::{Client, Result, TestContext};
+
+/// Fixture that is passed to a test as an argument
+///
+/// Tests declare the resources that they need as arguments, and Doco sets them up from the
+/// [`TestContext`] before the test is called. The WebDriver [`Client`] is the most common fixture,
+/// but any type that implements this trait can be requested by a test.
+///
+/// # Example
+///
+/// ```no_run
+/// use doco::{Client, Fixture, Result, TestContext};
+///
+/// struct Homepage(Client);
+///
+/// impl Fixture for Homepage {
+///     async fn setup(context: &TestContext) -> Result<Self> {
+///         let client = context.client().clone();
+///         client.goto("/").await?;
+///
+///         Ok(Homepage(client))
+///     }
+/// }
+///
+/// #[doco::test]
+/// async fn visit_homepage(homepage: Homepage) -> Result<()> {
+///     let body = homepage.0.source().await?;
+///
+///     assert!(body.contains("Hello World"));
+///
+///     Ok(())
+/// }
+/// #
+/// # use doco::{Doco, Server};
+/// #
+/// # #[doco::main]
+/// # async fn main() -> Doco {
+/// #    let server = Server::builder()
+/// #        .image("crccheck/hello-world")
+/// #        .tag("v1.0.0")
+/// #        .port(8000)
+/// #        .build();
+/// #
+/// #    Doco::builder().server(server).build()
+/// # }
+/// ```
+pub trait Fixture: Sized {
+    /// Set up the fixture for the test with the given context
+    fn setup(context: &TestContext) -> impl Future<Output = Result<Self>>;
+}
+
+impl Fixture for Client {
+    async fn setup(context: &TestContext) -> Result<Self> {
+        Ok(context.client().clone())
+    }
+}
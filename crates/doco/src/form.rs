@@ -0,0 +1,469 @@
+//! Helpers to fill and submit forms
+
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use fantoccini::elements::Element;
+use fantoccini::Locator;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::by::literal;
+use crate::{By, Client, Result};
+
+/// Script that puts a file into an `<input type="file">`
+///
+/// The browser runs inside a container and cannot read files from the host. The content of the
+/// file is therefore passed to the script, which creates the file in the browser and assigns it to
+/// the input. Afterwards, the events are dispatched that the browser would dispatch for a user.
+const UPLOAD: &str = r#"
+const [input, name, content] = arguments;
+const bytes = Uint8Array.from(atob(content), (c) => c.charCodeAt(0));
+const transfer = new DataTransfer();
+transfer.items.add(new File([bytes], name));
+input.files = transfer.files;
+input.dispatchEvent(new Event("input", { bubbles: true }));
+input.dispatchEvent(new Event("change", { bubbles: true }));
+"#;
+
+/// Script that submits the form that a field belongs to
+const SUBMIT: &str = r#"
+const [field] = arguments;
+if (!field.form) throw new Error("field does not belong to a form");
+field.form.requestSubmit();
+"#;
+
+/// Value for a single form field
+#[derive(Clone, Eq, PartialEq, Debug)]
+enum FieldValue {
+    /// Text that is typed into an input or selected in a dropdown
+    Text(String),
+
+    /// Whether a checkbox is checked
+    Checked(bool),
+}
+
+impl FieldValue {
+    /// Convert a serialized value into a field value
+    ///
+    /// Returns `None` for `null`, which leaves the field untouched.
+    fn from_json(field: &str, value: Value) -> Result<Option<Self>> {
+        let value = match value {
+            Value::Null => return Ok(None),
+            Value::Bool(checked) => FieldValue::Checked(checked),
+            Value::Number(number) => FieldValue::Text(number.to_string()),
+            Value::String(text) => FieldValue::Text(text),
+            Value::Array(_) | Value::Object(_) => {
+                bail!(
+                    "unsupported value for field `{field}`, expected a string, number, or boolean"
+                )
+            }
+        };
+
+        Ok(Some(value))
+    }
+}
+
+/// Kind of a form field, determined by its tag name and type
+#[derive(Clone, Eq, PartialEq, Debug)]
+struct FieldKind {
+    /// The tag name of the element, e.g. `input`
+    tag: String,
+
+    /// The `type` attribute of the element, e.g. `checkbox`
+    kind: Option<String>,
+}
+
+impl FieldKind {
+    /// Determine the kind of the given element
+    async fn of(element: &Element) -> Result<Self> {
+        Ok(Self {
+            tag: element.tag_name().await?.to_lowercase(),
+            kind: element.attr("type").await?.map(|kind| kind.to_lowercase()),
+        })
+    }
+
+    /// Check if the element is an input with the given type
+    fn is_input(&self, kind: &str) -> bool {
+        self.tag == "input" && self.kind.as_deref() == Some(kind)
+    }
+
+    /// Check if the element is a dropdown
+    fn is_select(&self) -> bool {
+        self.tag == "select"
+    }
+}
+
+impl std::fmt::Display for FieldKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            Some(kind) => write!(f, "<{} type=\"{kind}\">", self.tag),
+            None => write!(f, "<{}>", self.tag),
+        }
+    }
+}
+
+/// Serialize the values of a form into its fields in the order in which they are serialized
+///
+/// Fields with a value of `null` are skipped, since they are left untouched.
+fn form_values<T: Serialize + ?Sized>(values: &T) -> Result<Vec<(String, FieldValue)>> {
+    let Value::Object(values) = serde_json::to_value(values)? else {
+        bail!("form values must serialize to a map");
+    };
+
+    let mut fields = Vec::with_capacity(values.len());
+    for (field, value) in values {
+        if let Some(value) = FieldValue::from_json(&field, value)? {
+            fields.push((field, value));
+        }
+    }
+
+    Ok(fields)
+}
+
+impl Client {
+    /// Fill a form with the given values
+    ///
+    /// The values can be any type that serializes to a map, for example a `HashMap` or a struct
+    /// that derives `serde::Serialize`. Each key identifies a field by its `name` attribute or by
+    /// the text of its label. Strings and numbers are typed into inputs, select an option in
+    /// dropdowns, or pick the radio button with that value. Booleans check or uncheck checkboxes.
+    /// Fields with a value of `None` are left untouched.
+    ///
+    /// The fields are filled in the order in which they are serialized, i.e. in the order in which
+    /// the fields of a struct are declared. This way, fields that depend on each other can be
+    /// filled in the right order, e.g. a country before its state. Maps like `HashMap` have no
+    /// stable order, so a struct or an ordered map should be used for such forms.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use doco::{Client, Result};
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct SignUp {
+    ///     email: &'static str,
+    ///     country: &'static str,
+    ///     newsletter: bool,
+    /// }
+    ///
+    /// async fn signs_up(client: Client) -> Result<()> {
+    ///     client.goto("/signup").await?;
+    ///
+    ///     let form = SignUp {
+    ///         email: "user@example.com",
+    ///         country: "Germany",
+    ///         newsletter: true,
+    ///     };
+    ///
+    ///     client.fill_form(&form).await?;
+    ///     client.submit("email").await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn fill_form<T: Serialize + ?Sized>(&self, values: &T) -> Result<()> {
+        for (field, value) in form_values(values)? {
+            match value {
+                FieldValue::Text(text) => self.fill(&field, &text).await?,
+                FieldValue::Checked(true) => self.check(&field).await?,
+                FieldValue::Checked(false) => self.uncheck(&field).await?,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fill a single field with the given value
+    ///
+    /// The field is identified by its `name` attribute or by the text of its label. Text inputs
+    /// are cleared before the value is typed, dropdowns select the option with the value as its
+    /// label or value, and radio buttons pick the button with the value.
+    pub async fn fill(&self, field: &str, value: &str) -> Result<()> {
+        let element = self.field(field).await?;
+        let kind = FieldKind::of(&element).await?;
+
+        if kind.is_select() {
+            return self.select_option(field, value).await;
+        }
+
+        if kind.is_input("radio") {
+            // The field might have been found by its label, so the group is found by its name
+            let name = element
+                .attr("name")
+                .await?
+                .with_context(|| format!("radio button `{field}` has no `name` attribute"))?;
+            let radio = format!(
+                "//input[@type='radio' and @name={} and @value={}]",
+                literal(&name),
+                literal(value)
+            );
+
+            let radio = self.find(Locator::XPath(&radio)).await.with_context(|| {
+                format!("field `{field}` has no radio button with value `{value}`")
+            })?;
+
+            return Ok(radio.click().await?);
+        }
+
+        if kind.is_input("checkbox") || kind.is_input("file") {
+            bail!("field `{field}` is a {kind}, which cannot be filled with text");
+        }
+
+        element.clear().await?;
+        element.send_keys(value).await?;
+
+        Ok(())
+    }
+
+    /// Select an option in a dropdown by its label or value
+    pub async fn select_option(&self, field: &str, option: &str) -> Result<()> {
+        let element = self.field(field).await?;
+        let kind = FieldKind::of(&element).await?;
+
+        if !kind.is_select() {
+            bail!("field `{field}` is a {kind}, expected a <select>");
+        }
+
+        match element.select_by_label(option).await {
+            Ok(()) => return Ok(()),
+            Err(error) if error.is_no_such_element() => {}
+            Err(error) => return Err(error.into()),
+        }
+
+        match element.select_by_value(option).await {
+            Ok(()) => Ok(()),
+            Err(error) if error.is_no_such_element() => Err(anyhow!(
+                "field `{field}` has no option with the label or value `{option}`"
+            )),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Check a checkbox or radio button
+    pub async fn check(&self, field: &str) -> Result<()> {
+        self.set_checked(field, true).await
+    }
+
+    /// Uncheck a checkbox
+    pub async fn uncheck(&self, field: &str) -> Result<()> {
+        self.set_checked(field, false).await
+    }
+
+    /// Upload a local file into an `<input type="file">`
+    ///
+    /// The file is read on the host and transferred into the browser, so the path does not need to
+    /// exist inside the Selenium container.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use doco::{Client, Result};
+    ///
+    /// async fn uploads_avatar(client: Client) -> Result<()> {
+    ///     client.goto("/profile").await?;
+    ///
+    ///     client.upload("Avatar", "tests/fixtures/avatar.png").await?;
+    ///     client.submit("Avatar").await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn upload(&self, field: &str, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+
+        let element = self.field(field).await?;
+        let kind = FieldKind::of(&element).await?;
+
+        if !kind.is_input("file") {
+            bail!("field `{field}` is a {kind}, expected an <input type=\"file\">");
+        }
+
+        let content = std::fs::read(path)
+            .with_context(|| format!("failed to read file {}", path.display()))?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        self.execute(
+            UPLOAD,
+            vec![
+                serde_json::to_value(&element)?,
+                Value::String(name),
+                Value::String(BASE64.encode(content)),
+            ],
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Submit the form that the given field belongs to
+    ///
+    /// The form is submitted as if the user had clicked its submit button, which means that the
+    /// browser validates the form and dispatches a `submit` event first.
+    pub async fn submit(&self, field: &str) -> Result<()> {
+        let element = self.field(field).await?;
+
+        self.execute(SUBMIT, vec![serde_json::to_value(&element)?])
+            .await
+            .with_context(|| format!("failed to submit the form of field `{field}`"))?;
+
+        Ok(())
+    }
+
+    /// Set the checked state of a checkbox or radio button
+    async fn set_checked(&self, field: &str, checked: bool) -> Result<()> {
+        let element = self.field(field).await?;
+        let kind = FieldKind::of(&element).await?;
+
+        if kind.is_input("radio") && !checked {
+            bail!("field `{field}` is a radio button, which cannot be unchecked");
+        }
+
+        if !kind.is_input("checkbox") && !kind.is_input("radio") {
+            bail!("field `{field}` is a {kind}, expected a checkbox or radio button");
+        }
+
+        if element.is_selected().await? != checked {
+            element.click().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Find a form field by its name or the text of its label
+    async fn field(&self, field: &str) -> Result<Element> {
+        let by_name = format!(
+            "//*[(self::input or self::select or self::textarea) and @name={}]",
+            literal(field)
+        );
+
+        if let Some(element) = self
+            .find_all(Locator::XPath(&by_name))
+            .await?
+            .into_iter()
+            .next()
+        {
+            return Ok(element);
+        }
+
+        if let Some(element) = self
+            .find_all(By::label(field).locator())
+            .await?
+            .into_iter()
+            .next()
+        {
+            return Ok(element);
+        }
+
+        bail!("failed to find form field with the name or label `{field}`")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn field_value_from_string() -> Result<()> {
+        let value = FieldValue::from_json("email", json!("user@example.com"))?;
+
+        assert_eq!(
+            Some(FieldValue::Text(String::from("user@example.com"))),
+            value
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn field_value_from_number() -> Result<()> {
+        let value = FieldValue::from_json("age", json!(42))?;
+
+        assert_eq!(Some(FieldValue::Text(String::from("42"))), value);
+
+        Ok(())
+    }
+
+    #[test]
+    fn field_value_from_bool() -> Result<()> {
+        let value = FieldValue::from_json("newsletter", json!(true))?;
+
+        assert_eq!(Some(FieldValue::Checked(true)), value);
+
+        Ok(())
+    }
+
+    #[test]
+    fn field_value_from_null() -> Result<()> {
+        assert_eq!(None, FieldValue::from_json("nickname", Value::Null)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn field_value_from_array() {
+        let error = FieldValue::from_json("tags", json!(["a", "b"])).unwrap_err();
+
+        assert!(error
+            .to_string()
+            .contains("unsupported value for field `tags`"));
+    }
+
+    #[test]
+    fn form_values_keep_declaration_order() -> Result<()> {
+        #[derive(Serialize)]
+        struct Address {
+            country: &'static str,
+            state: &'static str,
+            city: Option<&'static str>,
+            billing: bool,
+        }
+
+        let fields = form_values(&Address {
+            country: "United States",
+            state: "Oregon",
+            city: None,
+            billing: true,
+        })?;
+
+        assert_eq!(
+            vec![
+                (
+                    String::from("country"),
+                    FieldValue::Text(String::from("United States"))
+                ),
+                (
+                    String::from("state"),
+                    FieldValue::Text(String::from("Oregon"))
+                ),
+                (String::from("billing"), FieldValue::Checked(true)),
+            ],
+            fields
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn form_values_must_be_a_map() {
+        assert!(form_values(&["email"]).is_err());
+    }
+
+    #[test]
+    fn field_kind_display() {
+        let kind = FieldKind {
+            tag: String::from("input"),
+            kind: Some(String::from("checkbox")),
+        };
+
+        assert_eq!("<input type=\"checkbox\">", kind.to_string());
+    }
+}
@@ -0,0 +1,45 @@
+//! Helpers for writing files to the snapshot and artifacts directories
+
+use std::path::Path;
+
+use anyhow::Context;
+
+use crate::Result;
+
+/// Write the content to the file at the given path, creating its parent directories
+pub(crate) fn write(path: &Path, content: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create directory {}", parent.display()))?;
+    }
+
+    std::fs::write(path, content).with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_creates_parent_directories() {
+        let directory = std::env::temp_dir().join(format!("doco-fs-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        let path = directory.join("login").join("network.har");
+
+        write(&path, b"{}").unwrap();
+
+        assert_eq!(b"{}".to_vec(), std::fs::read(&path).unwrap());
+    }
+
+    #[test]
+    fn write_names_file_in_error() {
+        let directory = std::env::temp_dir().join(format!("doco-fs-error-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(directory.join("junit.xml")).unwrap();
+
+        let error = write(&directory.join("junit.xml"), b"<testsuites/>").unwrap_err();
+
+        assert!(error.to_string().starts_with("failed to write "));
+        assert!(error.to_string().ends_with("junit.xml"));
+    }
+}
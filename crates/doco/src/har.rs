@@ -0,0 +1,197 @@
+//! Export of recorded network traffic in the HTTP Archive (HAR) format
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Value};
+
+use crate::network::Exchange;
+
+/// Convert the exchanges into an HTTP Archive (HAR) 1.2 document
+///
+/// Requests that have not received a response are included with a status of `0`, which is how
+/// browsers export requests that were aborted.
+pub(crate) fn har(exchanges: &[Exchange]) -> Value {
+    let entries: Vec<Value> = exchanges.iter().map(entry).collect();
+
+    json!({
+        "log": {
+            "version": "1.2",
+            "creator": {
+                "name": "doco",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "entries": entries,
+        }
+    })
+}
+
+/// Convert a single exchange into a HAR entry
+fn entry(exchange: &Exchange) -> Value {
+    let request = exchange.request();
+    let time = exchange
+        .duration()
+        .map_or(0.0, |duration| duration.as_secs_f64() * 1000.0);
+
+    let mut har_request = json!({
+        "method": request.method(),
+        "url": request.url(),
+        "httpVersion": "HTTP/1.1",
+        "cookies": [],
+        "headers": headers(request.headers()),
+        "queryString": query(request.url()),
+        "headersSize": -1,
+        "bodySize": request.body().len(),
+    });
+
+    if !request.body().is_empty() {
+        har_request["postData"] = json!({
+            "mimeType": request.header("content-type").unwrap_or_default(),
+            "text": request.text(),
+        });
+    }
+
+    let har_response = match exchange.response() {
+        Some(response) => {
+            let mime_type = response.header("content-type").unwrap_or_default();
+            let mut content = json!({
+                "size": response.body().len(),
+                "mimeType": mime_type,
+            });
+
+            match std::str::from_utf8(response.body()) {
+                Ok(text) => content["text"] = json!(text),
+                Err(_) => {
+                    content["text"] = json!(BASE64.encode(response.body()));
+                    content["encoding"] = json!("base64");
+                }
+            }
+
+            json!({
+                "status": response.status(),
+                "statusText": "",
+                "httpVersion": "HTTP/1.1",
+                "cookies": [],
+                "headers": headers(response.headers()),
+                "content": content,
+                "redirectURL": response.header("location").unwrap_or_default(),
+                "headersSize": -1,
+                "bodySize": response.body().len(),
+            })
+        }
+        None => json!({
+            "status": 0,
+            "statusText": "",
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": [],
+            "content": { "size": 0, "mimeType": "" },
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": -1,
+        }),
+    };
+
+    json!({
+        "startedDateTime": DateTime::<Utc>::from(exchange.started())
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+        "time": time,
+        "request": har_request,
+        "response": har_response,
+        "cache": {},
+        "timings": {
+            "send": 0,
+            "wait": time,
+            "receive": 0,
+        },
+        "comment": if exchange.stubbed() { "stubbed by doco" } else { "" },
+    })
+}
+
+/// Convert headers into HAR name-value pairs
+fn headers(headers: &[(String, String)]) -> Vec<Value> {
+    headers
+        .iter()
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect()
+}
+
+/// Extract the query parameters of a URL as HAR name-value pairs
+fn query(url: &str) -> Vec<Value> {
+    reqwest::Url::parse(url)
+        .map(|url| {
+            url.query_pairs()
+                .map(|(name, value)| json!({ "name": name, "value": value }))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::network::{CapturedRequest, CapturedResponse, Network};
+
+    use super::*;
+
+    #[test]
+    fn har_contains_entries() {
+        let network = Network::new(Duration::from_secs(1));
+
+        let index = network.record_request(
+            CapturedRequest::new(
+                String::from("POST"),
+                String::from("http://app/api/todos?page=2"),
+                vec![(
+                    String::from("content-type"),
+                    String::from("application/json"),
+                )],
+                br#"{"title":"Test"}"#.to_vec(),
+            ),
+            false,
+        );
+        network.record_response(
+            index,
+            CapturedResponse::new(201, Vec::new(), b"created".to_vec()),
+        );
+
+        let har = har(&network.exchanges());
+        let entry = &har["log"]["entries"][0];
+
+        assert_eq!("1.2", har["log"]["version"]);
+        assert!(entry["startedDateTime"]
+            .as_str()
+            .is_some_and(|time| DateTime::parse_from_rfc3339(time).is_ok() && time.ends_with('Z')));
+        assert_eq!("POST", entry["request"]["method"]);
+        assert_eq!("page", entry["request"]["queryString"][0]["name"]);
+        assert_eq!(r#"{"title":"Test"}"#, entry["request"]["postData"]["text"]);
+        assert_eq!(201, entry["response"]["status"]);
+        assert_eq!("created", entry["response"]["content"]["text"]);
+    }
+
+    #[test]
+    fn har_encodes_binary_bodies() {
+        let network = Network::new(Duration::from_secs(1));
+
+        let index = network.record_request(
+            CapturedRequest::new(
+                String::from("GET"),
+                String::from("http://app/logo.png"),
+                Vec::new(),
+                Vec::new(),
+            ),
+            false,
+        );
+        network.record_response(
+            index,
+            CapturedResponse::new(200, Vec::new(), vec![0xff, 0xfe]),
+        );
+
+        let har = har(&network.exchanges());
+        let content = &har["log"]["entries"][0]["response"]["content"];
+
+        assert_eq!("base64", content["encoding"]);
+        assert_eq!("//4=", content["text"]);
+    }
+}
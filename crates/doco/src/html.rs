@@ -0,0 +1,651 @@
+//! Snapshots of the rendered HTML that are compared against baselines
+
+use anyhow::bail;
+use fantoccini::Locator;
+use getset::{CopyGetters, Getters};
+use typed_builder::TypedBuilder;
+
+use crate::{ArtifactKind, Client, Result};
+
+/// Elements that never have children or a closing tag
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Elements whose content is text that is not parsed as HTML
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style", "textarea", "title"];
+
+/// Elements in which whitespace is significant, so that their text is kept verbatim
+const PREFORMATTED_ELEMENTS: &[&str] = &["pre", "textarea"];
+
+/// The maximum number of lines for which a diff is printed when a snapshot does not match
+const MAX_DIFF_LINES: usize = 2_000;
+
+/// Options for comparing the HTML of a page or element against its snapshot
+///
+/// The HTML is normalized before it is compared, so that the snapshot does not change when only
+/// the formatting of the markup changes: each tag and each text is put on its own, indented line,
+/// attributes are sorted, and runs of whitespace are collapsed. Text inside `pre` and `textarea`
+/// elements is kept verbatim, since its whitespace is shown to the user. Comments are removed by
+/// default.
+///
+/// Attributes whose values change from run to run, e.g. the hydration keys that Leptos renders as
+/// `data-hk`, can be stripped from the snapshot. Attribute names that end with `*` strip all
+/// attributes with that prefix.
+///
+/// # Example
+///
+/// ```no_run
+/// use doco::{Client, HtmlSnapshotOptions, Locator, Result};
+///
+/// async fn renders_counter(client: Client) -> Result<()> {
+///     client.goto("/").await?;
+///
+///     let options = HtmlSnapshotOptions::builder()
+///         .strip_attribute("data-hk")
+///         .strip_attribute("data-v-*")
+///         .build();
+///
+///     client
+///         .assert_element_html_snapshot(Locator::Css("main"), "counter", &options)
+///         .await
+/// }
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug, CopyGetters, Getters, TypedBuilder)]
+pub struct HtmlSnapshotOptions {
+    /// The attributes that are removed from the snapshot, e.g. `data-hk`
+    #[builder(via_mutators(init = Vec::new()), mutators(
+        pub fn strip_attribute(mut self, attribute: impl Into<String>) {
+            self.stripped_attributes.push(attribute.into());
+        }
+    ))]
+    #[getset(get = "pub")]
+    stripped_attributes: Vec<String>,
+
+    /// Whether comments are removed from the snapshot, e.g. `true`
+    #[builder(default = true)]
+    #[getset(get_copy = "pub")]
+    strip_comments: bool,
+}
+
+impl HtmlSnapshotOptions {
+    /// Check if the attribute with the given name is removed from the snapshot
+    fn strips(&self, attribute: &str) -> bool {
+        self.stripped_attributes
+            .iter()
+            .any(|stripped| match stripped.strip_suffix('*') {
+                Some(prefix) => attribute.starts_with(prefix),
+                None => attribute == stripped,
+            })
+    }
+}
+
+impl Default for HtmlSnapshotOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl Client {
+    /// Compare the normalized HTML of the page against its snapshot
+    ///
+    /// Snapshots are stored per test in the snapshot directory of the test suite, e.g.
+    /// `tests/snapshots/<test>/<name>.html`, and should be committed to version control. If the
+    /// snapshot does not exist yet, it is created and the assertion fails.
+    ///
+    /// When the HTML does not match, the differences are printed and the actual HTML is saved to
+    /// the test's artifacts directory for review. Run the tests with `--update-snapshots` or with
+    /// the environment variable `DOCO_UPDATE_SNAPSHOTS=1` to accept the changes and rewrite the
+    /// snapshots.
+    pub async fn assert_html_snapshot(
+        &self,
+        name: &str,
+        options: &HtmlSnapshotOptions,
+    ) -> Result<()> {
+        let html = self.source().await?;
+        self.compare_html_snapshot(name, &normalize(&html, options))
+    }
+
+    /// Compare the normalized outer HTML of an element against its snapshot
+    ///
+    /// The element is waited for like with [`Client::wait_for_element`]. Otherwise, the HTML is
+    /// compared just like with [`Client::assert_html_snapshot`].
+    pub async fn assert_element_html_snapshot<'l>(
+        &self,
+        locator: impl Into<Locator<'l>>,
+        name: &str,
+        options: &HtmlSnapshotOptions,
+    ) -> Result<()> {
+        let html = self.wait_for_element(locator).await?.html(false).await?;
+        self.compare_html_snapshot(name, &normalize(&html, options))
+    }
+
+    /// Compare normalized HTML against its snapshot and save it as an artifact on a mismatch
+    fn compare_html_snapshot(&self, name: &str, html: &str) -> Result<()> {
+        let snapshots = self.snapshots();
+        let file = format!("{name}.html");
+
+        let Some(expected) =
+            snapshots.read_baseline(&format!("HTML snapshot `{name}`"), &file, html.as_bytes())?
+        else {
+            return Ok(());
+        };
+        let expected = String::from_utf8_lossy(&expected);
+
+        if expected == html {
+            return Ok(());
+        }
+
+        let actual = snapshots.save_artifact(
+            ArtifactKind::HtmlSnapshot,
+            &format!("{name}.actual.html"),
+            html.as_bytes(),
+        )?;
+
+        bail!(
+            "HTML snapshot `{name}` does not match its baseline\n  baseline: {}\n  actual:   {}\n\n{}",
+            snapshots.baseline(&file).display(),
+            actual.display(),
+            diff(&expected, html)
+        )
+    }
+}
+
+/// Token in an HTML document
+#[derive(Clone, Eq, PartialEq, Debug)]
+enum Token<'a> {
+    /// The doctype declaration, e.g. `<!DOCTYPE html>`
+    Doctype(&'a str),
+
+    /// An opening tag with its name and attributes
+    Open {
+        /// The name of the tag in lowercase
+        name: String,
+
+        /// The attributes of the tag, with their values as they appear in the source
+        attributes: Vec<(String, Option<&'a str>)>,
+
+        /// Whether the tag is closed with `/>`
+        self_closing: bool,
+    },
+
+    /// A closing tag with its name in lowercase
+    Close(String),
+
+    /// The text between tags
+    Text(&'a str),
+
+    /// The content of a comment
+    Comment(&'a str),
+}
+
+/// Split HTML into tokens
+///
+/// The tokenizer expects HTML that the browser has serialized, i.e. quoted attribute values,
+/// escaped text, and a closing tag for every element that is not void. It does not validate the
+/// HTML and never fails: a `<` that does not start a tag is kept as text, and markup that is cut
+/// off at the end of the input, e.g. an unterminated comment or attribute value, extends to the
+/// end of the input.
+fn tokenize(html: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            let end = comment.find("-->").unwrap_or(comment.len());
+            tokens.push(Token::Comment(&comment[..end]));
+            rest = comment.get(end + 3..).unwrap_or_default();
+        } else if rest.starts_with("<!") {
+            let end = rest.find('>').map_or(rest.len(), |end| end + 1);
+            tokens.push(Token::Doctype(&rest[..end]));
+            rest = &rest[end..];
+        } else if let Some(tag) = rest
+            .strip_prefix("</")
+            .filter(|tag| tag.starts_with(|c: char| c.is_ascii_alphabetic()))
+        {
+            let end = tag.find('>').unwrap_or(tag.len());
+            tokens.push(Token::Close(tag[..end].trim().to_lowercase()));
+            rest = tag.get(end + 1..).unwrap_or_default();
+        } else if starts_tag(rest) && !rest.starts_with("</") {
+            let (token, remainder) = open_tag(&rest[1..]);
+
+            if let Token::Open { name, .. } = &token {
+                if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+                    let closing = format!("</{name}");
+                    let end = remainder
+                        .to_ascii_lowercase()
+                        .find(&closing)
+                        .unwrap_or(remainder.len());
+
+                    tokens.push(token);
+                    tokens.push(Token::Text(&remainder[..end]));
+                    rest = &remainder[end..];
+                    continue;
+                }
+            }
+
+            tokens.push(token);
+            rest = remainder;
+        } else {
+            // A `<` that does not start a tag or comment is part of the text
+            let start = rest.chars().next().map_or(0, char::len_utf8);
+            let end = rest[start..]
+                .match_indices('<')
+                .map(|(index, _)| index + start)
+                .find(|&index| starts_tag(&rest[index..]))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Text(&rest[..end]));
+            rest = &rest[end..];
+        }
+    }
+
+    tokens
+}
+
+/// Check if the input starts with a tag, e.g. `<p` or `</p`, or with a comment or doctype
+fn starts_tag(input: &str) -> bool {
+    let Some(rest) = input.strip_prefix('<') else {
+        return false;
+    };
+    let rest = rest.strip_prefix('/').unwrap_or(rest);
+
+    rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '!')
+}
+
+/// Parse an opening tag after its `<` and return the rest of the input
+fn open_tag(input: &str) -> (Token<'_>, &str) {
+    let name_end = input
+        .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+        .unwrap_or(input.len());
+    let name = input[..name_end].to_lowercase();
+
+    let mut rest = &input[name_end..];
+    let mut attributes = Vec::new();
+    let mut self_closing = false;
+
+    loop {
+        rest = rest.trim_start();
+
+        if let Some(remainder) = rest.strip_prefix("/>") {
+            self_closing = true;
+            rest = remainder;
+            break;
+        }
+        if let Some(remainder) = rest.strip_prefix('>') {
+            rest = remainder;
+            break;
+        }
+        if let Some(remainder) = rest.strip_prefix('/') {
+            rest = remainder;
+            continue;
+        }
+        if rest.is_empty() {
+            break;
+        }
+
+        let attribute_end = rest
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '>' || c == '/')
+            .unwrap_or(rest.len());
+        let attribute = rest[..attribute_end].to_lowercase();
+        rest = rest[attribute_end..].trim_start();
+
+        let value = match rest.strip_prefix('=') {
+            Some(value) => {
+                let value = value.trim_start();
+                let (value, remainder) = match value.chars().next() {
+                    Some(quote @ ('"' | '\'')) => {
+                        let end = value[1..].find(quote).map_or(value.len(), |end| end + 1);
+                        (&value[1..end], value.get(end + 1..).unwrap_or_default())
+                    }
+                    _ => {
+                        let end = value
+                            .find(|c: char| c.is_whitespace() || c == '>')
+                            .unwrap_or(value.len());
+                        (&value[..end], &value[end..])
+                    }
+                };
+
+                rest = remainder;
+                Some(value)
+            }
+            None => None,
+        };
+
+        attributes.push((attribute, value));
+    }
+
+    let token = Token::Open {
+        name,
+        attributes,
+        self_closing,
+    };
+
+    (token, rest)
+}
+
+/// Normalize HTML so that only changes to its content change the snapshot
+fn normalize(html: &str, options: &HtmlSnapshotOptions) -> String {
+    let mut output = String::new();
+    let mut depth: usize = 0;
+    let mut preformatted: usize = 0;
+
+    let mut line = |depth: usize, content: &str| {
+        output.push_str(&"  ".repeat(depth));
+        output.push_str(content);
+        output.push('\n');
+    };
+
+    for token in tokenize(html) {
+        match token {
+            Token::Doctype(doctype) => line(depth, doctype),
+            Token::Comment(comment) => {
+                if !options.strip_comments() {
+                    line(depth, &format!("<!--{}-->", collapse(comment)));
+                }
+            }
+            Token::Text(text) if preformatted > 0 => {
+                if !text.is_empty() {
+                    line(depth, text);
+                }
+            }
+            Token::Text(text) => {
+                let text = collapse(text);
+                if !text.is_empty() {
+                    line(depth, &text);
+                }
+            }
+            Token::Open {
+                name,
+                mut attributes,
+                self_closing,
+            } => {
+                attributes.retain(|(attribute, _)| !options.strips(attribute));
+                attributes.sort();
+
+                let mut tag = format!("<{name}");
+                for (attribute, value) in attributes {
+                    match value {
+                        Some(value) => tag.push_str(&format!(" {attribute}=\"{value}\"")),
+                        None => tag.push_str(&format!(" {attribute}")),
+                    }
+                }
+                tag.push('>');
+
+                line(depth, &tag);
+
+                if !self_closing && !VOID_ELEMENTS.contains(&name.as_str()) {
+                    depth += 1;
+
+                    if PREFORMATTED_ELEMENTS.contains(&name.as_str()) {
+                        preformatted += 1;
+                    }
+                }
+            }
+            Token::Close(name) => {
+                if !VOID_ELEMENTS.contains(&name.as_str()) {
+                    depth = depth.saturating_sub(1);
+                    line(depth, &format!("</{name}>"));
+                }
+                if PREFORMATTED_ELEMENTS.contains(&name.as_str()) {
+                    preformatted = preformatted.saturating_sub(1);
+                }
+            }
+        }
+    }
+
+    output
+}
+
+/// Collapse runs of whitespace into a single space and trim the text
+fn collapse(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Show the lines that differ between two texts, prefixed with `-` and `+`
+///
+/// The diff is based on the longest common subsequence of the lines. For very large texts, only the
+/// number of lines is reported to keep the comparison fast.
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+
+    if expected.len() > MAX_DIFF_LINES || actual.len() > MAX_DIFF_LINES {
+        return format!(
+            "the snapshots are too large to show a diff ({} and {} lines)",
+            expected.len(),
+            actual.len()
+        );
+    }
+
+    // Length of the longest common subsequence of the suffixes starting at each pair of lines
+    let mut lengths = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lengths[i][j] = if expected[i] == actual[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut output = String::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            i += 1;
+            j += 1;
+        } else if i < expected.len()
+            && (j == actual.len() || lengths[i + 1][j] >= lengths[i][j + 1])
+        {
+            output.push_str(&format!("- {}\n", expected[i]));
+            i += 1;
+        } else {
+            output.push_str(&format!("+ {}\n", actual[j]));
+            j += 1;
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    use super::*;
+
+    #[test]
+    fn normalize_indents_elements() {
+        let html = "<main><h1>Title</h1><p>Some\n   text</p></main>";
+
+        assert_eq!(
+            "<main>\n  <h1>\n    Title\n  </h1>\n  <p>\n    Some text\n  </p>\n</main>\n",
+            normalize(html, &HtmlSnapshotOptions::default())
+        );
+    }
+
+    #[test]
+    fn normalize_sorts_attributes() {
+        let html = r#"<input type="text" name="email" disabled="">"#;
+
+        assert_eq!(
+            "<input disabled=\"\" name=\"email\" type=\"text\">\n",
+            normalize(html, &HtmlSnapshotOptions::default())
+        );
+    }
+
+    #[test]
+    fn normalize_strips_attributes() {
+        let html = r#"<div data-hk="0-1" data-v-123="" class="counter"></div>"#;
+        let options = HtmlSnapshotOptions::builder()
+            .strip_attribute("data-hk")
+            .strip_attribute("data-v-*")
+            .build();
+
+        assert_eq!(
+            "<div class=\"counter\">\n</div>\n",
+            normalize(html, &options)
+        );
+    }
+
+    #[test]
+    fn normalize_strips_comments() {
+        let html = "<p><!--hk=0-1-->Text<!--/--></p>";
+
+        assert_eq!(
+            "<p>\n  Text\n</p>\n",
+            normalize(html, &HtmlSnapshotOptions::default())
+        );
+    }
+
+    #[test]
+    fn normalize_keeps_comments() {
+        let html = "<p><!-- note --></p>";
+        let options = HtmlSnapshotOptions::builder().strip_comments(false).build();
+
+        assert_eq!("<p>\n  <!--note-->\n</p>\n", normalize(html, &options));
+    }
+
+    #[test]
+    fn normalize_keeps_scripts_as_text() {
+        let html = "<script>if (a < b) { run(); }</script>";
+
+        assert_eq!(
+            "<script>\n  if (a < b) { run(); }\n</script>\n",
+            normalize(html, &HtmlSnapshotOptions::default())
+        );
+    }
+
+    #[test]
+    fn normalize_keeps_preformatted_text_verbatim() {
+        let html = "<pre>fn main() {\n    <b>run</b>();\n}</pre><textarea>  two  spaces</textarea><p>a  b</p>";
+
+        assert_eq!(
+            "<pre>\n  fn main() {\n    \n  <b>\n    run\n  </b>\n  ();\n}\n</pre>\n<textarea>\n    two  spaces\n</textarea>\n<p>\n  a b\n</p>\n",
+            normalize(html, &HtmlSnapshotOptions::default())
+        );
+    }
+
+    #[test]
+    fn normalize_keeps_unicode_text() {
+        let html = "<p>Grüße &amp; 👋</p>";
+
+        assert_eq!(
+            "<p>\n  Grüße &amp; 👋\n</p>\n",
+            normalize(html, &HtmlSnapshotOptions::default())
+        );
+    }
+
+    #[test]
+    fn normalize_keeps_less_than_in_text() {
+        let html = "<p>a < b and c <= d</p>";
+
+        assert_eq!(
+            "<p>\n  a < b and c <= d\n</p>\n",
+            normalize(html, &HtmlSnapshotOptions::default())
+        );
+    }
+
+    #[test]
+    fn normalize_lowercases_tags_and_attributes() {
+        let html = r#"<DIV CLASS="a">Text</DIV>"#;
+
+        assert_eq!(
+            "<div class=\"a\">\n  Text\n</div>\n",
+            normalize(html, &HtmlSnapshotOptions::default())
+        );
+    }
+
+    #[test]
+    fn normalize_quotes_unquoted_and_empty_values() {
+        let html = "<input value=abc disabled><p class=>Text</p>";
+
+        assert_eq!(
+            "<input disabled value=\"abc\">\n<p class=\"\">\n  Text\n</p>\n",
+            normalize(html, &HtmlSnapshotOptions::default())
+        );
+    }
+
+    #[test]
+    fn normalize_unclosed_element_at_end() {
+        let html = "<main><p>Text";
+
+        assert_eq!(
+            "<main>\n  <p>\n    Text\n",
+            normalize(html, &HtmlSnapshotOptions::default())
+        );
+    }
+
+    #[test]
+    fn normalize_unmatched_closing_tag() {
+        let html = "</div><p>Text</p>";
+
+        assert_eq!(
+            "</div>\n<p>\n  Text\n</p>\n",
+            normalize(html, &HtmlSnapshotOptions::default())
+        );
+    }
+
+    #[test]
+    fn normalize_truncated_tags_as_text() {
+        let options = HtmlSnapshotOptions::default();
+
+        assert_eq!("<p>\n  Text <\n", normalize("<p>Text <", &options));
+        assert_eq!("<p>\n  Text</\n", normalize("<p>Text</", &options));
+    }
+
+    #[test]
+    fn normalize_unterminated_comment() {
+        let html = "<p>Text<!-- note";
+
+        assert_eq!(
+            "<p>\n  Text\n",
+            normalize(html, &HtmlSnapshotOptions::default())
+        );
+    }
+
+    #[test]
+    fn normalize_unterminated_attribute_value() {
+        let html = r#"<a href="/home>Home</a>"#;
+
+        assert_eq!(
+            "<a href=\"/home>Home</a>\">\n",
+            normalize(html, &HtmlSnapshotOptions::default())
+        );
+    }
+
+    #[test]
+    fn normalize_unterminated_script() {
+        let html = "<script>if (a < b) {";
+
+        assert_eq!(
+            "<script>\n  if (a < b) {\n",
+            normalize(html, &HtmlSnapshotOptions::default())
+        );
+    }
+
+    #[test]
+    fn diff_shows_changed_lines() {
+        let diff = diff("<p>\n  One\n</p>\n", "<p>\n  Two\n</p>\n");
+
+        assert_eq!("-   One\n+   Two\n", diff);
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<HtmlSnapshotOptions>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<HtmlSnapshotOptions>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<HtmlSnapshotOptions>();
+    }
+}
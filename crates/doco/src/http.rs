@@ -0,0 +1,355 @@
+//! HTTP client that sends requests to the server under test
+
+use std::sync::Arc;
+
+use anyhow::Context;
+use fantoccini::cookies::Cookie;
+use reqwest::cookie::{CookieStore, Jar};
+use reqwest::{Method, RequestBuilder, Url};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::{Client, Fixture, Result, TestContext};
+
+/// HTTP client that sends requests to the server under test
+///
+/// End-to-end tests often mix calls to an API with checks in the browser, for example to create
+/// data through a REST endpoint and then check that it is shown in the web application. The
+/// `HttpClient` fixture is preconfigured to target the server of the test, and resolves relative
+/// paths just like [`Client::goto`].
+///
+/// The client keeps cookies between requests. They can be shared with the browser session in both
+/// directions, which makes it possible to log in through the API and continue in the browser.
+///
+/// # Example
+///
+/// ```no_run
+/// use doco::{By, Client, HttpClient, Result};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize)]
+/// struct NewTodo {
+///     title: &'static str,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Todo {
+///     id: u64,
+/// }
+///
+/// #[doco::test]
+/// async fn shows_created_todo(client: Client, http: HttpClient) -> Result<()> {
+///     let todo: Todo = http.post_json("/api/todos", &NewTodo { title: "Write tests" }).await?;
+///
+///     client.goto(&format!("/todos/{}", todo.id)).await?;
+///     client.wait_for_text(&By::role("heading"), "Write tests").await?;
+///
+///     Ok(())
+/// }
+/// #
+/// # use doco::{Doco, Server};
+/// #
+/// # #[doco::main]
+/// # async fn main() -> Doco {
+/// #    let server = Server::builder()
+/// #        .image("crccheck/hello-world")
+/// #        .tag("v1.0.0")
+/// #        .port(8000)
+/// #        .build();
+/// #
+/// #    Doco::builder().server(server).build()
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct HttpClient {
+    /// The base URL under which the test process reaches the server
+    base_url: Url,
+
+    /// The cookies that the client has received or that have been copied from the browser
+    cookies: Arc<Jar>,
+
+    /// The HTTP client that is used internally
+    client: reqwest::Client,
+
+    /// The browser session with which cookies can be shared
+    browser: Client,
+}
+
+impl HttpClient {
+    /// Create a new HTTP client for the given server
+    pub(crate) fn new(base_url: Url, browser: Client) -> Result<Self> {
+        let cookies = Arc::new(Jar::default());
+
+        let client = reqwest::Client::builder()
+            .cookie_provider(cookies.clone())
+            .build()
+            .context("failed to build HTTP client")?;
+
+        Ok(Self {
+            base_url,
+            cookies,
+            client,
+            browser,
+        })
+    }
+
+    /// Return the base URL under which the test process reaches the server
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// Resolve the given path relative to the base URL of the server
+    pub fn url(&self, path: &str) -> Result<Url> {
+        resolve(&self.base_url, path)
+    }
+
+    /// Start building a request with the given method to the given path
+    pub fn request(&self, method: Method, path: &str) -> Result<RequestBuilder> {
+        Ok(self.client.request(method, self.url(path)?))
+    }
+
+    /// Start building a `GET` request to the given path
+    pub fn get(&self, path: &str) -> Result<RequestBuilder> {
+        self.request(Method::GET, path)
+    }
+
+    /// Start building a `POST` request to the given path
+    pub fn post(&self, path: &str) -> Result<RequestBuilder> {
+        self.request(Method::POST, path)
+    }
+
+    /// Start building a `PUT` request to the given path
+    pub fn put(&self, path: &str) -> Result<RequestBuilder> {
+        self.request(Method::PUT, path)
+    }
+
+    /// Start building a `PATCH` request to the given path
+    pub fn patch(&self, path: &str) -> Result<RequestBuilder> {
+        self.request(Method::PATCH, path)
+    }
+
+    /// Start building a `DELETE` request to the given path
+    pub fn delete(&self, path: &str) -> Result<RequestBuilder> {
+        self.request(Method::DELETE, path)
+    }
+
+    /// Send a `GET` request and deserialize the JSON response
+    ///
+    /// An error is returned if the server responds with an error status.
+    pub async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        Self::json(self.get(path)?).await
+    }
+
+    /// Send a `POST` request with a JSON body and deserialize the JSON response
+    pub async fn post_json<B, T>(&self, path: &str, body: &B) -> Result<T>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        Self::json(self.post(path)?.json(body)).await
+    }
+
+    /// Send a `PUT` request with a JSON body and deserialize the JSON response
+    pub async fn put_json<B, T>(&self, path: &str, body: &B) -> Result<T>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        Self::json(self.put(path)?.json(body)).await
+    }
+
+    /// Send a `PATCH` request with a JSON body and deserialize the JSON response
+    pub async fn patch_json<B, T>(&self, path: &str, body: &B) -> Result<T>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        Self::json(self.patch(path)?.json(body)).await
+    }
+
+    /// Copy the cookies of the browser session into the HTTP client
+    ///
+    /// This makes it possible to call the API as the user that has logged in through the browser.
+    pub async fn copy_cookies_from_browser(&self) -> Result<()> {
+        for cookie in self.browser.get_all_cookies().await? {
+            self.cookies
+                .add_cookie_str(&set_cookie(&cookie), &self.base_url);
+        }
+
+        Ok(())
+    }
+
+    /// Copy the cookies of the HTTP client into the browser session
+    ///
+    /// Browsers only accept cookies for the site that they are currently on, so the browser must
+    /// have navigated to a page on the server before this method is called.
+    pub async fn copy_cookies_to_browser(&self) -> Result<()> {
+        let Some(header) = self.cookies.cookies(&self.base_url) else {
+            return Ok(());
+        };
+
+        for cookie in parse_cookies(header.to_str()?) {
+            self.browser.add_cookie(cookie).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Send a request and deserialize the JSON response
+    async fn json<T: DeserializeOwned>(request: RequestBuilder) -> Result<T> {
+        let response = request.send().await?.error_for_status()?;
+
+        response
+            .json()
+            .await
+            .context("failed to deserialize JSON response")
+    }
+}
+
+impl Fixture for HttpClient {
+    async fn setup(context: &TestContext) -> Result<Self> {
+        Self::new(context.server_url().clone(), context.client().clone())
+    }
+}
+
+/// Resolve the given path relative to the base URL
+fn resolve(base_url: &Url, path: &str) -> Result<Url> {
+    base_url
+        .join(path)
+        .with_context(|| format!("failed to resolve path `{path}`"))
+}
+
+/// Format a cookie of the browser as a `Set-Cookie` header for the cookie jar
+fn set_cookie(cookie: &Cookie<'_>) -> String {
+    let path = cookie.path().unwrap_or("/");
+
+    format!("{}={}; Path={path}", cookie.name(), cookie.value())
+}
+
+/// Parse the `Cookie` header of the cookie jar into cookies for the browser
+///
+/// The header only contains the names and values of the cookies, so their path is set to `/`.
+fn parse_cookies(header: &str) -> Vec<Cookie<'static>> {
+    header
+        .split("; ")
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(name, value)| {
+            let mut cookie = Cookie::new(name.to_string(), value.to_string());
+            cookie.set_path("/");
+            cookie
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    use super::*;
+
+    fn base_url() -> Url {
+        "http://localhost:8080/app/".parse().unwrap()
+    }
+
+    #[test]
+    fn resolve_relative_path() -> Result<()> {
+        assert_eq!(
+            "http://localhost:8080/app/api/todos?done=true",
+            resolve(&base_url(), "api/todos?done=true")?.as_str()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_absolute_path() -> Result<()> {
+        assert_eq!(
+            "http://localhost:8080/api/todos",
+            resolve(&base_url(), "/api/todos")?.as_str()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn resolve_full_url() -> Result<()> {
+        assert_eq!(
+            "https://example.com/health",
+            resolve(&base_url(), "https://example.com/health")?.as_str()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn cookies_from_browser_are_sent_by_http_client() {
+        let jar = Jar::default();
+        let url = base_url();
+
+        let mut session = Cookie::new("session", "abc==");
+        session.set_path("/");
+        let mut admin = Cookie::new("admin", "1");
+        admin.set_path("/admin");
+
+        jar.add_cookie_str(&set_cookie(&session), &url);
+        jar.add_cookie_str(&set_cookie(&admin), &url);
+
+        assert_eq!(
+            "session=abc==",
+            jar.cookies(&url).unwrap().to_str().unwrap()
+        );
+    }
+
+    #[test]
+    fn cookies_from_http_client_are_added_to_browser() {
+        let jar = Jar::default();
+        let url = base_url();
+        jar.add_cookie_str("session=abc==; Path=/; HttpOnly", &url);
+        jar.add_cookie_str("theme=dark", &url);
+
+        let header = jar.cookies(&url).unwrap();
+        let mut cookies: Vec<(String, String, Option<String>)> =
+            parse_cookies(header.to_str().unwrap())
+                .into_iter()
+                .map(|cookie| {
+                    (
+                        cookie.name().to_string(),
+                        cookie.value().to_string(),
+                        cookie.path().map(String::from),
+                    )
+                })
+                .collect();
+        cookies.sort();
+
+        assert_eq!(
+            vec![
+                (
+                    String::from("session"),
+                    String::from("abc=="),
+                    Some(String::from("/"))
+                ),
+                (
+                    String::from("theme"),
+                    String::from("dark"),
+                    Some(String::from("/"))
+                ),
+            ],
+            cookies
+        );
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<HttpClient>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<HttpClient>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<HttpClient>();
+    }
+}
@@ -45,23 +45,126 @@
 #![warn(clippy::missing_docs_in_private_items)]
 
 pub use anyhow::{anyhow, Context, Error, Result};
-pub use doco_derive::{main, test};
+pub use doco_derive::{after_all, after_each, before_all, before_each, main, test, Page};
 pub use fantoccini::Locator;
-use getset::Getters;
+use getset::{CopyGetters, Getters};
 pub use inventory;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 pub use testcontainers::core::WaitFor;
 use typed_builder::TypedBuilder;
 
+pub use crate::accessibility::{AuditOptions, Impact, Violation, ViolationNode};
+pub use crate::by::By;
 pub use crate::client::Client;
+pub use crate::console::{ConsoleLevel, ConsoleMessage, ConsoleMessageKind};
+pub use crate::container::{Container, Containers, ExecOutput};
+pub use crate::context::TestContext;
+pub use crate::element::LazyElement;
+pub use crate::expect::{expect, Expect};
+pub use crate::fixture::Fixture;
+pub use crate::html::HtmlSnapshotOptions;
+pub use crate::http::HttpClient;
+pub use crate::logs::{Log, Logs};
+pub use crate::mail::{Email, Mailbox, SMTP_PORT};
+pub use crate::mock::{Mock, MockServer, Mocks, MOCK_PORT};
+pub use crate::network::{CapturedRequest, CapturedResponse, Exchange, Network, Stub};
+pub use crate::page::Page;
+pub use crate::reporter::{ArtifactKind, ContainerTiming, Provisioning, Reporter};
+pub use crate::screenshot::ScreenshotOptions;
 pub use crate::server::Server;
 pub use crate::service::Service;
+pub use crate::setup::Setup;
+pub use crate::storage::StorageState;
+pub use crate::suite::{
+    Hook, HookFailure, HookFunction, HookKind, Status, Summary, TestCase, TestOutcome,
+};
 pub use crate::test_runner::TestRunner;
+pub use crate::video::Video;
 
+/// SQL toolkit on which the database fixtures are built
+///
+/// With the `postgres` feature, tests can request an [`sqlx::PgPool`] as a fixture that is
+/// connected to the PostgreSQL service of the test. With the `mysql` feature, they can request an
+/// [`sqlx::MySqlPool`] for the MySQL service. The pools connect with the credentials that have been
+/// configured for the service, so that tests can seed data and check what the server has written.
+///
+/// # Example
+///
+/// ```no_run
+/// use doco::sqlx::PgPool;
+/// use doco::{By, Client, Result};
+///
+/// #[doco::test]
+/// async fn adds_todo(client: Client, db: PgPool) -> Result<()> {
+///     client.goto("/").await?;
+///     client.find(By::label("Title").locator()).await?.send_keys("Write tests\n").await?;
+///     client.wait_for_text(&By::role("listitem"), "Write tests").await?;
+///
+///     let (count,): (i64,) = doco::sqlx::query_as("SELECT COUNT(*) FROM todos")
+///         .fetch_one(&db)
+///         .await?;
+///     assert_eq!(1, count);
+///
+///     Ok(())
+/// }
+/// #
+/// # use doco::services::Postgres;
+/// # use doco::{Doco, Server};
+/// #
+/// # #[doco::main]
+/// # async fn main() -> Doco {
+/// #    let server = Server::builder()
+/// #        .image("crccheck/hello-world")
+/// #        .tag("v1.0.0")
+/// #        .port(8000)
+/// #        .build();
+/// #
+/// #    Doco::builder().server(server).service(Postgres::new()).build()
+/// # }
+/// ```
+#[cfg(any(feature = "postgres", feature = "mysql"))]
+pub use sqlx;
+
+mod accessibility;
+mod by;
+mod cli;
 mod client;
+mod console;
+mod container;
+mod context;
+#[cfg(any(feature = "postgres", feature = "mysql"))]
+mod database;
+mod element;
 mod environment;
+mod events;
+mod expect;
+mod fixture;
+mod form;
+mod fs;
+mod har;
+mod html;
+mod http;
+mod logs;
+mod mail;
+mod mock;
+mod network;
+mod page;
+mod proxy;
+mod reporter;
+pub mod reporters;
+mod screenshot;
 mod server;
 mod service;
+pub mod services;
+mod setup;
+mod snapshot;
+mod storage;
+mod suite;
 mod test_runner;
+mod video;
+mod wait;
 
 #[cfg(test)]
 mod test_utils;
@@ -88,7 +191,7 @@ mod test_utils;
 ///     Doco::builder().server(server).build()
 /// }
 /// ```
-#[derive(Clone, Debug, Getters, TypedBuilder)]
+#[derive(Clone, Debug, CopyGetters, Getters, TypedBuilder)]
 pub struct Doco {
     /// The server that Doco will test
     #[getset(get = "pub")]
@@ -96,12 +199,80 @@ pub struct Doco {
 
     /// Additional services (e.g. databases or caches) that the server depends on
     #[builder(via_mutators(init = Vec::new()), mutators(
-        pub fn service(mut self, service: Service) {
-            self.services.push(service);
+        pub fn service(mut self, service: impl Into<Service>) {
+            self.services.push(service.into());
         }
     ))]
     #[getset(get = "pub")]
     services: Vec<Service>,
+
+    /// The directory in which artifacts like logs are saved, e.g. `target/doco`
+    ///
+    /// Doco creates a subdirectory for each test, in which it saves the artifacts of the test.
+    #[builder(default = PathBuf::from("target/doco"), setter(into))]
+    #[getset(get = "pub")]
+    artifacts: PathBuf,
+
+    /// The directory in which the baselines for snapshots are stored, e.g. `tests/snapshots`
+    ///
+    /// Doco creates a subdirectory for each test, in which it stores the baselines of the test.
+    /// The directory should be committed to version control. Run the tests with
+    /// `--update-snapshots` or with `DOCO_UPDATE_SNAPSHOTS=1` to create or rewrite the baselines.
+    #[builder(default = PathBuf::from("tests/snapshots"), setter(into))]
+    #[getset(get = "pub")]
+    snapshots: PathBuf,
+
+    /// Whether tests fail when errors have been logged to the browser console, e.g. `false`
+    ///
+    /// When enabled, Doco checks the browser console after each successful test and fails the test
+    /// if any errors or uncaught JavaScript exceptions have been logged.
+    #[builder(default)]
+    #[getset(get_copy = "pub")]
+    fail_on_console_errors: bool,
+
+    /// Whether videos of the browser session are recorded, e.g. `Video::RetainOnFailure`
+    #[builder(default)]
+    #[getset(get_copy = "pub")]
+    video: Video,
+
+    /// The time that locators on the [`Client`] wait for an element, e.g. 5 seconds
+    #[builder(default = wait::DEFAULT_TIMEOUT)]
+    #[getset(get_copy = "pub")]
+    timeout: Duration,
+
+    /// The attribute that identifies elements for [`Client::test_id`], e.g. `data-testid`
+    #[builder(default = String::from(by::DEFAULT_TEST_ID_ATTRIBUTE), setter(into))]
+    #[getset(get = "pub")]
+    test_id_attribute: String,
+
+    /// Function that runs once before the tests, e.g. to log in through the web application
+    ///
+    /// The cookies and `localStorage` of the browser are captured after the function has run, and
+    /// restored at the start of every test. See [`Setup`] for an example.
+    #[builder(default, setter(into, strip_option))]
+    #[getset(get = "pub")]
+    setup: Option<Setup>,
+
+    /// Whether the network traffic of the browser is recorded, e.g. `false`
+    ///
+    /// When enabled, the browser sends its traffic through a proxy that records it. Tests can
+    /// inspect and stub the traffic with the [`Network`] fixture, and the traffic of each test is
+    /// saved as `network.har` in its artifacts directory.
+    #[builder(default)]
+    #[getset(get_copy = "pub")]
+    record_network: bool,
+
+    /// Reporters that present the progress and results of the tests, e.g. `reporters::Pretty`
+    ///
+    /// Several reporters can be registered, and all of them are notified. When no reporter has
+    /// been registered, the [`Pretty`](reporters::Pretty) reporter is used.
+    #[builder(via_mutators(init = Vec::new()), mutators(
+        pub fn reporter(mut self, reporter: impl Reporter + 'static) {
+            self.reporters.push(Arc::new(reporter));
+        }
+    ))]
+    #[getset(get = "pub")]
+    reporters: Vec<Arc<dyn Reporter>>,
 }
 
 #[cfg(test)]
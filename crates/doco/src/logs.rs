@@ -0,0 +1,225 @@
+//! Logs of the containers that run the server and its services
+
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::Context;
+use getset::Getters;
+use testcontainers::{ContainerAsync, GenericImage};
+
+use crate::{Fixture, Result, TestContext};
+
+/// Logs of the containers that run the server and its services
+///
+/// The `Logs` fixture gives tests access to the output of the server and its services. Errors on
+/// the server often cause assertions in the web application to fail, and the logs make it possible
+/// to check for them directly. The logs are read when requested, so they always contain everything
+/// that a container has written up to that point.
+///
+/// Doco also collects the logs after each test, saves them to the artifacts directory, and prints
+/// them when the test fails.
+///
+/// # Example
+///
+/// ```no_run
+/// use doco::{Client, Logs, Result};
+///
+/// #[doco::test]
+/// async fn logs_no_errors(client: Client, logs: Logs) -> Result<()> {
+///     client.goto("/").await?;
+///
+///     assert!(!logs.server().await?.contains("ERROR"));
+///
+///     Ok(())
+/// }
+/// #
+/// # use doco::{Doco, Server};
+/// #
+/// # #[doco::main]
+/// # async fn main() -> Doco {
+/// #    let server = Server::builder()
+/// #        .image("crccheck/hello-world")
+/// #        .tag("v1.0.0")
+/// #        .port(8000)
+/// #        .build();
+/// #
+/// #    Doco::builder().server(server).build()
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Logs {
+    /// The container that runs the server
+    server: Arc<ContainerAsync<GenericImage>>,
+
+    /// The containers that run the services, keyed by their hostname
+    services: Vec<(String, Arc<ContainerAsync<GenericImage>>)>,
+}
+
+impl Logs {
+    /// Read the logs of the server
+    pub async fn server(&self) -> Result<Log> {
+        Log::read(&self.server).await
+    }
+
+    /// Read the logs of the service with the given hostname
+    ///
+    /// Services are identified by the hostname under which the server can reach them, which is the
+    /// name of their image unless a hostname has been set. An error is returned if no such service
+    /// exists.
+    pub async fn service(&self, name: &str) -> Result<Log> {
+        let (_, container) = self
+            .services
+            .iter()
+            .find(|(host, _)| host == name)
+            .with_context(|| format!("failed to find service '{name}'"))?;
+
+        Log::read(container).await
+    }
+
+    /// Read the logs of the server and all services
+    ///
+    /// The logs are returned together with the name of their container, starting with the server.
+    pub(crate) async fn all(&self) -> Result<Vec<(String, Log)>> {
+        let mut logs = Vec::with_capacity(self.services.len() + 1);
+
+        logs.push((String::from("server"), self.server().await?));
+
+        for (name, container) in &self.services {
+            logs.push((name.clone(), Log::read(container).await?));
+        }
+
+        Ok(logs)
+    }
+}
+
+impl Fixture for Logs {
+    async fn setup(context: &TestContext) -> Result<Self> {
+        Ok(Self {
+            server: context.server().clone(),
+            services: context.services().clone(),
+        })
+    }
+}
+
+/// Output of a single container
+///
+/// A `Log` contains everything that a container has written to its standard output and standard
+/// error streams at the time it was read.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Getters)]
+pub struct Log {
+    /// The output that the container has written to stdout
+    #[getset(get = "pub")]
+    stdout: String,
+
+    /// The output that the container has written to stderr
+    #[getset(get = "pub")]
+    stderr: String,
+}
+
+impl Log {
+    /// Read the current logs of the given container
+    async fn read(container: &ContainerAsync<GenericImage>) -> Result<Self> {
+        let stdout = container
+            .stdout_to_vec()
+            .await
+            .context("failed to read stdout of container")?;
+        let stderr = container
+            .stderr_to_vec()
+            .await
+            .context("failed to read stderr of container")?;
+
+        Ok(Self {
+            stdout: String::from_utf8_lossy(&stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&stderr).into_owned(),
+        })
+    }
+
+    /// Iterate over the lines in stdout and stderr
+    pub fn lines(&self) -> impl Iterator<Item = &str> {
+        self.stdout.lines().chain(self.stderr.lines())
+    }
+
+    /// Check if any line in stdout or stderr contains the given pattern
+    pub fn contains(&self, pattern: &str) -> bool {
+        self.lines().any(|line| line.contains(pattern))
+    }
+
+    /// Save the logs to the given directory
+    ///
+    /// The logs are written to two files, `<name>.stdout.log` and `<name>.stderr.log`.
+    pub(crate) fn save(&self, directory: &Path, name: &str) -> Result<()> {
+        std::fs::create_dir_all(directory)
+            .with_context(|| format!("failed to create directory {}", directory.display()))?;
+
+        for (stream, content) in [("stdout", &self.stdout), ("stderr", &self.stderr)] {
+            let path = directory.join(format!("{name}.{stream}.log"));
+
+            std::fs::write(&path, content)
+                .with_context(|| format!("failed to write logs to {}", path.display()))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    use super::*;
+
+    fn log() -> Log {
+        Log {
+            stdout: String::from("INFO starting server\nINFO listening on port 8080"),
+            stderr: String::from("ERROR failed to connect to database"),
+        }
+    }
+
+    #[test]
+    fn lines_chains_stdout_and_stderr() {
+        let log = log();
+
+        assert_eq!(3, log.lines().count());
+    }
+
+    #[test]
+    fn contains_searches_stdout_and_stderr() {
+        let log = log();
+
+        assert!(log.contains("listening"));
+        assert!(log.contains("ERROR"));
+        assert!(!log.contains("WARN"));
+    }
+
+    #[test]
+    fn save_writes_files() -> Result<()> {
+        let directory = std::env::temp_dir().join("doco-logs-save-writes-files");
+
+        log().save(&directory, "server")?;
+
+        let stderr = std::fs::read_to_string(directory.join("server.stderr.log"))?;
+        assert_eq!("ERROR failed to connect to database", stderr);
+
+        std::fs::remove_dir_all(directory)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<Logs>();
+        assert_send::<Log>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<Logs>();
+        assert_sync::<Log>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<Logs>();
+        assert_unpin::<Log>();
+    }
+}
@@ -0,0 +1,628 @@
+//! Email capture service that receives the mail of the server
+
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context};
+use futures_util::future::try_join_all;
+use getset::Getters;
+use reqwest::Url;
+use serde::Deserialize;
+use testcontainers::core::WaitFor;
+use testcontainers::Image;
+
+use crate::network::matches;
+use crate::wait::POLL_INTERVAL;
+use crate::{Fixture, Result, Service, TestContext};
+
+/// The Docker image of the email capture service
+const IMAGE: &str = "axllent/mailpit";
+
+/// The tag of the email capture service's Docker image
+const TAG: &str = "v1.21";
+
+/// The port of the email capture service's HTTP API
+const API_PORT: u16 = 8025;
+
+/// The port on which the email capture service accepts mail over SMTP
+pub const SMTP_PORT: u16 = 1025;
+
+/// The number of summaries that are requested from the list of messages at once
+const PAGE_SIZE: usize = 100;
+
+impl Service {
+    /// Create an email capture service that the server reaches under the given hostname
+    ///
+    /// The service runs [Mailpit](https://mailpit.axllent.org) in a container, which accepts any
+    /// mail over SMTP on port [`SMTP_PORT`] without authentication or TLS and keeps it instead of
+    /// delivering it. Point the server to `<hostname>:1025` as its SMTP server, and read the mail
+    /// from the test with the [`Mailbox`] fixture.
+    pub fn mail(hostname: impl Into<String>) -> Service {
+        Service::builder()
+            .image(IMAGE)
+            .tag(TAG)
+            .hostname(hostname)
+            .wait(WaitFor::message_on_stdout("accessible via"))
+            .build()
+    }
+}
+
+/// Address of the sender or a recipient of an email
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize)]
+struct Address {
+    /// The email address, e.g. `jane@example.com`
+    #[serde(rename = "Address")]
+    address: String,
+}
+
+/// Summary of an email in the list of messages
+#[derive(Clone, Debug, Deserialize)]
+struct Summary {
+    /// The id under which the message is stored
+    #[serde(rename = "ID")]
+    id: String,
+
+    /// The recipients of the message
+    #[serde(rename = "To", default)]
+    to: Vec<Address>,
+}
+
+/// Page of the list of messages in the mailbox, with the newest message first
+#[derive(Clone, Debug, Deserialize)]
+struct Summaries {
+    /// The total number of messages in the mailbox
+    total: usize,
+
+    /// The messages on the page
+    messages: Vec<Summary>,
+}
+
+/// Email that the server has sent
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize, Getters)]
+pub struct Email {
+    /// The id under which the message is stored in the mailbox
+    #[serde(rename = "ID")]
+    #[getset(get = "pub")]
+    id: String,
+
+    /// The address of the sender
+    #[serde(rename = "From", deserialize_with = "address")]
+    #[getset(get = "pub")]
+    from: String,
+
+    /// The addresses of the recipients
+    #[serde(rename = "To", default, deserialize_with = "addresses")]
+    #[getset(get = "pub")]
+    to: Vec<String>,
+
+    /// The subject of the message
+    #[serde(rename = "Subject")]
+    #[getset(get = "pub")]
+    subject: String,
+
+    /// The plain text body of the message
+    #[serde(rename = "Text", default)]
+    #[getset(get = "pub")]
+    text: String,
+
+    /// The HTML body of the message
+    #[serde(rename = "HTML", default)]
+    #[getset(get = "pub")]
+    html: String,
+}
+
+impl Email {
+    /// Return the URLs of all links in the message, in the order in which they appear
+    ///
+    /// Links are collected from the `href` attributes of the HTML body and from the URLs in the
+    /// plain text body. Each URL is returned only once.
+    pub fn links(&self) -> Vec<String> {
+        let mut links = Vec::new();
+
+        for link in html_links(&self.html)
+            .into_iter()
+            .chain(text_links(&self.text))
+        {
+            if !links.contains(&link) {
+                links.push(link);
+            }
+        }
+
+        links
+    }
+
+    /// Return the path of the first link whose URL matches the given pattern
+    ///
+    /// A `*` in the pattern matches any number of characters, e.g. `*/reset-password*`. The link
+    /// is returned as a path with its query, e.g. `/reset-password?token=abc`, which can be passed
+    /// to [`Client::goto`](crate::Client::goto). This way, the link is opened on the server of the
+    /// test, even if the server does not know the address under which the browser reaches it.
+    pub fn link(&self, pattern: &str) -> Result<String> {
+        let link = self
+            .links()
+            .into_iter()
+            .find(|link| matches(pattern, link))
+            .with_context(|| format!("failed to find a link matching `{pattern}` in the email"))?;
+
+        Ok(path(&link))
+    }
+}
+
+/// Mailbox of the email capture service
+///
+/// The `Mailbox` fixture reads the mail that the server has sent to the email capture service,
+/// which has been added to the test suite with [`Service::mail`]. Each test starts with an empty
+/// mailbox.
+///
+/// # Example
+///
+/// ```no_run
+/// use doco::{By, Client, Mailbox, Result};
+///
+/// #[doco::test]
+/// async fn reset_password(client: Client, mailbox: Mailbox) -> Result<()> {
+///     client.goto("/forgot-password").await?;
+///     client.find(By::label("Email").locator()).await?.send_keys("jane@example.com").await?;
+///     client.find(By::role("button").name("Send link").locator()).await?.click().await?;
+///
+///     let email = mailbox.wait_for_message("jane@example.com").await?;
+///     assert_eq!("Reset your password", email.subject());
+///
+///     client.goto(&email.link("*/reset-password*")?).await?;
+///     client.wait_for_text(&By::role("heading"), "Choose a new password").await?;
+///
+///     Ok(())
+/// }
+/// #
+/// # use doco::{Doco, Server, Service};
+/// #
+/// # #[doco::main]
+/// # async fn main() -> Doco {
+/// #    let server = Server::builder()
+/// #        .image("crccheck/hello-world")
+/// #        .tag("v1.0.0")
+/// #        .port(8000)
+/// #        .env("SMTP_URL", "smtp://mail:1025")
+/// #        .build();
+/// #
+/// #    Doco::builder()
+/// #        .server(server)
+/// #        .service(Service::mail("mail"))
+/// #        .build()
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Mailbox {
+    /// The URL under which the test process reaches the API of the email capture service
+    api: Url,
+
+    /// The HTTP client that calls the API
+    client: reqwest::Client,
+
+    /// The time that [`Mailbox::wait_for_message`] waits for a message
+    timeout: Duration,
+}
+
+impl Mailbox {
+    /// Return all messages in the mailbox, in the order in which they were received
+    ///
+    /// The list of messages contains no bodies, so each message is read with its own request. The
+    /// requests are sent concurrently.
+    pub async fn messages(&self) -> Result<Vec<Email>> {
+        let summaries = self.summaries().await?;
+
+        try_join_all(summaries.iter().map(|summary| self.message(&summary.id))).await
+    }
+
+    /// Return the messages that have been sent to the given address
+    ///
+    /// Only the messages to the address are read from the mailbox.
+    pub async fn messages_to(&self, address: &str) -> Result<Vec<Email>> {
+        let summaries = self.summaries().await?;
+
+        try_join_all(
+            summaries
+                .iter()
+                .filter(|summary| is_recipient(summary, address))
+                .map(|summary| self.message(&summary.id)),
+        )
+        .await
+    }
+
+    /// Wait for a message to the given address
+    ///
+    /// Messages that have been received before this method was called are considered as well. The
+    /// method waits for the timeout of the test suite, and returns the first matching message.
+    pub async fn wait_for_message(&self, address: &str) -> Result<Email> {
+        let deadline = Instant::now() + self.timeout;
+
+        loop {
+            if let Some(summary) = self
+                .summaries()
+                .await?
+                .into_iter()
+                .find(|summary| is_recipient(summary, address))
+            {
+                return self.message(&summary.id).await;
+            }
+
+            if Instant::now() >= deadline {
+                return Err(anyhow!(
+                    "timed out after {:?} waiting for an email to '{address}'",
+                    self.timeout
+                ));
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Delete all messages from the mailbox
+    pub async fn clear(&self) -> Result<()> {
+        self.client
+            .delete(self.api.join("api/v1/messages")?)
+            .send()
+            .await?
+            .error_for_status()
+            .context("failed to clear the mailbox")?;
+
+        Ok(())
+    }
+
+    /// Return the summaries of all messages, with the oldest message first
+    ///
+    /// The list of messages is paginated, so it is read page by page until all messages have been
+    /// returned.
+    async fn summaries(&self) -> Result<Vec<Summary>> {
+        let url = self.api.join("api/v1/messages")?;
+        let mut summaries = Vec::new();
+
+        loop {
+            let page: Summaries = self
+                .client
+                .get(url.clone())
+                .query(&[("start", summaries.len()), ("limit", PAGE_SIZE)])
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await
+                .context("failed to read the messages in the mailbox")?;
+
+            let last = page.messages.is_empty();
+            summaries.extend(page.messages);
+
+            if last || summaries.len() >= page.total {
+                break;
+            }
+        }
+
+        summaries.reverse();
+        Ok(summaries)
+    }
+
+    /// Return the message with the given id
+    async fn message(&self, id: &str) -> Result<Email> {
+        self.client
+            .get(self.api.join(&format!("api/v1/message/{id}"))?)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .with_context(|| format!("failed to read the message '{id}'"))
+    }
+}
+
+impl Fixture for Mailbox {
+    async fn setup(context: &TestContext) -> Result<Self> {
+        let (_, container) = context
+            .services()
+            .iter()
+            .find(|(_, container)| container.image().name() == IMAGE)
+            .context("no email capture service, add one with `Service::mail`")?;
+
+        let api = format!(
+            "http://{}:{}",
+            container.get_host().await?,
+            container.get_host_port_ipv4(API_PORT).await?
+        )
+        .parse()?;
+
+        Ok(Self {
+            api,
+            client: reqwest::Client::new(),
+            timeout: context.client().timeout(),
+        })
+    }
+}
+
+/// Check if the given address is a recipient of the message, ignoring its case
+fn is_recipient(summary: &Summary, address: &str) -> bool {
+    summary
+        .to
+        .iter()
+        .any(|to| to.address.eq_ignore_ascii_case(address))
+}
+
+/// Deserialize a single address into a string
+fn address<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(Address::deserialize(deserializer)?.address)
+}
+
+/// Deserialize a list of addresses into strings
+fn addresses<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let addresses = Option::<Vec<Address>>::deserialize(deserializer)?.unwrap_or_default();
+
+    Ok(addresses.into_iter().map(|to| to.address).collect())
+}
+
+/// Extract the `href` attributes of the links in an HTML body
+fn html_links(html: &str) -> Vec<String> {
+    let mut links = Vec::new();
+
+    // Lowercasing ASCII keeps the byte offsets, so the matches can be looked up in the original
+    let lowercase = html.to_ascii_lowercase();
+    let mut offset = 0;
+
+    while let Some(start) = lowercase[offset..].find("href") {
+        let start = offset + start;
+        offset = start + 4;
+
+        // Attributes that end in `href`, e.g. `data-href`, are not links
+        let boundary = html[..start]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_whitespace() || c == '<');
+        if !boundary {
+            continue;
+        }
+
+        let Some(rest) = html[offset..].trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let rest = rest.trim_start();
+
+        let (quote, value) = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => (Some(quote), &rest[1..]),
+            _ => (None, rest),
+        };
+        let end = value
+            .find(|c: char| match quote {
+                Some(quote) => c == quote,
+                None => c.is_whitespace() || c == '>',
+            })
+            .unwrap_or(value.len());
+
+        let link = value[..end].replace("&amp;", "&");
+        if link.starts_with("http://") || link.starts_with("https://") || link.starts_with('/') {
+            links.push(link);
+        }
+
+        offset = html.len() - value[end..].len();
+    }
+
+    links
+}
+
+/// Extract the URLs in a plain text body
+fn text_links(text: &str) -> Vec<String> {
+    text.split(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"' | '(' | ')'))
+        .filter(|word| word.starts_with("http://") || word.starts_with("https://"))
+        .map(|word| {
+            word.trim_end_matches(['.', ',', ';', ':', '!', '?'])
+                .to_string()
+        })
+        .collect()
+}
+
+/// Return the path, query, and fragment of a link
+fn path(link: &str) -> String {
+    match Url::parse(link) {
+        Ok(url) => {
+            let mut path = url.path().to_string();
+            if let Some(query) = url.query() {
+                path.push('?');
+                path.push_str(query);
+            }
+            if let Some(fragment) = url.fragment() {
+                path.push('#');
+                path.push_str(fragment);
+            }
+            path
+        }
+        Err(_) => link.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use axum::extract::{Path, Query};
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use serde_json::{json, Value};
+    use tokio::net::TcpListener;
+
+    use crate::test_utils::*;
+
+    use super::*;
+
+    /// The number of messages in the fake mailbox, which spans more than one page
+    const MESSAGES: usize = PAGE_SIZE + 20;
+
+    /// Serve the list of messages and the messages like Mailpit, with the newest message first
+    async fn serve_mailpit() -> Result<Mailbox> {
+        async fn list(Query(query): Query<HashMap<String, usize>>) -> Json<Value> {
+            let start = query.get("start").copied().unwrap_or_default();
+            let limit = query.get("limit").copied().unwrap_or(50);
+
+            let messages: Vec<Value> = (0..MESSAGES)
+                .rev()
+                .skip(start)
+                .take(limit)
+                .map(|id| {
+                    let to = if id % 2 == 0 { "even" } else { "odd" };
+                    json!({ "ID": id.to_string(), "To": [{ "Address": format!("{to}@example.com") }] })
+                })
+                .collect();
+
+            Json(json!({ "total": MESSAGES, "count": messages.len(), "messages": messages }))
+        }
+
+        async fn message(Path(id): Path<String>) -> Json<Value> {
+            Json(json!({
+                "ID": id,
+                "From": { "Address": "noreply@example.com" },
+                "To": [],
+                "Subject": format!("Message {id}"),
+            }))
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let api = format!("http://{}", listener.local_addr()?).parse()?;
+
+        let app = Router::new()
+            .route("/api/v1/messages", get(list))
+            .route("/api/v1/message/{id}", get(message));
+        tokio::spawn(async { axum::serve(listener, app).await });
+
+        Ok(Mailbox {
+            api,
+            client: reqwest::Client::new(),
+            timeout: Duration::from_secs(1),
+        })
+    }
+
+    fn email() -> Email {
+        serde_json::from_value(json!({
+            "ID": "abc",
+            "From": { "Name": "Todos", "Address": "noreply@example.com" },
+            "To": [{ "Name": "Jane", "Address": "jane@example.com" }],
+            "Subject": "Reset your password",
+            "Text": "Reset it at http://localhost:8080/reset-password?token=abc.\nThanks!",
+            "HTML": "<p><a href=\"http://localhost:8080/reset-password?token=abc&amp;lang=en\">Reset</a> or <a href='/help'>get help</a></p>"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn service_mail_sets_hostname() {
+        let service = Service::mail("mail");
+
+        assert_eq!(IMAGE, service.image());
+        assert_eq!("mail", service.host());
+    }
+
+    #[test]
+    fn email_deserializes_addresses() {
+        let email = email();
+
+        assert_eq!("noreply@example.com", email.from());
+        assert_eq!(&vec![String::from("jane@example.com")], email.to());
+    }
+
+    #[test]
+    fn links_are_collected_from_html_and_text() {
+        assert_eq!(
+            vec![
+                String::from("http://localhost:8080/reset-password?token=abc&lang=en"),
+                String::from("/help"),
+                String::from("http://localhost:8080/reset-password?token=abc"),
+            ],
+            email().links()
+        );
+    }
+
+    #[test]
+    fn link_returns_path() -> Result<()> {
+        assert_eq!(
+            "/reset-password?token=abc&lang=en",
+            email().link("*/reset-password*")?
+        );
+        assert_eq!("/help", email().link("/help")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn link_fails_without_match() {
+        assert!(email().link("*/unsubscribe*").is_err());
+    }
+
+    #[test]
+    fn summaries_match_recipient() {
+        let summary: Summary = serde_json::from_value(json!({
+            "ID": "abc",
+            "To": [{ "Name": "", "Address": "Jane@Example.com" }]
+        }))
+        .unwrap();
+
+        assert!(is_recipient(&summary, "jane@example.com"));
+        assert!(!is_recipient(&summary, "john@example.com"));
+    }
+
+    #[test]
+    fn html_links_with_uppercase_attributes() {
+        let html = "<A HREF=\"/one\">One</A> <a Href='/two'>Two</a> <a href=/three>Three</a>";
+
+        assert_eq!(vec!["/one", "/two", "/three"], html_links(html));
+    }
+
+    #[test]
+    fn html_links_ignore_attributes_ending_in_href() {
+        let html = "<a data-href=\"/tracking\" href=\"/reset\">Reset</a> <p>href=/text</p>";
+
+        assert_eq!(vec!["/reset"], html_links(html));
+    }
+
+    #[test]
+    fn html_links_with_whitespace_around_equals() {
+        let html = "<a href = \"/one\">One</a> <a\nhref\n=\n'/two'>Two</a>";
+
+        assert_eq!(vec!["/one", "/two"], html_links(html));
+    }
+
+    #[tokio::test]
+    async fn messages_are_read_from_all_pages() -> Result<()> {
+        let mailbox = serve_mailpit().await?;
+
+        let messages = mailbox.messages().await?;
+
+        assert_eq!(MESSAGES, messages.len());
+        assert_eq!("0", messages[0].id());
+        assert_eq!(&(MESSAGES - 1).to_string(), messages[MESSAGES - 1].id());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn messages_to_reads_only_matching_messages() -> Result<()> {
+        let mailbox = serve_mailpit().await?;
+
+        let messages = mailbox.messages_to("odd@example.com").await?;
+
+        assert_eq!(MESSAGES / 2, messages.len());
+        assert_eq!("Message 1", messages[0].subject());
+
+        Ok(())
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<Email>();
+        assert_send::<Mailbox>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<Email>();
+        assert_sync::<Mailbox>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<Email>();
+        assert_unpin::<Mailbox>();
+    }
+}
@@ -0,0 +1,555 @@
+//! Mock HTTP service that stands in for third-party APIs
+
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Context};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use getset::{CopyGetters, Getters};
+use reqwest::Url;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use testcontainers::core::WaitFor;
+use testcontainers::Image;
+use typed_builder::TypedBuilder;
+
+use crate::network::{matches, CapturedRequest};
+use crate::wait::POLL_INTERVAL;
+use crate::{Fixture, Result, Service, TestContext};
+
+/// The Docker image of the mock server
+const IMAGE: &str = "wiremock/wiremock";
+
+/// The tag of the mock server's Docker image
+const TAG: &str = "3.9.1";
+
+/// The port on which the mock server listens inside its container
+pub const MOCK_PORT: u16 = 8080;
+
+impl Service {
+    /// Create a mock HTTP service that the server reaches under the given hostname
+    ///
+    /// The mock service runs [WireMock](https://wiremock.org) in a container and listens on port
+    /// [`MOCK_PORT`]. Point the server to `http://<hostname>:8080` instead of the real API, e.g.
+    /// with an environment variable, and program the responses from the test with the [`Mocks`]
+    /// fixture. Requests that match no route are answered with `404 Not Found`.
+    pub fn mock(hostname: impl Into<String>) -> Service {
+        Service::builder()
+            .image(IMAGE)
+            .tag(TAG)
+            .hostname(hostname)
+            .wait(WaitFor::message_on_stdout("port:"))
+            .build()
+    }
+}
+
+/// Route of a mock service and the response that it returns
+///
+/// The path of a route matches the path of a request, ignoring its query. A `*` in the path matches
+/// any number of characters, e.g. `/v1/customers/*` matches `/v1/customers/42`. Routes that are
+/// registered later take precedence over earlier ones.
+///
+/// # Example
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use doco::Mock;
+///
+/// let slow_charge = Mock::builder()
+///     .method("POST")
+///     .path("/v1/charges")
+///     .status(201)
+///     .header("content-type", "application/json")
+///     .body(r#"{"id":"ch_1"}"#)
+///     .delay(Duration::from_secs(2))
+///     .build();
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug, CopyGetters, Getters, TypedBuilder)]
+pub struct Mock {
+    /// The method that the route matches, e.g. `POST`, or `ANY` to match all methods
+    #[builder(default = String::from("ANY"), setter(into))]
+    #[getset(get = "pub")]
+    method: String,
+
+    /// The path that the route matches, e.g. `/v1/charges`
+    #[builder(setter(into))]
+    #[getset(get = "pub")]
+    path: String,
+
+    /// The status code of the response, e.g. `200`
+    #[builder(default = 200)]
+    #[getset(get_copy = "pub")]
+    status: u16,
+
+    /// The headers of the response
+    #[builder(via_mutators(init = Vec::new()), mutators(
+        pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) {
+            self.headers.push((name.into(), value.into()));
+        }
+    ))]
+    #[getset(get = "pub")]
+    headers: Vec<(String, String)>,
+
+    /// The body of the response
+    #[builder(default, setter(into))]
+    #[getset(get = "pub")]
+    body: Vec<u8>,
+
+    /// The time that the mock waits before it responds, e.g. to simulate a slow API
+    #[builder(default, setter(strip_option))]
+    #[getset(get_copy = "pub")]
+    delay: Option<Duration>,
+}
+
+impl Mock {
+    /// Create a route that responds with the given value as JSON
+    pub fn json<T: Serialize + ?Sized>(
+        method: impl Into<String>,
+        path: impl Into<String>,
+        value: &T,
+    ) -> Result<Self> {
+        Ok(Self::builder()
+            .method(method)
+            .path(path)
+            .header("content-type", "application/json")
+            .body(serde_json::to_vec(value)?)
+            .build())
+    }
+
+    /// Convert the route into a WireMock stub mapping
+    fn mapping(&self) -> Value {
+        let mut request = json!({ "method": self.method.to_uppercase() });
+        if self.path.contains('*') {
+            request["urlPathPattern"] = json!(path_pattern(&self.path));
+        } else {
+            request["urlPath"] = json!(self.path);
+        }
+
+        let headers: Map<String, Value> = self
+            .headers
+            .iter()
+            .map(|(name, value)| (name.clone(), json!(value)))
+            .collect();
+
+        let mut response = json!({
+            "status": self.status,
+            "headers": headers,
+            "base64Body": BASE64.encode(&self.body),
+        });
+        if let Some(delay) = self.delay {
+            response["fixedDelayMilliseconds"] = json!(delay.as_millis());
+        }
+
+        json!({ "request": request, "response": response })
+    }
+}
+
+/// Mock HTTP service that has been started for a test
+///
+/// The `MockServer` programs the routes of a mock service through its admin API, and returns the
+/// requests that the server under test has sent to it. Each test starts with a new mock service
+/// without routes.
+#[derive(Clone, Debug)]
+pub struct MockServer {
+    /// The hostname under which the server reaches the mock service
+    hostname: String,
+
+    /// The URL under which the test process reaches the admin API of the mock service
+    admin: Url,
+
+    /// The HTTP client that calls the admin API
+    client: reqwest::Client,
+
+    /// The time that [`MockServer::wait_for_request`] waits for a request
+    timeout: Duration,
+}
+
+impl MockServer {
+    /// Return the hostname under which the server reaches the mock service
+    pub fn hostname(&self) -> &str {
+        &self.hostname
+    }
+
+    /// Register a route on the mock service
+    pub async fn mock(&self, mock: Mock) -> Result<()> {
+        self.client
+            .post(self.admin.join("__admin/mappings")?)
+            .json(&mock.mapping())
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("failed to register route on mock '{}'", self.hostname))?;
+
+        Ok(())
+    }
+
+    /// Remove all routes and recorded requests from the mock service
+    pub async fn reset(&self) -> Result<()> {
+        self.client
+            .post(self.admin.join("__admin/reset")?)
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("failed to reset mock '{}'", self.hostname))?;
+
+        Ok(())
+    }
+
+    /// Return all requests that the mock service has received, in the order in which they arrived
+    pub async fn requests(&self) -> Result<Vec<CapturedRequest>> {
+        let journal: Value = self
+            .client
+            .get(self.admin.join("__admin/requests")?)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .with_context(|| format!("failed to read requests of mock '{}'", self.hostname))?;
+
+        journal_requests(&journal)
+    }
+
+    /// Return the requests with the given method whose path matches the given path
+    pub async fn requests_to(&self, method: &str, path: &str) -> Result<Vec<CapturedRequest>> {
+        Ok(self
+            .requests()
+            .await?
+            .into_iter()
+            .filter(|request| request_matches(request, method, path))
+            .collect())
+    }
+
+    /// Wait for a request with the given method whose path matches the given path
+    ///
+    /// Requests that have been received before this method was called are considered as well. The
+    /// method waits for the timeout of the test suite, and returns the first matching request.
+    pub async fn wait_for_request(&self, method: &str, path: &str) -> Result<CapturedRequest> {
+        let deadline = Instant::now() + self.timeout;
+
+        loop {
+            if let Some(request) = self.requests_to(method, path).await?.into_iter().next() {
+                return Ok(request);
+            }
+
+            if Instant::now() >= deadline {
+                return Err(anyhow!(
+                    "timed out after {:?} waiting for `{method} {path}` on mock '{}'",
+                    self.timeout,
+                    self.hostname
+                ));
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Mock HTTP services of a test
+///
+/// The `Mocks` fixture gives access to the mock services that have been added to the test suite
+/// with [`Service::mock`], identified by their hostname.
+///
+/// # Example
+///
+/// ```no_run
+/// use doco::{By, Client, Mock, Mocks, Result};
+/// use serde_json::json;
+///
+/// #[doco::test]
+/// async fn declined_payment(client: Client, mocks: Mocks) -> Result<()> {
+///     let payments = mocks.get("payments")?;
+///     payments
+///         .mock(Mock::json("POST", "/v1/charges", &json!({ "status": "declined" }))?)
+///         .await?;
+///
+///     client.goto("/checkout").await?;
+///     client.find(By::role("button").name("Pay").locator()).await?.click().await?;
+///     client.wait_for_text(&By::role("alert"), "Your card was declined").await?;
+///
+///     let charge = payments.wait_for_request("POST", "/v1/charges").await?;
+///     assert_eq!(Some("application/json"), charge.header("content-type"));
+///
+///     Ok(())
+/// }
+/// #
+/// # use doco::{Doco, Server, Service};
+/// #
+/// # #[doco::main]
+/// # async fn main() -> Doco {
+/// #    let server = Server::builder()
+/// #        .image("crccheck/hello-world")
+/// #        .tag("v1.0.0")
+/// #        .port(8000)
+/// #        .env("PAYMENTS_URL", "http://payments:8080")
+/// #        .build();
+/// #
+/// #    Doco::builder()
+/// #        .server(server)
+/// #        .service(Service::mock("payments"))
+/// #        .build()
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Mocks {
+    /// The mock services of the test
+    servers: Vec<MockServer>,
+}
+
+impl Mocks {
+    /// Return the mock service with the given hostname
+    pub fn get(&self, hostname: &str) -> Result<MockServer> {
+        self.servers
+            .iter()
+            .find(|server| server.hostname == hostname)
+            .cloned()
+            .with_context(|| format!("failed to find mock service '{hostname}'"))
+    }
+}
+
+impl Fixture for Mocks {
+    async fn setup(context: &TestContext) -> Result<Self> {
+        let client = reqwest::Client::new();
+        let mut servers = Vec::new();
+
+        for (hostname, container) in context.services() {
+            if container.image().name() != IMAGE {
+                continue;
+            }
+
+            let admin = format!(
+                "http://{}:{}",
+                container.get_host().await?,
+                container.get_host_port_ipv4(MOCK_PORT).await?
+            )
+            .parse()?;
+
+            servers.push(MockServer {
+                hostname: hostname.clone(),
+                admin,
+                client: client.clone(),
+                timeout: context.client().timeout(),
+            });
+        }
+
+        Ok(Self { servers })
+    }
+}
+
+/// Convert a path with `*` wildcards into a regular expression for WireMock
+fn path_pattern(path: &str) -> String {
+    let parts: Vec<String> = path
+        .split('*')
+        .map(|part| {
+            part.chars()
+                .map(|c| match c {
+                    '\\' | '.' | '+' | '?' | '(' | ')' | '[' | ']' | '{' | '}' | '^' | '$'
+                    | '|' => {
+                        format!("\\{c}")
+                    }
+                    c => c.to_string(),
+                })
+                .collect()
+        })
+        .collect();
+
+    parts.join(".*")
+}
+
+/// Check if a request has the given method and a path that matches the given path
+fn request_matches(request: &CapturedRequest, method: &str, path: &str) -> bool {
+    let method_matches =
+        method.eq_ignore_ascii_case("ANY") || request.method().eq_ignore_ascii_case(method);
+    let request_path = Url::parse(request.url())
+        .map(|url| url.path().to_string())
+        .unwrap_or_default();
+
+    method_matches && matches(path, &request_path)
+}
+
+/// Parse the request journal of WireMock, which lists the newest requests first
+fn journal_requests(journal: &Value) -> Result<Vec<CapturedRequest>> {
+    let entries = journal["requests"]
+        .as_array()
+        .context("failed to parse requests of mock service")?;
+
+    let mut requests: Vec<CapturedRequest> = entries
+        .iter()
+        .map(|entry| {
+            let request = &entry["request"];
+
+            let headers = request["headers"]
+                .as_object()
+                .map(|headers| {
+                    headers
+                        .iter()
+                        .flat_map(|(name, value)| match value {
+                            Value::Array(values) => values
+                                .iter()
+                                .filter_map(|value| value.as_str())
+                                .map(|value| (name.to_lowercase(), value.to_string()))
+                                .collect(),
+                            value => value
+                                .as_str()
+                                .map(|value| vec![(name.to_lowercase(), value.to_string())])
+                                .unwrap_or_default(),
+                        })
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            let body = request["bodyAsBase64"]
+                .as_str()
+                .and_then(|body| BASE64.decode(body).ok())
+                .unwrap_or_else(|| {
+                    request["body"]
+                        .as_str()
+                        .unwrap_or_default()
+                        .as_bytes()
+                        .to_vec()
+                });
+
+            CapturedRequest::new(
+                request["method"].as_str().unwrap_or_default().to_string(),
+                request["absoluteUrl"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                headers,
+                body,
+            )
+        })
+        .collect();
+
+    requests.reverse();
+    Ok(requests)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    use super::*;
+
+    fn journal() -> Value {
+        json!({
+            "requests": [
+                {
+                    "request": {
+                        "url": "/v1/charges?expand=customer",
+                        "absoluteUrl": "http://payments:8080/v1/charges?expand=customer",
+                        "method": "POST",
+                        "headers": { "Content-Type": "application/json" },
+                        "body": "{\"amount\":100}",
+                        "bodyAsBase64": "eyJhbW91bnQiOjEwMH0="
+                    }
+                },
+                {
+                    "request": {
+                        "url": "/v1/customers/42",
+                        "absoluteUrl": "http://payments:8080/v1/customers/42",
+                        "method": "GET",
+                        "headers": { "Accept": ["text/html", "application/json"] },
+                        "body": ""
+                    }
+                }
+            ]
+        })
+    }
+
+    #[test]
+    fn service_mock_sets_hostname() {
+        let service = Service::mock("payments");
+
+        assert_eq!(IMAGE, service.image());
+        assert_eq!("payments", service.host());
+    }
+
+    #[test]
+    fn mapping_with_exact_path() {
+        let mock = Mock::builder()
+            .method("post")
+            .path("/v1/charges")
+            .status(201)
+            .header("content-type", "application/json")
+            .body("{}")
+            .delay(Duration::from_millis(1500))
+            .build();
+
+        let mapping = mock.mapping();
+
+        assert_eq!("POST", mapping["request"]["method"]);
+        assert_eq!("/v1/charges", mapping["request"]["urlPath"]);
+        assert_eq!(201, mapping["response"]["status"]);
+        assert_eq!(
+            "application/json",
+            mapping["response"]["headers"]["content-type"]
+        );
+        assert_eq!("e30=", mapping["response"]["base64Body"]);
+        assert_eq!(1500, mapping["response"]["fixedDelayMilliseconds"]);
+    }
+
+    #[test]
+    fn mapping_with_wildcard_path() {
+        let mock = Mock::builder().path("/v1/customers/*.json").build();
+
+        let mapping = mock.mapping();
+
+        assert_eq!("ANY", mapping["request"]["method"]);
+        assert_eq!(
+            "/v1/customers/.*\\.json",
+            mapping["request"]["urlPathPattern"]
+        );
+        assert!(mapping["response"].get("fixedDelayMilliseconds").is_none());
+    }
+
+    #[test]
+    fn journal_requests_are_in_order() -> Result<()> {
+        let requests = journal_requests(&journal())?;
+
+        assert_eq!(2, requests.len());
+        assert_eq!("GET", requests[0].method());
+        assert_eq!(
+            vec![
+                (String::from("accept"), String::from("text/html")),
+                (String::from("accept"), String::from("application/json")),
+            ],
+            *requests[0].headers()
+        );
+        assert_eq!("POST", requests[1].method());
+        assert_eq!(r#"{"amount":100}"#, requests[1].text());
+
+        Ok(())
+    }
+
+    #[test]
+    fn request_matches_method_and_path() -> Result<()> {
+        let requests = journal_requests(&journal())?;
+
+        assert!(request_matches(&requests[1], "post", "/v1/charges"));
+        assert!(request_matches(&requests[0], "ANY", "/v1/customers/*"));
+        assert!(!request_matches(&requests[0], "POST", "/v1/customers/*"));
+        assert!(!request_matches(&requests[1], "POST", "/v1/charges/*"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<Mock>();
+        assert_send::<Mocks>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<Mock>();
+        assert_sync::<Mocks>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<Mock>();
+        assert_unpin::<Mocks>();
+    }
+}
@@ -0,0 +1,571 @@
+//! Network traffic of the browser that is recorded and stubbed by Doco
+
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{anyhow, Context};
+use getset::{CopyGetters, Getters};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use tokio::sync::Notify;
+use typed_builder::TypedBuilder;
+
+use crate::fs::write;
+use crate::har::har;
+use crate::{Fixture, Result, TestContext};
+
+/// HTTP request that the browser has sent
+#[derive(Clone, Eq, PartialEq, Debug, Getters)]
+pub struct CapturedRequest {
+    /// The method of the request, e.g. `POST`
+    #[getset(get = "pub")]
+    method: String,
+
+    /// The full URL of the request, e.g. `http://host.docker.internal:8080/api/todos`
+    #[getset(get = "pub")]
+    url: String,
+
+    /// The headers of the request in the order in which they were sent
+    #[getset(get = "pub")]
+    headers: Vec<(String, String)>,
+
+    /// The body of the request
+    #[getset(get = "pub")]
+    body: Vec<u8>,
+}
+
+impl CapturedRequest {
+    /// Create a new captured request
+    pub(crate) fn new(
+        method: String,
+        url: String,
+        headers: Vec<(String, String)>,
+        body: Vec<u8>,
+    ) -> Self {
+        Self {
+            method,
+            url,
+            headers,
+            body,
+        }
+    }
+
+    /// Return the value of the header with the given name, ignoring its case
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Return the body of the request as text
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// Deserialize the body of the request from JSON
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_slice(&self.body).context("failed to deserialize request body")
+    }
+}
+
+/// HTTP response that the browser has received
+#[derive(Clone, Eq, PartialEq, Debug, CopyGetters, Getters)]
+pub struct CapturedResponse {
+    /// The status code of the response, e.g. `200`
+    #[getset(get_copy = "pub")]
+    status: u16,
+
+    /// The headers of the response in the order in which they were received
+    #[getset(get = "pub")]
+    headers: Vec<(String, String)>,
+
+    /// The body of the response
+    #[getset(get = "pub")]
+    body: Vec<u8>,
+}
+
+impl CapturedResponse {
+    /// Create a new captured response
+    pub(crate) fn new(status: u16, headers: Vec<(String, String)>, body: Vec<u8>) -> Self {
+        Self {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    /// Return the value of the header with the given name, ignoring its case
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Return the body of the response as text
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// Deserialize the body of the response from JSON
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        serde_json::from_slice(&self.body).context("failed to deserialize response body")
+    }
+}
+
+/// Request of the browser together with its response
+#[derive(Clone, Eq, PartialEq, Debug, CopyGetters, Getters)]
+pub struct Exchange {
+    /// The request that the browser has sent
+    #[getset(get = "pub")]
+    request: CapturedRequest,
+
+    /// The response that the browser has received, or `None` while the request is pending
+    #[getset(get = "pub")]
+    response: Option<CapturedResponse>,
+
+    /// Whether the response has been returned by a [`Stub`] instead of the server
+    #[getset(get_copy = "pub")]
+    stubbed: bool,
+
+    /// The time at which the request was sent
+    #[getset(get_copy = "pub")]
+    started: SystemTime,
+
+    /// The time that it took to receive the response
+    #[getset(get_copy = "pub")]
+    duration: Option<Duration>,
+}
+
+/// Response that is returned instead of forwarding a request to its destination
+///
+/// Stubs are registered with [`Network::stub`] for a URL pattern. They make it possible to test the
+/// web application without calling third-party services, or to simulate errors that are hard to
+/// provoke on a real server.
+///
+/// # Example
+///
+/// ```rust
+/// use doco::Stub;
+///
+/// let unavailable = Stub::builder()
+///     .status(503)
+///     .header("retry-after", "120")
+///     .body("Service Unavailable")
+///     .build();
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug, CopyGetters, Getters, TypedBuilder)]
+pub struct Stub {
+    /// The status code of the response, e.g. `200`
+    #[builder(default = 200)]
+    #[getset(get_copy = "pub")]
+    status: u16,
+
+    /// The headers of the response
+    #[builder(via_mutators(init = Vec::new()), mutators(
+        pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) {
+            self.headers.push((name.into(), value.into()));
+        }
+    ))]
+    #[getset(get = "pub")]
+    headers: Vec<(String, String)>,
+
+    /// The body of the response
+    #[builder(default, setter(into))]
+    #[getset(get = "pub")]
+    body: Vec<u8>,
+}
+
+impl Stub {
+    /// Create a stub that responds with the given value as JSON
+    pub fn json<T: Serialize + ?Sized>(value: &T) -> Result<Self> {
+        Ok(Self::builder()
+            .header("content-type", "application/json")
+            .body(serde_json::to_vec(value)?)
+            .build())
+    }
+
+    /// Convert the stub into the response that the browser receives
+    pub(crate) fn response(&self) -> CapturedResponse {
+        CapturedResponse::new(self.status, self.headers.clone(), self.body.clone())
+    }
+}
+
+/// Recorded exchanges and registered stubs of a test
+#[derive(Debug, Default)]
+struct State {
+    /// The exchanges in the order in which the requests were sent
+    exchanges: Vec<(Exchange, Instant)>,
+
+    /// The stubs with their URL patterns in the order in which they were registered
+    stubs: Vec<(String, Stub)>,
+}
+
+/// Network traffic of the browser
+///
+/// When network recording is enabled with
+/// [`Doco::record_network`](crate::Doco::record_network), the browser sends its traffic through a
+/// proxy that Doco runs for each test. The proxy records every request and response, and can
+/// answer requests with stubs instead of forwarding them. Tests request the `Network` as a fixture
+/// to inspect the traffic. After each test, the traffic is saved as `network.har` in the test's
+/// artifacts directory, which can be opened in the developer tools of most browsers.
+///
+/// URL patterns match the full URL of a request. A `*` in the pattern matches any number of
+/// characters, e.g. `*/api/todos*` matches `http://host.docker.internal:8080/api/todos?page=2`.
+///
+/// HTTPS traffic is passed through the proxy without being decrypted. It is recorded with the
+/// method `CONNECT` and the host, but its content is not available and it cannot be stubbed. This
+/// means that stubs for third-party APIs only work for endpoints that the browser calls over plain
+/// HTTP, e.g. a mock of the API that the server under test points the browser to.
+///
+/// # Example
+///
+/// ```no_run
+/// use doco::{Client, Network, Result, Stub};
+/// use serde_json::json;
+///
+/// #[doco::test]
+/// async fn shows_weather(client: Client, network: Network) -> Result<()> {
+///     network.stub("http://api.weather.example/*", Stub::json(&json!({ "temp": 21 }))?);
+///
+///     client.goto("/").await?;
+///
+///     let exchange = network.wait_for_request("*/api/todos*").await?;
+///     assert_eq!("GET", exchange.request().method());
+///
+///     Ok(())
+/// }
+/// #
+/// # use doco::{Doco, Server};
+/// #
+/// # #[doco::main]
+/// # async fn main() -> Doco {
+/// #    let server = Server::builder()
+/// #        .image("crccheck/hello-world")
+/// #        .tag("v1.0.0")
+/// #        .port(8000)
+/// #        .build();
+/// #
+/// #    Doco::builder().server(server).record_network(true).build()
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Network {
+    /// The recorded exchanges and registered stubs
+    state: Arc<Mutex<State>>,
+
+    /// Notification that is sent whenever an exchange has been recorded or completed
+    changed: Arc<Notify>,
+
+    /// The time that [`Network::wait_for_request`] waits for a request
+    timeout: Duration,
+}
+
+impl Network {
+    /// Create a new, empty recording
+    pub(crate) fn new(timeout: Duration) -> Self {
+        Self {
+            state: Arc::default(),
+            changed: Arc::default(),
+            timeout,
+        }
+    }
+
+    /// Return all exchanges in the order in which the requests were sent
+    pub fn exchanges(&self) -> Vec<Exchange> {
+        self.state()
+            .exchanges
+            .iter()
+            .map(|(exchange, _)| exchange.clone())
+            .collect()
+    }
+
+    /// Return the exchanges whose URL matches the given pattern
+    pub fn exchanges_matching(&self, pattern: &str) -> Vec<Exchange> {
+        self.exchanges()
+            .into_iter()
+            .filter(|exchange| matches(pattern, exchange.request().url()))
+            .collect()
+    }
+
+    /// Wait for a completed exchange whose URL matches the given pattern
+    ///
+    /// Requests that have been sent before this method was called are considered as well, so that
+    /// it does not matter whether the request was sent before or after the call. The method waits
+    /// for the timeout of the test suite, and returns the first matching exchange that has received
+    /// a response.
+    pub async fn wait_for_request(&self, pattern: &str) -> Result<Exchange> {
+        let deadline = Instant::now() + self.timeout;
+
+        loop {
+            let changed = self.changed.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+
+            if let Some(exchange) = self.exchanges().into_iter().find(|exchange| {
+                exchange.response().is_some() && matches(pattern, exchange.request().url())
+            }) {
+                return Ok(exchange);
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if tokio::time::timeout(remaining, changed).await.is_err() {
+                return Err(anyhow!(
+                    "timed out after {:?} waiting for a request to `{pattern}`",
+                    self.timeout
+                ));
+            }
+        }
+    }
+
+    /// Respond to requests whose URL matches the pattern with the given stub
+    ///
+    /// Stubs that are registered later take precedence over earlier ones. Only requests over plain
+    /// HTTP can be stubbed, since HTTPS connections are tunneled through the proxy.
+    pub fn stub(&self, pattern: &str, stub: Stub) {
+        self.state().stubs.push((pattern.to_string(), stub));
+    }
+
+    /// Remove all stubs so that requests are forwarded again
+    pub fn clear_stubs(&self) {
+        self.state().stubs.clear();
+    }
+
+    /// Return the recorded exchanges in the HTTP Archive (HAR) format
+    pub fn har(&self) -> Value {
+        har(&self.exchanges())
+    }
+
+    /// Save the recorded exchanges as an HTTP Archive (HAR) file
+    pub fn save_har(&self, path: &Path) -> Result<()> {
+        write(path, &serde_json::to_vec_pretty(&self.har())?)
+    }
+
+    /// Record a request and return its index for [`Network::record_response`]
+    pub(crate) fn record_request(&self, request: CapturedRequest, stubbed: bool) -> usize {
+        let exchange = Exchange {
+            request,
+            response: None,
+            stubbed,
+            started: SystemTime::now(),
+            duration: None,
+        };
+
+        let index = {
+            let mut state = self.state();
+            state.exchanges.push((exchange, Instant::now()));
+            state.exchanges.len() - 1
+        };
+
+        self.changed.notify_waiters();
+        index
+    }
+
+    /// Record the response to the request with the given index
+    pub(crate) fn record_response(&self, index: usize, response: CapturedResponse) {
+        if let Some((exchange, started)) = self.state().exchanges.get_mut(index) {
+            exchange.response = Some(response);
+            exchange.duration = Some(started.elapsed());
+        }
+
+        self.changed.notify_waiters();
+    }
+
+    /// Return the stub that responds to requests to the given URL, if any
+    pub(crate) fn stub_for(&self, url: &str) -> Option<Stub> {
+        self.state()
+            .stubs
+            .iter()
+            .rev()
+            .find(|(pattern, _)| matches(pattern, url))
+            .map(|(_, stub)| stub.clone())
+    }
+
+    /// Lock the state of the recording
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+impl Fixture for Network {
+    async fn setup(context: &TestContext) -> Result<Self> {
+        context.network().clone().ok_or_else(|| {
+            anyhow!("network recording is disabled, enable it with `Doco::builder().record_network(true)`")
+        })
+    }
+}
+
+/// Return the value of the header with the given name, ignoring its case
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(header, _)| header.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Check if a URL matches a pattern in which `*` matches any number of characters
+pub(crate) fn matches(pattern: &str, url: &str) -> bool {
+    let mut parts = pattern.split('*');
+
+    // The pattern is never empty after splitting, so the first part always exists
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = url.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    use super::*;
+
+    fn request(url: &str) -> CapturedRequest {
+        CapturedRequest::new(
+            String::from("GET"),
+            url.to_string(),
+            vec![(String::from("Accept"), String::from("application/json"))],
+            Vec::new(),
+        )
+    }
+
+    #[test]
+    fn matches_exact_url() {
+        assert!(matches("http://app/api", "http://app/api"));
+        assert!(!matches("http://app/api", "http://app/api/todos"));
+    }
+
+    #[test]
+    fn matches_wildcards() {
+        assert!(matches("*/api/*", "http://app/api/todos"));
+        assert!(matches("*/api/todos*", "http://app/api/todos?page=2"));
+        assert!(matches("http://*/todos", "http://app/api/todos"));
+        assert!(!matches("*/api/users*", "http://app/api/todos"));
+    }
+
+    #[test]
+    fn matches_overlapping_parts() {
+        assert!(matches("*ab*ab", "xabab"));
+        assert!(!matches("*ab*ab", "xab"));
+    }
+
+    #[test]
+    fn header_ignores_case() {
+        assert_eq!(
+            Some("application/json"),
+            request("http://app/").header("accept")
+        );
+    }
+
+    #[test]
+    fn records_exchanges() {
+        let network = Network::new(Duration::from_secs(1));
+
+        let index = network.record_request(request("http://app/api/todos"), false);
+        network.record_response(index, CapturedResponse::new(204, Vec::new(), Vec::new()));
+
+        let exchanges = network.exchanges_matching("*/todos");
+
+        assert_eq!(1, exchanges.len());
+        assert_eq!(
+            Some(204),
+            exchanges[0].response().as_ref().map(|r| r.status())
+        );
+    }
+
+    #[test]
+    fn later_stubs_take_precedence() {
+        let network = Network::new(Duration::from_secs(1));
+
+        network.stub("*", Stub::builder().status(500).build());
+        network.stub("*/api/*", Stub::builder().status(404).build());
+
+        assert_eq!(
+            Some(404),
+            network.stub_for("http://app/api/todos").map(|s| s.status())
+        );
+        assert_eq!(
+            Some(500),
+            network.stub_for("http://app/").map(|s| s.status())
+        );
+
+        network.clear_stubs();
+
+        assert_eq!(None, network.stub_for("http://app/"));
+    }
+
+    #[test]
+    fn stub_json() -> Result<()> {
+        let stub = Stub::json(&serde_json::json!({ "temp": 21 }))?;
+
+        assert_eq!(br#"{"temp":21}"#.to_vec(), *stub.body());
+        assert_eq!(
+            Some("application/json"),
+            find_header(stub.headers(), "Content-Type")
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn wait_for_request_finds_later_request() -> Result<()> {
+        let network = Network::new(Duration::from_secs(5));
+
+        let recorder = network.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            let index = recorder.record_request(request("http://app/api/todos"), false);
+            recorder.record_response(index, CapturedResponse::new(200, Vec::new(), Vec::new()));
+        });
+
+        let exchange = network.wait_for_request("*/api/todos").await?;
+
+        assert_eq!("http://app/api/todos", exchange.request().url());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn wait_for_request_times_out() {
+        let network = Network::new(Duration::from_millis(50));
+
+        let error = network.wait_for_request("*/api/*").await.unwrap_err();
+
+        assert_eq!(
+            "timed out after 50ms waiting for a request to `*/api/*`",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<Network>();
+        assert_send::<Exchange>();
+        assert_send::<Stub>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<Network>();
+        assert_sync::<Exchange>();
+        assert_sync::<Stub>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<Network>();
+        assert_unpin::<Exchange>();
+        assert_unpin::<Stub>();
+    }
+}
@@ -0,0 +1,80 @@
+//! Page objects that describe the pages of the web application
+
+use std::future::Future;
+
+use crate::{Client, Result};
+
+/// Page object that describes a page of the web application
+///
+/// Page objects collect the path and the elements of a page in one place, so that tests do not
+/// need to repeat the same selectors over and over again. The trait is usually implemented with
+/// the [`Page`](derive@crate::Page) derive macro, which turns every field annotated with
+/// `#[locator(...)]` into a [`LazyElement`](crate::LazyElement) and generates an accessor for it.
+///
+/// The following locators are supported in the `#[locator(...)]` attribute: `css`, `id`,
+/// `link_text`, `xpath`, `test_id`, `text`, `label`, `placeholder`, and `role` with an optional
+/// `name`. Fields without the attribute must be a [`Client`] and receive a clone of it. Since the
+/// accessors are methods of the page, fields with a locator cannot be named `new` or `visit`.
+///
+/// Page objects that are derived with the macro can also be requested as fixtures by tests. The
+/// fixture navigates to the page before the test is run.
+///
+/// # Example
+///
+/// ```no_run
+/// use doco::{expect, Client, LazyElement, Page, Result};
+///
+/// #[derive(Page)]
+/// #[page(path = "/login")]
+/// struct LoginPage {
+///     #[locator(label = "Email")]
+///     email: LazyElement,
+///
+///     #[locator(role = "button", name = "Sign in")]
+///     submit: LazyElement,
+/// }
+///
+/// #[doco::test]
+/// async fn signs_in(client: Client) -> Result<()> {
+///     let page = LoginPage::visit(&client).await?;
+///
+///     page.email().resolve().await?.send_keys("user@example.com").await?;
+///     page.submit().resolve().await?.click().await?;
+///
+///     expect(&client).to_have_url("/dashboard").await
+/// }
+///
+/// #[doco::test]
+/// async fn shows_form(page: LoginPage) -> Result<()> {
+///     expect(page.submit()).to_be_visible().await
+/// }
+/// #
+/// # use doco::{Doco, Server};
+/// #
+/// # #[doco::main]
+/// # async fn main() -> Doco {
+/// #    let server = Server::builder()
+/// #        .image("crccheck/hello-world")
+/// #        .tag("v1.0.0")
+/// #        .port(8000)
+/// #        .build();
+/// #
+/// #    Doco::builder().server(server).build()
+/// # }
+/// ```
+pub trait Page: Sized {
+    /// The path of the page, relative to the base URL of the server
+    const PATH: &'static str;
+
+    /// Create the page object for the given client without navigating to the page
+    fn new(client: &Client) -> Self;
+
+    /// Navigate to the page and return the page object
+    fn visit(client: &Client) -> impl Future<Output = Result<Self>> {
+        async move {
+            client.goto(Self::PATH).await?;
+
+            Ok(Self::new(client))
+        }
+    }
+}
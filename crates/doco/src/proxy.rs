@@ -0,0 +1,506 @@
+//! Recording proxy through which the browser sends its traffic
+
+use std::convert::Infallible;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use anyhow::Context;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::header::{HeaderMap, HeaderName};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response};
+use hyper_util::rt::TokioIo;
+use reqwest::redirect::Policy;
+use reqwest::Url;
+use serde_json::{json, Value};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+use crate::network::{CapturedRequest, CapturedResponse, Network};
+use crate::test_runner::DOCKER_HOST;
+use crate::Result;
+
+/// Headers that apply to a single connection and must not be forwarded by a proxy
+const HOP_BY_HOP_HEADERS: &[&str] = &[
+    "connection",
+    "content-length",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Recording proxy through which the browser sends its traffic
+///
+/// The proxy runs inside the test process and listens on a random port. The browser reaches it
+/// through the host gateway of Docker, just like the server. Since the proxy forwards requests
+/// without authentication, it only listens on the loopback interface and on the gateway of the
+/// Docker bridge, and not on the other interfaces of the machine.
+///
+/// Plain HTTP requests are recorded in the [`Network`], answered by a stub if one matches, and
+/// otherwise forwarded to their destination. HTTPS connections are tunneled without being
+/// decrypted.
+#[derive(Debug)]
+pub(crate) struct Proxy {
+    /// The addresses on which the proxy listens, which all share the same port
+    addresses: Vec<SocketAddr>,
+
+    /// The tasks that accept connections, one for each address
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl Proxy {
+    /// Start a proxy that records the traffic in the given network
+    ///
+    /// The proxy listens on the loopback interface, and on the given gateway through which the
+    /// containers reach the host if it is an address of this machine. Requests to the Docker host
+    /// are forwarded to the given host of the server, where the ports of the containers are
+    /// published.
+    pub(crate) async fn start(
+        network: Network,
+        server_host: String,
+        gateway: Option<IpAddr>,
+    ) -> Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .context("failed to start network proxy")?;
+        let port = listener.local_addr()?.port();
+
+        let mut listeners = vec![listener];
+        if let Some(gateway) = gateway.filter(|gateway| !gateway.is_loopback()) {
+            // On Docker Desktop, the gateway is an address of a virtual machine that cannot be
+            // bound, and the host gateway forwards to the loopback interface instead
+            if let Ok(listener) = TcpListener::bind((gateway, port)).await {
+                listeners.push(listener);
+            }
+        }
+
+        let upstream = reqwest::Client::builder()
+            .redirect(Policy::none())
+            .no_proxy()
+            .build()
+            .context("failed to build HTTP client for network proxy")?;
+
+        let addresses = listeners
+            .iter()
+            .map(TcpListener::local_addr)
+            .collect::<std::io::Result<_>>()?;
+
+        let tasks = listeners
+            .into_iter()
+            .map(|listener| {
+                tokio::spawn(accept(
+                    listener,
+                    network.clone(),
+                    upstream.clone(),
+                    server_host.clone(),
+                ))
+            })
+            .collect();
+
+        Ok(Self { addresses, tasks })
+    }
+
+    /// Return the port on which the proxy listens
+    fn port(&self) -> u16 {
+        self.addresses[0].port()
+    }
+
+    /// Return the WebDriver capability that configures the browser to use the proxy
+    pub(crate) fn capability(&self) -> Value {
+        let address = format!("{DOCKER_HOST}:{}", self.port());
+
+        json!({
+            "proxyType": "manual",
+            "httpProxy": address,
+            "sslProxy": address,
+        })
+    }
+}
+
+impl Drop for Proxy {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Accept connections on the listener and handle their requests
+async fn accept(
+    listener: TcpListener,
+    network: Network,
+    upstream: reqwest::Client,
+    server_host: String,
+) {
+    while let Ok((stream, _)) = listener.accept().await {
+        let network = network.clone();
+        let upstream = upstream.clone();
+        let server_host = server_host.clone();
+
+        tokio::spawn(async move {
+            let service = service_fn(move |request| {
+                handle(
+                    network.clone(),
+                    upstream.clone(),
+                    server_host.clone(),
+                    request,
+                )
+            });
+
+            let _ = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .with_upgrades()
+                .await;
+        });
+    }
+}
+
+/// Handle a single request from the browser
+async fn handle(
+    network: Network,
+    upstream: reqwest::Client,
+    server_host: String,
+    request: Request<Incoming>,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    if request.method() == Method::CONNECT {
+        return Ok(tunnel(&network, request));
+    }
+
+    let (parts, body) = request.into_parts();
+    let body = body
+        .collect()
+        .await
+        .map(|body| body.to_bytes())
+        .unwrap_or_default();
+
+    let url = parts.uri.to_string();
+    let stub = network.stub_for(&url);
+
+    let index = network.record_request(
+        CapturedRequest::new(
+            parts.method.to_string(),
+            url.clone(),
+            headers(&parts.headers),
+            body.to_vec(),
+        ),
+        stub.is_some(),
+    );
+
+    let response = match stub {
+        Some(stub) => stub.response(),
+        None => {
+            forward(
+                &upstream,
+                &server_host,
+                parts.method,
+                &url,
+                &parts.headers,
+                body,
+            )
+            .await
+        }
+    };
+
+    network.record_response(index, response.clone());
+
+    Ok(into_response(response))
+}
+
+/// Forward a request to its destination and return the response
+///
+/// The browser addresses the server with the hostname of the Docker host, which does not resolve
+/// on the host itself. Such requests are sent to the host of the server instead, where the ports
+/// of the containers are published.
+async fn forward(
+    upstream: &reqwest::Client,
+    server_host: &str,
+    method: Method,
+    url: &str,
+    request_headers: &HeaderMap,
+    body: Bytes,
+) -> CapturedResponse {
+    let mut url = match Url::parse(url) {
+        Ok(url) => url,
+        Err(error) => return error_response(400, &format!("invalid URL `{url}`: {error}")),
+    };
+
+    if url.host_str() == Some(DOCKER_HOST) {
+        let _ = url.set_host(Some(server_host));
+    }
+
+    let mut request = upstream.request(method, url).body(body);
+    for (name, value) in request_headers {
+        if !is_hop_by_hop(name) {
+            request = request.header(name, value);
+        }
+    }
+
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(error) => return error_response(502, &error.to_string()),
+    };
+
+    let status = response.status().as_u16();
+    let headers = headers(response.headers());
+
+    match response.bytes().await {
+        Ok(body) => CapturedResponse::new(status, headers, body.to_vec()),
+        Err(error) => error_response(502, &error.to_string()),
+    }
+}
+
+/// Tunnel an HTTPS connection to its destination without decrypting it
+fn tunnel(network: &Network, request: Request<Incoming>) -> Response<Full<Bytes>> {
+    let authority = request
+        .uri()
+        .authority()
+        .map(|authority| authority.to_string())
+        .unwrap_or_default();
+
+    let index = network.record_request(
+        CapturedRequest::new(
+            Method::CONNECT.to_string(),
+            authority.clone(),
+            headers(request.headers()),
+            Vec::new(),
+        ),
+        false,
+    );
+    network.record_response(index, CapturedResponse::new(200, Vec::new(), Vec::new()));
+
+    tokio::spawn(async move {
+        let Ok(upgraded) = hyper::upgrade::on(request).await else {
+            return;
+        };
+        let Ok(mut destination) = TcpStream::connect(&authority).await else {
+            return;
+        };
+
+        let _ = tokio::io::copy_bidirectional(&mut TokioIo::new(upgraded), &mut destination).await;
+    });
+
+    Response::new(Full::new(Bytes::new()))
+}
+
+/// Convert a recorded response into the response that is sent to the browser
+fn into_response(captured: CapturedResponse) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from(captured.body().clone())));
+    *response.status_mut() = captured
+        .status()
+        .try_into()
+        .unwrap_or(hyper::StatusCode::BAD_GATEWAY);
+
+    for (name, value) in captured.headers() {
+        let (Ok(name), Ok(value)) = (name.parse::<HeaderName>(), value.parse()) else {
+            continue;
+        };
+
+        if !is_hop_by_hop(&name) {
+            response.headers_mut().append(name, value);
+        }
+    }
+
+    response
+}
+
+/// Create a response for a request that the proxy could not forward
+fn error_response(status: u16, message: &str) -> CapturedResponse {
+    CapturedResponse::new(
+        status,
+        vec![(String::from("content-type"), String::from("text/plain"))],
+        message.as_bytes().to_vec(),
+    )
+}
+
+/// Check if a header applies to a single connection only
+fn is_hop_by_hop(name: &HeaderName) -> bool {
+    HOP_BY_HOP_HEADERS.contains(&name.as_str())
+}
+
+/// Convert a header map into name-value pairs, skipping values that are not valid strings
+fn headers(headers: &HeaderMap) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|value| (name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::routing::get;
+    use axum::Router;
+
+    use crate::test_utils::*;
+
+    use super::*;
+
+    /// Start a proxy for a server that publishes its ports on the loopback interface
+    async fn start(network: Network) -> Result<Proxy> {
+        Proxy::start(network, String::from("127.0.0.1"), None).await
+    }
+
+    /// Send a request through the proxy to the given URL
+    async fn send_through(proxy: &Proxy, url: &str) -> Result<reqwest::Response> {
+        let client = reqwest::Client::builder()
+            .proxy(reqwest::Proxy::http(format!(
+                "http://127.0.0.1:{}",
+                proxy.port()
+            ))?)
+            .build()?;
+
+        Ok(client.get(url).header("x-test", "1").send().await?)
+    }
+
+    #[tokio::test]
+    async fn forwards_and_records_requests() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+
+        let app = Router::new().route("/hello", get(|| async { "hello through the proxy" }));
+        tokio::spawn(async { axum::serve(listener, app).await });
+
+        let network = Network::new(std::time::Duration::from_secs(5));
+        let proxy = start(network.clone()).await?;
+
+        let url = format!("http://127.0.0.1:{port}/hello");
+        let response = send_through(&proxy, &url).await?;
+
+        assert_eq!(200, response.status().as_u16());
+        assert_eq!("hello through the proxy", response.text().await?);
+
+        let exchange = network.wait_for_request("*/hello").await?;
+
+        assert_eq!(Some("1"), exchange.request().header("x-test"));
+        assert_eq!(
+            "hello through the proxy",
+            exchange
+                .response()
+                .as_ref()
+                .map(|r| r.text())
+                .unwrap_or_default()
+        );
+        assert!(!exchange.stubbed());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn forwards_docker_host_to_server_host() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+
+        let app = Router::new().route("/hello", get(|| async { "hello from the server" }));
+        tokio::spawn(async { axum::serve(listener, app).await });
+
+        let proxy = start(Network::new(std::time::Duration::from_secs(5))).await?;
+
+        let url = format!("http://{DOCKER_HOST}:{port}/hello");
+        let response = send_through(&proxy, &url).await?;
+
+        assert_eq!("hello from the server", response.text().await?);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn listens_only_on_loopback_without_gateway() -> Result<()> {
+        let proxy = start(Network::new(Default::default())).await?;
+
+        assert!(!proxy.addresses.is_empty());
+        assert!(proxy
+            .addresses
+            .iter()
+            .all(|address| address.ip().is_loopback()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ignores_gateway_that_cannot_be_bound() -> Result<()> {
+        let gateway = "192.0.2.1".parse()?;
+        let proxy = Proxy::start(
+            Network::new(Default::default()),
+            String::from("127.0.0.1"),
+            Some(gateway),
+        )
+        .await?;
+
+        assert_eq!(1, proxy.addresses.len());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn responds_with_stubs() -> Result<()> {
+        let network = Network::new(std::time::Duration::from_secs(5));
+        let proxy = start(network.clone()).await?;
+
+        network.stub(
+            "http://api.example.com/*",
+            crate::Stub::builder()
+                .status(418)
+                .header("x-stub", "yes")
+                .body("stubbed")
+                .build(),
+        );
+
+        let response = send_through(&proxy, "http://api.example.com/teapot").await?;
+
+        assert_eq!(418, response.status().as_u16());
+        assert_eq!(
+            Some("yes"),
+            response
+                .headers()
+                .get("x-stub")
+                .and_then(|v| v.to_str().ok())
+        );
+        assert_eq!("stubbed", response.text().await?);
+        assert!(network.exchanges()[0].stubbed());
+
+        Ok(())
+    }
+
+    #[test]
+    fn capability_uses_docker_host() -> Result<()> {
+        let runtime = tokio::runtime::Runtime::new()?;
+        let proxy = runtime.block_on(start(Network::new(Default::default())))?;
+
+        let capability = proxy.capability();
+
+        assert_eq!("manual", capability["proxyType"]);
+        assert_eq!(
+            format!("{DOCKER_HOST}:{}", proxy.port()),
+            capability["httpProxy"]
+        );
+
+        drop(proxy);
+        Ok(())
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<Proxy>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<Proxy>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<Proxy>();
+    }
+}
@@ -0,0 +1,320 @@
+//! Reporters that present the progress and results of the test suite
+
+use std::cell::RefCell;
+use std::fmt::{Debug, Display, Formatter};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use getset::{CopyGetters, Getters};
+
+use crate::{Log, Status, Summary, TestCase, TestOutcome};
+
+/// Reporter that is notified about the progress of the test suite
+///
+/// The test runner calls the methods of a reporter at each step of the test suite's lifecycle.
+/// Every method has an empty default implementation, so that a reporter only implements the events
+/// that it is interested in. Doco ships with reporters in [`doco::reporters`](crate::reporters),
+/// and custom reporters are registered with [`Doco::builder`](crate::Doco::builder). When no
+/// reporter has been registered, the [`Pretty`](crate::reporters::Pretty) reporter is used.
+///
+/// # Example
+///
+/// ```rust
+/// use doco::{Doco, Reporter, Server, TestOutcome};
+///
+/// #[derive(Debug)]
+/// struct Failures;
+///
+/// impl Reporter for Failures {
+///     fn test_finished(&self, outcome: &TestOutcome) {
+///         if let Some(error) = outcome.error() {
+///             eprintln!("{} failed: {error}", outcome.name());
+///         }
+///     }
+/// }
+///
+/// fn doco() -> Doco {
+///     let server = Server::builder()
+///         .image("crccheck/hello-world")
+///         .tag("v1.0.0")
+///         .port(8000)
+///         .build();
+///
+///     Doco::builder()
+///         .server(server)
+///         .reporter(doco::reporters::Pretty)
+///         .reporter(Failures)
+///         .build()
+/// }
+/// ```
+pub trait Reporter: Debug + Send + Sync {
+    /// Called before the first test with the number of tests that will be run and filtered out
+    fn suite_started(&self, _tests: usize, _filtered: usize) {}
+
+    /// Called before the containers of a test are started
+    fn provisioning_started(&self, _test: &str) {}
+
+    /// Called after the containers of a test have started
+    fn provisioning_finished(&self, _test: &str, _provisioning: &Provisioning) {}
+
+    /// Called before the environment of a test is provisioned
+    fn test_started(&self, _test: &TestCase) {}
+
+    /// Called after a step of a test has finished, i.e. a hook or the test function itself
+    fn step_finished(&self, _test: &str, _step: &str, _status: Status, _duration: Duration) {}
+
+    /// Called with the logs of each container after a test or one of its hooks has failed
+    fn test_logs(&self, _test: &str, _container: &str, _log: &Log) {}
+
+    /// Called after a test has saved an artifact in its artifacts directory
+    fn artifact(&self, _test: &str, _kind: ArtifactKind, _path: &Path) {}
+
+    /// Called when something has gone wrong that does not fail the test suite
+    ///
+    /// Warnings are reported, for example, when an artifact cannot be saved or when another
+    /// reporter cannot write its output. The test is `None` for warnings that concern the whole
+    /// test suite.
+    fn warning(&self, _test: Option<&str>, _message: &str) {}
+
+    /// Called after a test and its hooks have finished
+    fn test_finished(&self, _outcome: &TestOutcome) {}
+
+    /// Called after all tests and hooks have been run
+    fn suite_finished(&self, _summary: &Summary) {}
+
+    /// Check if the reporter writes to stdout
+    ///
+    /// Reporters that write to stdout cannot be combined with `--events -`, which writes the event
+    /// stream to stdout, since they would break the stream for the tools that read it.
+    fn writes_to_stdout(&self) -> bool {
+        false
+    }
+}
+
+thread_local! {
+    /// The reporters that receive the warnings of the reporter that is currently being notified
+    static OTHERS: RefCell<Vec<Arc<dyn Reporter>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Notify each of the given reporters, forwarding its warnings to the other reporters
+///
+/// Reporters are notified synchronously, so the reporters that receive the warnings are kept in a
+/// thread-local variable for the duration of each notification.
+pub(crate) fn notify(reporters: &[Arc<dyn Reporter>], notify: impl Fn(&dyn Reporter)) {
+    for (index, reporter) in reporters.iter().enumerate() {
+        let others = reporters
+            .iter()
+            .enumerate()
+            .filter(|(other, _)| *other != index)
+            .map(|(_, other)| Arc::clone(other))
+            .collect();
+
+        let previous = OTHERS.replace(others);
+        notify(reporter.as_ref());
+        OTHERS.set(previous);
+    }
+}
+
+/// Pass a warning of the reporter that is currently being notified on to the other reporters
+///
+/// The warning is printed to stderr if there are no other reporters. While the warning is passed
+/// on, further warnings are printed as well, so that a reporter that fails to report a warning
+/// does not cause an endless loop.
+pub(crate) fn warn(test: Option<&str>, message: &str) {
+    let others = OTHERS.take();
+
+    if others.is_empty() {
+        match test {
+            Some(test) => eprintln!("warning: {test}: {message}"),
+            None => eprintln!("warning: {message}"),
+        }
+    }
+
+    for reporter in &others {
+        reporter.warning(test, message);
+    }
+
+    OTHERS.set(others);
+}
+
+/// Kind of artifact that a test has saved
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum ArtifactKind {
+    /// The directory with the logs of the server and its services
+    Logs,
+
+    /// The network traffic of the browser as an HTTP Archive (HAR) file
+    Har,
+
+    /// The video of the browser session
+    Video,
+
+    /// A screenshot that does not match its baseline, or the differences to the baseline
+    Screenshot,
+
+    /// An HTML snapshot that does not match its baseline
+    HtmlSnapshot,
+}
+
+impl Display for ArtifactKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            ArtifactKind::Logs => "logs",
+            ArtifactKind::Har => "har",
+            ArtifactKind::Video => "video",
+            ArtifactKind::Screenshot => "screenshot",
+            ArtifactKind::HtmlSnapshot => "html_snapshot",
+        };
+
+        write!(f, "{kind}")
+    }
+}
+
+/// Time it took to provision the environment of a test
+#[derive(Clone, Eq, PartialEq, Debug, CopyGetters, Getters)]
+pub struct Provisioning {
+    /// The time it took to start the whole environment
+    #[getset(get_copy = "pub")]
+    duration: Duration,
+
+    /// The time it took to start each container
+    #[getset(get = "pub")]
+    containers: Vec<ContainerTiming>,
+
+    /// The time it took to create the WebDriver session of the browser
+    #[getset(get_copy = "pub")]
+    session: Duration,
+}
+
+impl Provisioning {
+    /// Create the provisioning timings of an environment
+    pub(crate) fn new(
+        duration: Duration,
+        containers: Vec<ContainerTiming>,
+        session: Duration,
+    ) -> Self {
+        Self {
+            duration,
+            containers,
+            session,
+        }
+    }
+}
+
+/// Time it took to start a container, broken down into its phases
+///
+/// The phases add up to the total [`duration`](ContainerTiming::duration). Pulling takes no time
+/// when the image exists locally, and waiting for readiness takes no time for containers without a
+/// wait condition.
+#[derive(Clone, Eq, PartialEq, Debug, CopyGetters, Getters)]
+pub struct ContainerTiming {
+    /// The hostname of the service, or `server` for the server
+    #[getset(get = "pub")]
+    name: String,
+
+    /// The time it took to pull the image of the container
+    #[getset(get_copy = "pub")]
+    pull: Duration,
+
+    /// The time it took to create and start the container
+    #[getset(get_copy = "pub")]
+    start: Duration,
+
+    /// The time it took for the container to become ready after it has started
+    #[getset(get_copy = "pub")]
+    ready: Duration,
+}
+
+impl ContainerTiming {
+    /// Record the time that each phase of starting the container with the given name took
+    pub(crate) fn new(
+        name: impl Into<String>,
+        pull: Duration,
+        start: Duration,
+        ready: Duration,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            pull,
+            start,
+            ready,
+        }
+    }
+
+    /// Return the time it took to pull, start, and wait for the container
+    pub fn duration(&self) -> Duration {
+        self.pull + self.start + self.ready
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    use super::*;
+
+    #[test]
+    fn artifact_kind_display() {
+        assert_eq!("har", ArtifactKind::Har.to_string());
+    }
+
+    /// Reporter that records the warnings it receives and emits one for every started suite
+    #[derive(Debug, Default)]
+    struct Warnings(std::sync::Mutex<Vec<String>>);
+
+    impl Reporter for Warnings {
+        fn suite_started(&self, _tests: usize, _filtered: usize) {
+            warn(None, "failed to write report");
+        }
+
+        fn warning(&self, _test: Option<&str>, message: &str) {
+            self.0.lock().unwrap().push(message.into());
+        }
+    }
+
+    #[test]
+    fn notify_forwards_warnings_to_other_reporters() {
+        let first = Arc::new(Warnings::default());
+        let second = Arc::new(Warnings::default());
+        let reporters: Vec<Arc<dyn Reporter>> = vec![first.clone(), second.clone()];
+
+        notify(&reporters, |reporter| reporter.suite_started(1, 0));
+
+        assert_eq!(vec!["failed to write report"], *first.0.lock().unwrap());
+        assert_eq!(vec!["failed to write report"], *second.0.lock().unwrap());
+    }
+
+    #[test]
+    fn container_timing_adds_up_phases() {
+        let timing = ContainerTiming::new(
+            "postgres",
+            Duration::from_secs(3),
+            Duration::from_millis(500),
+            Duration::from_millis(1500),
+        );
+
+        assert_eq!(Duration::from_secs(5), timing.duration());
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<ArtifactKind>();
+        assert_send::<Provisioning>();
+        assert_send::<ContainerTiming>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<ArtifactKind>();
+        assert_sync::<Provisioning>();
+        assert_sync::<ContainerTiming>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<ArtifactKind>();
+        assert_unpin::<Provisioning>();
+        assert_unpin::<ContainerTiming>();
+    }
+}
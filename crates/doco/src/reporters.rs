@@ -0,0 +1,610 @@
+//! Reporters that ship with Doco
+//!
+//! The [`Pretty`] reporter is used unless other reporters have been registered on the
+//! [`Doco`](crate::Doco) builder. Several reporters can run at once, for example [`Pretty`] for the
+//! console and [`JUnit`] for the CI pipeline.
+//!
+//! # Example
+//!
+//! ```rust
+//! use doco::reporters::{JUnit, Terse};
+//! use doco::{Doco, Server};
+//!
+//! fn doco() -> Doco {
+//!     let server = Server::builder()
+//!         .image("crccheck/hello-world")
+//!         .tag("v1.0.0")
+//!         .port(8000)
+//!         .build();
+//!
+//!     Doco::builder()
+//!         .server(server)
+//!         .reporter(Terse)
+//!         .reporter(JUnit::new("target/doco/junit.xml"))
+//!         .build()
+//! }
+//! ```
+
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::events::{create, Event, Events};
+use crate::fs::write;
+use crate::reporter::warn;
+use crate::{
+    ArtifactKind, HookFailure, Log, Provisioning, Reporter, Result, Status, Summary, TestCase,
+    TestOutcome,
+};
+
+/// The target of [`Json::open`] that writes the events to stdout instead of a file
+const STDOUT: &str = "-";
+
+/// The number of tests and containers that are listed as the slowest in the summary
+const SLOWEST: usize = 5;
+
+/// Reporter that prints the progress of each test and a summary with all failures
+///
+/// For each test, the time it took to start each container and the browser session is printed,
+/// as well as the time each hook and the test itself took. The logs of the server and its services
+/// are printed when a test fails.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Pretty;
+
+impl Reporter for Pretty {
+    fn suite_started(&self, tests: usize, filtered: usize) {
+        println!("Running {tests} tests ({filtered} filtered out)...\n");
+    }
+
+    fn provisioning_finished(&self, _test: &str, provisioning: &Provisioning) {
+        for container in provisioning.containers() {
+            println!(
+                "  started {} in {} (pull {}, start {}, ready {})",
+                container.name(),
+                seconds(container.duration()),
+                seconds(container.pull()),
+                seconds(container.start()),
+                seconds(container.ready())
+            );
+        }
+
+        println!(
+            "  created browser session in {}",
+            seconds(provisioning.session())
+        );
+    }
+
+    fn test_started(&self, test: &TestCase) {
+        println!("{}...", test.qualified_name());
+    }
+
+    fn step_finished(&self, _test: &str, step: &str, status: Status, duration: Duration) {
+        println!("  ran {step} in {}: {status}", seconds(duration));
+    }
+
+    fn test_logs(&self, _test: &str, container: &str, log: &Log) {
+        println!("\n--- logs of {container} ---");
+        print!("{}", log.stdout());
+        eprint!("{}", log.stderr());
+    }
+
+    fn warning(&self, test: Option<&str>, message: &str) {
+        print_warning(test, message);
+    }
+
+    fn test_finished(&self, outcome: &TestOutcome) {
+        println!(
+            "{}: {} in {}",
+            outcome.name(),
+            outcome.status(),
+            seconds(outcome.duration())
+        );
+    }
+
+    fn suite_finished(&self, summary: &Summary) {
+        print_summary(summary);
+    }
+
+    fn writes_to_stdout(&self) -> bool {
+        true
+    }
+}
+
+/// Reporter that prints a single character for each test and a summary with all failures
+///
+/// Passed tests are printed as `.`, failed tests as `F`, and skipped tests as `s`. The logs of
+/// failed tests are not printed, but they are saved in the artifacts directory.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default)]
+pub struct Terse;
+
+impl Reporter for Terse {
+    fn suite_started(&self, tests: usize, _filtered: usize) {
+        println!("Running {tests} tests");
+    }
+
+    fn test_finished(&self, outcome: &TestOutcome) {
+        let status = match outcome.status() {
+            Status::Passed => '.',
+            Status::Failed => 'F',
+            Status::Skipped => 's',
+        };
+
+        print!("{status}");
+        let _ = std::io::stdout().flush();
+    }
+
+    fn warning(&self, test: Option<&str>, message: &str) {
+        print_warning(test, message);
+    }
+
+    fn suite_finished(&self, summary: &Summary) {
+        println!();
+        print_summary(summary);
+    }
+
+    fn writes_to_stdout(&self) -> bool {
+        true
+    }
+}
+
+/// Reporter that writes the results of the test suite as a JUnit XML file
+///
+/// Most CI services can display the results of a test suite from a JUnit XML file. Each test is
+/// written as a `testcase` with the module of the test as its `classname`, and with the `file` and
+/// `line` on which it is defined. Failed hooks are counted as errors and written to `system-err`.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct JUnit {
+    /// The path of the file to which the results are written
+    path: PathBuf,
+}
+
+impl JUnit {
+    /// Create a reporter that writes the results to the given path, e.g. `target/doco/junit.xml`
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl Reporter for JUnit {
+    fn suite_finished(&self, summary: &Summary) {
+        if let Err(error) = write(&self.path, junit_xml(summary).as_bytes()) {
+            warn(None, &format!("failed to write JUnit report: {error:#}"));
+        }
+    }
+}
+
+/// Reporter that writes a stream of events as newline-delimited JSON
+///
+/// Each line is a JSON object with the `event` that has happened, e.g. `test_finished`, a
+/// `timestamp` in milliseconds since the Unix epoch, and the fields of the event. Every event is
+/// flushed immediately, so that other tools can follow the stream while the tests are running.
+///
+/// The reporter can also be enabled without changing the test suite by passing `--events <path>` to
+/// the test binary or by setting the `DOCO_EVENTS` environment variable, where the path `-` writes
+/// to stdout. When the events are written to stdout, the default [`Pretty`] reporter is disabled,
+/// and the test runner refuses to start if another reporter writes to stdout as well.
+///
+/// The steps of a test are written with their status and duration, but without any output. The
+/// logs of the server and its services are saved in the artifacts directory of the test, which is
+/// written as an `artifact` event. When a test fails, the logs of each container are also written
+/// as a `test_logs` event.
+#[derive(Debug)]
+pub struct Json {
+    /// The stream to which the events are written
+    events: Events,
+
+    /// Whether the events are written to stdout
+    stdout: bool,
+}
+
+impl Json {
+    /// Create a reporter that writes the events to stdout
+    pub fn stdout() -> Self {
+        Self {
+            events: Events::new(Box::new(std::io::stdout())),
+            stdout: true,
+        }
+    }
+
+    /// Create a reporter that writes the events to a file, creating its parent directories
+    pub fn file(path: impl AsRef<Path>) -> Result<Self> {
+        Ok(Self {
+            events: Events::new(Box::new(create(path.as_ref())?)),
+            stdout: false,
+        })
+    }
+
+    /// Create a reporter for the target of `--events`, which is a path or `-` for stdout
+    pub(crate) fn open(target: &str) -> Result<Self> {
+        match target {
+            STDOUT => Ok(Self::stdout()),
+            path => Self::file(path),
+        }
+    }
+
+    /// Check if the target of `--events` writes to stdout
+    pub(crate) fn is_stdout(target: &str) -> bool {
+        target == STDOUT
+    }
+}
+
+impl Reporter for Json {
+    fn suite_started(&self, tests: usize, filtered: usize) {
+        self.events.emit(Event::SuiteStarted { tests, filtered });
+    }
+
+    fn provisioning_started(&self, test: &str) {
+        self.events
+            .emit(Event::ProvisioningStarted { test: test.into() });
+    }
+
+    fn provisioning_finished(&self, test: &str, provisioning: &Provisioning) {
+        self.events
+            .emit(Event::provisioning_finished(test, provisioning));
+    }
+
+    fn test_started(&self, test: &TestCase) {
+        self.events.emit(Event::TestStarted {
+            test: test.qualified_name(),
+            location: test.location(),
+        });
+    }
+
+    fn step_finished(&self, test: &str, step: &str, status: Status, duration: Duration) {
+        self.events.emit(Event::step(test, step, status, duration));
+    }
+
+    fn test_logs(&self, test: &str, container: &str, log: &Log) {
+        self.events.emit(Event::test_logs(test, container, log));
+    }
+
+    fn artifact(&self, test: &str, kind: ArtifactKind, path: &Path) {
+        self.events.emit(Event::artifact(test, kind, path));
+    }
+
+    fn warning(&self, test: Option<&str>, message: &str) {
+        self.events.emit(Event::Warning {
+            test: test.map(String::from),
+            message: message.into(),
+        });
+    }
+
+    fn test_finished(&self, outcome: &TestOutcome) {
+        self.events.emit(Event::test_finished(outcome));
+    }
+
+    fn suite_finished(&self, summary: &Summary) {
+        self.events.emit(Event::suite_finished(summary));
+    }
+
+    fn writes_to_stdout(&self) -> bool {
+        self.stdout
+    }
+}
+
+/// Print the failures, the slowest tests and containers, and a summary of the test suite
+///
+/// Failures of hooks are listed separately from failures of tests, since a failing hook does not
+/// necessarily mean that the tested behavior is broken.
+fn print_summary(summary: &Summary) {
+    let failed = summary.count(Status::Failed);
+    let hook_failures = summary.hook_failures();
+
+    if failed > 0 {
+        println!("\nFailures:");
+
+        for outcome in summary.outcomes() {
+            if let Some(error) = outcome.error() {
+                println!(
+                    "\n---- {} ({}) ----\n{error:?}",
+                    outcome.name(),
+                    outcome.location()
+                );
+            }
+        }
+    }
+
+    if !hook_failures.is_empty() {
+        println!("\nHook failures:");
+
+        for failure in &hook_failures {
+            println!("\n---- {failure} ----\n{:?}", failure.error());
+        }
+    }
+
+    let slowest_tests = summary.slowest_tests(SLOWEST);
+    if !slowest_tests.is_empty() {
+        println!("\nSlowest tests:");
+
+        for outcome in slowest_tests {
+            println!("  {:>8}  {}", seconds(outcome.duration()), outcome.name());
+        }
+    }
+
+    let slowest_containers = summary.slowest_containers(SLOWEST);
+    if !slowest_containers.is_empty() {
+        println!("\nSlowest containers:");
+
+        for container in slowest_containers {
+            println!(
+                "  {:>8}  {} (pull {}, start {}, ready {})",
+                seconds(container.duration()),
+                container.name(),
+                seconds(container.pull()),
+                seconds(container.start()),
+                seconds(container.ready())
+            );
+        }
+    }
+
+    println!(
+        "\nResult: {} passed; {failed} failed; {} skipped; {} hooks failed; finished in {}",
+        summary.count(Status::Passed),
+        summary.count(Status::Skipped),
+        hook_failures.len(),
+        seconds(summary.duration())
+    );
+}
+
+/// Print a warning to stderr, prefixed with the test that it concerns
+fn print_warning(test: Option<&str>, message: &str) {
+    match test {
+        Some(test) => eprintln!("warning: {test}: {message}"),
+        None => eprintln!("warning: {message}"),
+    }
+}
+
+/// Format a duration in seconds with two decimal places, e.g. `1.25s`
+fn seconds(duration: Duration) -> String {
+    format!("{:.2}s", duration.as_secs_f64())
+}
+
+/// Render the summary of a test suite as JUnit XML
+fn junit_xml(summary: &Summary) -> String {
+    let attributes = format!(
+        "name=\"doco\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{:.3}\"",
+        summary.outcomes().len(),
+        summary.count(Status::Failed),
+        summary.hook_failures().len(),
+        summary.count(Status::Skipped),
+        summary.duration().as_secs_f64()
+    );
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(xml, "<testsuites {attributes}>");
+    let _ = writeln!(xml, "  <testsuite {attributes}>");
+
+    for outcome in summary.outcomes() {
+        let (classname, name) = outcome
+            .name()
+            .rsplit_once("::")
+            .unwrap_or(("doco", outcome.name()));
+
+        let mut children = Vec::new();
+
+        match (outcome.status(), outcome.error()) {
+            (Status::Failed, Some(error)) => children.push(format!(
+                "<failure message=\"{}\">{}</failure>",
+                escape(&error.to_string()),
+                escape(&format!("{error:?}"))
+            )),
+            (Status::Skipped, _) => children.push(String::from("<skipped/>")),
+            _ => {}
+        }
+
+        if !outcome.hook_failures().is_empty() {
+            children.push(system_err(outcome.hook_failures()));
+        }
+
+        let _ = write!(
+            xml,
+            "    <testcase name=\"{}\" classname=\"{}\" file=\"{}\" line=\"{}\" time=\"{:.3}\"",
+            escape(name),
+            escape(classname),
+            escape(outcome.file()),
+            outcome.line(),
+            outcome.duration().as_secs_f64()
+        );
+
+        if children.is_empty() {
+            xml.push_str("/>\n");
+        } else {
+            xml.push_str(">\n");
+            for child in children {
+                let _ = writeln!(xml, "      {child}");
+            }
+            xml.push_str("    </testcase>\n");
+        }
+    }
+
+    if !summary.suite_hook_failures().is_empty() {
+        let _ = writeln!(xml, "    {}", system_err(summary.suite_hook_failures()));
+    }
+
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+
+    xml
+}
+
+/// Render failed hooks as a `system-err` element
+fn system_err(hook_failures: &[HookFailure]) -> String {
+    let failures: Vec<String> = hook_failures
+        .iter()
+        .map(|failure| format!("{failure}: {:?}", failure.error()))
+        .collect();
+
+    format!("<system-err>{}</system-err>", escape(&failures.join("\n")))
+}
+
+/// Escape the characters that have a special meaning in XML
+///
+/// Characters that are not allowed in XML 1.0 are dropped, since not even an escaped reference to
+/// them is valid. Errors often contain ANSI escape codes for colors, which are removed completely
+/// instead of leaving their parameters behind.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\u{1b}' if chars.as_str().starts_with('[') => {
+                // A control sequence ends with a character between `@` and `~`
+                for char in chars.by_ref().skip(1) {
+                    if ('@'..='~').contains(&char) {
+                        break;
+                    }
+                }
+            }
+            '\t'
+            | '\n'
+            | '\r'
+            | '\u{20}'..='\u{d7ff}'
+            | '\u{e000}'..='\u{fffd}'
+            | '\u{10000}'.. => escaped.push(char),
+            _ => {}
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use crate::test_utils::*;
+
+    use super::*;
+
+    fn test_case(module_path: &'static str, name: &'static str) -> TestCase {
+        TestCase {
+            name,
+            module_path,
+            file: "tests/e2e.rs",
+            line: 12,
+            storage_state: true,
+            function: |_| Ok(()),
+        }
+    }
+
+    fn summary() -> Summary {
+        let outcomes = vec![
+            TestOutcome::new(
+                &test_case("e2e::auth", "login"),
+                Ok(true),
+                Vec::new(),
+                Duration::from_millis(1500),
+                None,
+            ),
+            TestOutcome::new(
+                &test_case("e2e", "checkout"),
+                Err(anyhow!("expected <b> & \"c\"")),
+                Vec::new(),
+                Duration::from_secs(2),
+                None,
+            ),
+        ];
+
+        Summary::new(outcomes, Vec::new(), Duration::from_secs(4))
+    }
+
+    #[test]
+    fn seconds_rounds_to_two_decimal_places() {
+        assert_eq!("1.25s", seconds(Duration::from_millis(1249)));
+    }
+
+    #[test]
+    fn escape_special_characters() {
+        assert_eq!(
+            "&lt;a href=&quot;/&quot;&gt;Tom &amp; Jerry&apos;s&lt;/a&gt;",
+            escape("<a href=\"/\">Tom & Jerry's</a>")
+        );
+    }
+
+    #[test]
+    fn escape_drops_characters_outside_xml() {
+        assert_eq!(
+            "expected 1, got 2 \u{1f600}",
+            escape("\u{1b}[31mexpected 1\u{1b}[0m, got 2\u{0}\u{8} \u{fffe}\u{1f600}")
+        );
+    }
+
+    #[test]
+    fn junit_xml_counts_tests() {
+        let xml = junit_xml(&summary());
+
+        assert!(xml.contains(
+            "<testsuite name=\"doco\" tests=\"2\" failures=\"1\" errors=\"0\" skipped=\"0\" time=\"4.000\">"
+        ));
+    }
+
+    #[test]
+    fn junit_xml_uses_module_as_classname() {
+        let xml = junit_xml(&summary());
+
+        assert!(xml.contains(
+            "<testcase name=\"login\" classname=\"auth\" file=\"tests/e2e.rs\" line=\"12\" time=\"1.500\"/>"
+        ));
+    }
+
+    #[test]
+    fn junit_xml_escapes_failures() {
+        let xml = junit_xml(&summary());
+
+        assert!(xml.contains("<failure message=\"expected &lt;b&gt; &amp; &quot;c&quot;\">"));
+    }
+
+    #[test]
+    fn json_file_creates_parent_directories() {
+        let directory = std::env::temp_dir().join(format!("doco-json-{}", std::process::id()));
+        let path = directory.join("events").join("events.ndjson");
+
+        let json = Json::file(&path).unwrap();
+        json.suite_started(1, 0);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert!(
+            contents.contains("\"event\":\"suite_started\""),
+            "{contents}"
+        );
+        assert!(contents.ends_with("}\n"));
+    }
+
+    #[test]
+    fn json_open_stdout() {
+        assert!(Json::is_stdout("-"));
+        assert!(!Json::is_stdout("events.ndjson"));
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<Pretty>();
+        assert_send::<Terse>();
+        assert_send::<JUnit>();
+        assert_send::<Json>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<Pretty>();
+        assert_sync::<Terse>();
+        assert_sync::<JUnit>();
+        assert_sync::<Json>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<Pretty>();
+        assert_unpin::<Terse>();
+        assert_unpin::<JUnit>();
+        assert_unpin::<Json>();
+    }
+}
@@ -0,0 +1,416 @@
+//! Visual regression tests that compare screenshots against baselines
+
+use std::io::Cursor;
+
+use anyhow::{bail, Context};
+use fantoccini::elements::Element;
+use fantoccini::Locator;
+use getset::{CopyGetters, Getters};
+use serde_json::Value;
+use typed_builder::TypedBuilder;
+
+use crate::{ArtifactKind, Client, LazyElement, Result};
+
+/// Script that covers elements with a solid box before a screenshot is taken
+const MASK: &str = r##"
+const [elements] = arguments;
+for (const element of elements) {
+    const rect = element.getBoundingClientRect();
+    const mask = document.createElement("div");
+    mask.setAttribute("data-doco-mask", "");
+    Object.assign(mask.style, {
+        position: "absolute",
+        left: `${rect.left + window.scrollX}px`,
+        top: `${rect.top + window.scrollY}px`,
+        width: `${rect.width}px`,
+        height: `${rect.height}px`,
+        background: "#ff00ff",
+        zIndex: "2147483647",
+        pointerEvents: "none",
+    });
+    document.body.appendChild(mask);
+}
+"##;
+
+/// Script that removes the boxes that [`MASK`] has added
+const UNMASK: &str =
+    r#"document.querySelectorAll("[data-doco-mask]").forEach((mask) => mask.remove());"#;
+
+/// Options for comparing a screenshot against its baseline
+///
+/// Screenshots rarely match their baseline exactly, since fonts and images are rendered slightly
+/// differently from run to run. The `threshold` ignores small differences in the color of a pixel,
+/// and `max_diff_pixels` allows a number of pixels to differ. Regions with dynamic content, e.g.
+/// the current time, can be covered with a solid box by masking the elements that contain them.
+///
+/// # Example
+///
+/// ```no_run
+/// use doco::{Client, Result, ScreenshotOptions};
+///
+/// async fn renders_dashboard(client: Client) -> Result<()> {
+///     client.goto("/dashboard").await?;
+///
+///     let options = ScreenshotOptions::builder()
+///         .max_diff_pixels(100)
+///         .mask(client.locate(&client.test_id("clock")))
+///         .build();
+///
+///     client.assert_screenshot("dashboard", &options).await
+/// }
+/// ```
+#[derive(Clone, Debug, CopyGetters, Getters, TypedBuilder)]
+pub struct ScreenshotOptions {
+    /// The difference in color at which a pixel counts as changed, between 0 and 1, e.g. `0.1`
+    #[builder(default = 0.1)]
+    #[getset(get_copy = "pub")]
+    threshold: f64,
+
+    /// The number of pixels that may differ from the baseline, e.g. `0`
+    #[builder(default)]
+    #[getset(get_copy = "pub")]
+    max_diff_pixels: usize,
+
+    /// The elements that are covered with a solid box before the screenshot is taken
+    #[builder(via_mutators(init = Vec::new()), mutators(
+        pub fn mask(mut self, element: LazyElement) {
+            self.masks.push(element);
+        }
+    ))]
+    #[getset(get = "pub")]
+    masks: Vec<LazyElement>,
+}
+
+impl Default for ScreenshotOptions {
+    fn default() -> Self {
+        Self::builder().build()
+    }
+}
+
+impl Client {
+    /// Compare a screenshot of the page against its baseline
+    ///
+    /// Baselines are stored per test, browser, and window size in the snapshot directory of the
+    /// test suite, e.g. `tests/snapshots/<test>/<name>.firefox-1366x768.png`. If the baseline does
+    /// not exist yet, it is created from the screenshot and the assertion fails. Run the tests with
+    /// `--update-snapshots` or with `DOCO_UPDATE_SNAPSHOTS=1` to create or rewrite all baselines
+    /// instead.
+    ///
+    /// When the screenshot does not match, the actual screenshot, the baseline, and an image that
+    /// highlights the differences in red are saved to the test's artifacts directory.
+    pub async fn assert_screenshot(&self, name: &str, options: &ScreenshotOptions) -> Result<()> {
+        let screenshot = self.masked_screenshot(None, options).await?;
+        self.compare_screenshot(name, &screenshot, options).await
+    }
+
+    /// Compare a screenshot of a single element against its baseline
+    ///
+    /// The element is waited for like with [`Client::wait_for_element`]. Otherwise, the screenshot
+    /// is compared just like with [`Client::assert_screenshot`].
+    pub async fn assert_element_screenshot<'l>(
+        &self,
+        locator: impl Into<Locator<'l>>,
+        name: &str,
+        options: &ScreenshotOptions,
+    ) -> Result<()> {
+        let element = self.wait_for_element(locator).await?;
+
+        let screenshot = self.masked_screenshot(Some(&element), options).await?;
+        self.compare_screenshot(name, &screenshot, options).await
+    }
+
+    /// Take a screenshot of the page or an element with the masks of the options applied
+    async fn masked_screenshot(
+        &self,
+        element: Option<&Element>,
+        options: &ScreenshotOptions,
+    ) -> Result<Vec<u8>> {
+        let mut masked = Vec::new();
+        for mask in options.masks() {
+            for element in mask.resolve_all().await? {
+                masked.push(serde_json::to_value(&element)?);
+            }
+        }
+
+        self.execute(MASK, vec![Value::Array(masked)]).await?;
+
+        let screenshot = match element {
+            Some(element) => element.screenshot().await,
+            None => self.screenshot().await,
+        };
+
+        self.execute(UNMASK, Vec::new()).await?;
+
+        Ok(screenshot?)
+    }
+
+    /// Compare a screenshot against its baseline and save the artifacts on a mismatch
+    async fn compare_screenshot(
+        &self,
+        name: &str,
+        screenshot: &[u8],
+        options: &ScreenshotOptions,
+    ) -> Result<()> {
+        let file = format!("{name}.{}", self.profile().await?);
+        let snapshots = self.snapshots();
+        let baseline = snapshots.baseline(&format!("{file}.png"));
+
+        let Some(expected) = snapshots.read_baseline(
+            &format!("screenshot `{name}`"),
+            &format!("{file}.png"),
+            screenshot,
+        )?
+        else {
+            return Ok(());
+        };
+
+        let actual_image = Image::decode(screenshot)?;
+        let expected_image = Image::decode(&expected)?;
+
+        if (actual_image.width, actual_image.height)
+            != (expected_image.width, expected_image.height)
+        {
+            let actual = snapshots.save_artifact(
+                ArtifactKind::Screenshot,
+                &format!("{file}.actual.png"),
+                screenshot,
+            )?;
+            bail!(
+                "screenshot `{name}` is {}x{} pixels, but its baseline is {}x{} pixels\n  \
+                 baseline: {}\n  actual:   {}",
+                actual_image.width,
+                actual_image.height,
+                expected_image.width,
+                expected_image.height,
+                baseline.display(),
+                actual.display()
+            );
+        }
+
+        let (pixels, diff) = expected_image.diff(&actual_image, options.threshold());
+
+        if pixels <= options.max_diff_pixels() {
+            return Ok(());
+        }
+
+        let actual = snapshots.save_artifact(
+            ArtifactKind::Screenshot,
+            &format!("{file}.actual.png"),
+            screenshot,
+        )?;
+        snapshots.save_artifact(
+            ArtifactKind::Screenshot,
+            &format!("{file}.expected.png"),
+            &expected,
+        )?;
+        let diff_path = snapshots.save_artifact(
+            ArtifactKind::Screenshot,
+            &format!("{file}.diff.png"),
+            &diff.encode()?,
+        )?;
+
+        bail!(
+            "screenshot `{name}` differs from its baseline in {pixels} pixels (allowed: {})\n  \
+             baseline: {}\n  actual:   {}\n  diff:     {}",
+            options.max_diff_pixels(),
+            baseline.display(),
+            actual.display(),
+            diff_path.display()
+        )
+    }
+
+    /// Return the name of the browser and the size of its window, e.g. `firefox-1366x768`
+    async fn profile(&self) -> Result<String> {
+        let browser = self
+            .capabilities()
+            .and_then(|capabilities| capabilities.get("browserName"))
+            .and_then(Value::as_str)
+            .unwrap_or("browser")
+            .to_lowercase();
+
+        let (width, height) = self.get_window_size().await?;
+
+        Ok(format!("{browser}-{width}x{height}"))
+    }
+}
+
+/// Image with 8-bit RGBA pixels
+#[derive(Clone, Eq, PartialEq, Debug)]
+struct Image {
+    /// The width of the image in pixels
+    width: u32,
+
+    /// The height of the image in pixels
+    height: u32,
+
+    /// The pixels of the image, row by row, with four bytes per pixel
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    /// Decode a PNG image
+    fn decode(png: &[u8]) -> Result<Self> {
+        let mut decoder = png::Decoder::new(Cursor::new(png));
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+        let mut reader = decoder.read_info().context("failed to decode PNG image")?;
+        let mut buffer = vec![0; reader.output_buffer_size().unwrap_or_default()];
+        let info = reader
+            .next_frame(&mut buffer)
+            .context("failed to decode PNG image")?;
+        buffer.truncate(info.buffer_size());
+
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => buffer,
+            png::ColorType::Rgb => buffer
+                .chunks_exact(3)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], u8::MAX])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => buffer
+                .chunks_exact(2)
+                .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+                .collect(),
+            png::ColorType::Grayscale => buffer.iter().flat_map(|&g| [g, g, g, u8::MAX]).collect(),
+            png::ColorType::Indexed => bail!("failed to decode PNG image with a palette"),
+        };
+
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    /// Encode the image as PNG
+    fn encode(&self) -> Result<Vec<u8>> {
+        let mut png = Vec::new();
+
+        let mut encoder = png::Encoder::new(&mut png, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish()?;
+
+        Ok(png)
+    }
+
+    /// Compare the image to another image of the same size
+    ///
+    /// Returns the number of pixels that differ by more than the threshold, and an image that shows
+    /// these pixels in red on top of a faded copy of this image.
+    fn diff(&self, other: &Image, threshold: f64) -> (usize, Image) {
+        let mut count = 0;
+        let mut pixels = Vec::with_capacity(self.pixels.len());
+
+        for (expected, actual) in self
+            .pixels
+            .chunks_exact(4)
+            .zip(other.pixels.chunks_exact(4))
+        {
+            let difference = expected
+                .iter()
+                .zip(actual)
+                .map(|(a, b)| a.abs_diff(*b))
+                .max()
+                .unwrap_or_default();
+
+            if f64::from(difference) / f64::from(u8::MAX) > threshold {
+                count += 1;
+                pixels.extend_from_slice(&[u8::MAX, 0, 0, u8::MAX]);
+            } else {
+                let luma =
+                    (u16::from(expected[0]) + u16::from(expected[1]) + u16::from(expected[2])) / 3;
+                let faded = (u8::MAX as u16 - (u8::MAX as u16 - luma) / 4) as u8;
+                pixels.extend_from_slice(&[faded, faded, faded, u8::MAX]);
+            }
+        }
+
+        let diff = Image {
+            width: self.width,
+            height: self.height,
+            pixels,
+        };
+
+        (count, diff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    use super::*;
+
+    fn image(pixels: &[[u8; 4]]) -> Image {
+        Image {
+            width: pixels.len() as u32,
+            height: 1,
+            pixels: pixels.concat(),
+        }
+    }
+
+    #[test]
+    fn diff_identical_images() {
+        let image = image(&[[10, 20, 30, 255], [40, 50, 60, 255]]);
+
+        let (pixels, _) = image.diff(&image.clone(), 0.0);
+
+        assert_eq!(0, pixels);
+    }
+
+    #[test]
+    fn diff_counts_changed_pixels() {
+        let expected = image(&[[0, 0, 0, 255], [0, 0, 0, 255]]);
+        let actual = image(&[[0, 0, 0, 255], [255, 255, 255, 255]]);
+
+        let (pixels, diff) = expected.diff(&actual, 0.1);
+
+        assert_eq!(1, pixels);
+        assert_eq!(&[255, 0, 0, 255], &diff.pixels[4..8]);
+    }
+
+    #[test]
+    fn diff_ignores_changes_below_threshold() {
+        let expected = image(&[[100, 100, 100, 255]]);
+        let actual = image(&[[110, 100, 100, 255]]);
+
+        let (pixels, _) = expected.diff(&actual, 0.1);
+
+        assert_eq!(0, pixels);
+    }
+
+    #[test]
+    fn encode_and_decode() -> Result<()> {
+        let image = image(&[[1, 2, 3, 255], [4, 5, 6, 128]]);
+
+        assert_eq!(image, Image::decode(&image.encode()?)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn options_default() {
+        let options = ScreenshotOptions::default();
+
+        assert_eq!(0.1, options.threshold());
+        assert_eq!(0, options.max_diff_pixels());
+        assert!(options.masks().is_empty());
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<ScreenshotOptions>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<ScreenshotOptions>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<ScreenshotOptions>();
+    }
+}
@@ -13,8 +13,9 @@ use crate::environment::Variable;
 /// as a Docker container and can be configured with environment variables.
 ///
 /// Services can be accessed from the server by using the `image` name. For example, adding a
-/// `postgres` service will allow the server to connect to `postgres:5432`. See the `axum-postgres`
-/// example in the repository for a working demo.
+/// `postgres` service will allow the server to connect to `postgres:5432`. A different name can be
+/// set with `hostname`, which also makes it possible to run several services from the same image.
+/// See the `axum-postgres` example in the repository for a working demo.
 ///
 /// # Example
 ///
@@ -41,6 +42,13 @@ pub struct Service {
     #[getset(get = "pub")]
     tag: String,
 
+    /// The hostname under which the server reaches the service, e.g. `payments`
+    ///
+    /// When no hostname is set, the service is reachable under the name of its image.
+    #[builder(default, setter(into, strip_option))]
+    #[getset(get = "pub")]
+    hostname: Option<String>,
+
     /// Environment variables to set in the service's container
     #[builder(via_mutators(init = Vec::new()), mutators(
         pub fn env(mut self, name: impl Into<String>, value: impl Into<String>) {
@@ -56,6 +64,25 @@ pub struct Service {
     wait: Option<WaitFor>,
 }
 
+impl Service {
+    /// Return the name under which the server reaches the service
+    pub fn host(&self) -> &str {
+        self.hostname.as_deref().unwrap_or(&self.image)
+    }
+
+    /// Return the value of the environment variable with the given name
+    ///
+    /// If the variable has been set more than once, the last value is returned, which is the value
+    /// that the container sees.
+    pub fn env(&self, name: &str) -> Option<&str> {
+        self.envs
+            .iter()
+            .rev()
+            .find(|env| env.name() == name)
+            .map(|env| env.value().as_str())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::test_utils::*;
@@ -75,6 +102,37 @@ mod tests {
         assert_eq!(3, service.envs.len());
     }
 
+    #[test]
+    fn env_returns_last_value() {
+        let service = Service::builder()
+            .image("postgres")
+            .tag("latest")
+            .env("POSTGRES_PASSWORD", "password")
+            .env("POSTGRES_PASSWORD", "postgres")
+            .build();
+
+        assert_eq!(Some("postgres"), service.env("POSTGRES_PASSWORD"));
+        assert_eq!(None, service.env("POSTGRES_USER"));
+    }
+
+    #[test]
+    fn host_defaults_to_image() {
+        let service = Service::builder().image("postgres").tag("latest").build();
+
+        assert_eq!("postgres", service.host());
+    }
+
+    #[test]
+    fn host_uses_hostname() {
+        let service = Service::builder()
+            .image("postgres")
+            .tag("latest")
+            .hostname("db")
+            .build();
+
+        assert_eq!("db", service.host());
+    }
+
     #[test]
     fn trait_send() {
         assert_send::<Service>();
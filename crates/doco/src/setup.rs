@@ -0,0 +1,117 @@
+//! Setup function that runs once before the test suite
+
+use std::fmt::{Debug, Formatter};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use crate::{Client, Result};
+
+/// Future that is returned by a setup function
+type SetupFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+
+/// Setup function that runs once before the test suite
+///
+/// A setup function is an asynchronous function that receives a [`Client`] for a fresh instance of
+/// the server, for example to log in through the web application. Afterwards, Doco captures the
+/// cookies and `localStorage` of the browser and restores them in every test. Tests that should
+/// start with an empty browser can opt out with `#[doco::test(storage_state = false)]`.
+///
+/// Every test runs against a new instance of the server, so the captured state only works if the
+/// server accepts it without remembering the session, e.g. a signed cookie or a JWT that every
+/// instance can verify with the same secret. Sessions that are kept in the server's memory or
+/// database are lost with the instance that created them. In that case, log in with a
+/// [`doco::before_each`](crate::before_each) hook instead.
+///
+/// Any asynchronous function that takes a [`Client`] and returns a [`Result`] can be passed to
+/// [`Doco::setup`](crate::Doco::setup).
+///
+/// # Example
+///
+/// ```no_run
+/// use doco::{Client, Doco, Result, Server};
+///
+/// async fn login(client: Client) -> Result<()> {
+///     client.goto("/login").await?;
+///
+///     client.fill("email", "user@example.com").await?;
+///     client.fill("password", "secret").await?;
+///     client.submit("password").await?;
+///
+///     client.wait_for_visible(&client.test_id("dashboard")).await?;
+///
+///     Ok(())
+/// }
+///
+/// #[doco::test]
+/// async fn shows_dashboard(client: Client) -> Result<()> {
+///     client.goto("/dashboard").await?;
+///
+///     Ok(())
+/// }
+///
+/// #[doco::test(storage_state = false)]
+/// async fn redirects_to_login(client: Client) -> Result<()> {
+///     client.goto("/dashboard").await?;
+///
+///     doco::expect(&client).to_have_url("/login").await
+/// }
+///
+/// #[doco::main]
+/// async fn main() -> Doco {
+///     let server = Server::builder()
+///         .image("crccheck/hello-world")
+///         .tag("v1.0.0")
+///         .port(8000)
+///         .build();
+///
+///     Doco::builder().server(server).setup(login).build()
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Setup(Arc<dyn Fn(Client) -> SetupFuture + Send + Sync>);
+
+impl Setup {
+    /// Run the setup function with the given client
+    pub(crate) async fn run(&self, client: Client) -> Result<()> {
+        (self.0)(client).await
+    }
+}
+
+impl<F, Fut> From<F> for Setup
+where
+    F: Fn(Client) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    fn from(function: F) -> Self {
+        Self(Arc::new(move |client| Box::pin(function(client))))
+    }
+}
+
+impl Debug for Setup {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Setup").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    use super::*;
+
+    #[test]
+    fn trait_send() {
+        assert_send::<Setup>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<Setup>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<Setup>();
+    }
+}
@@ -0,0 +1,279 @@
+//! Storage for snapshots that are compared against baselines
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{bail, Context};
+use getset::{CopyGetters, Getters};
+
+use crate::fs::write;
+use crate::reporter::notify;
+use crate::{ArtifactKind, Reporter, Result};
+
+/// Storage for the snapshots of a single test
+///
+/// Baselines are stored in a directory per test inside the snapshot directory of the test suite,
+/// which is configured with [`Doco::snapshots`](crate::Doco::snapshots) and should be committed to
+/// version control. When a snapshot does not match its baseline, the actual snapshot and the
+/// differences are saved to the artifacts directory of the test, and reported to the reporters.
+#[derive(Clone, Debug, Default, CopyGetters, Getters)]
+pub(crate) struct Snapshots {
+    /// The qualified name of the test to which the snapshots belong
+    test: String,
+
+    /// The directory in which the baselines of the test are stored
+    #[getset(get = "pub(crate)")]
+    baselines: PathBuf,
+
+    /// The directory in which snapshots that do not match are saved
+    #[getset(get = "pub(crate)")]
+    artifacts: PathBuf,
+
+    /// Whether baselines are rewritten instead of compared
+    #[getset(get_copy = "pub(crate)")]
+    update: bool,
+
+    /// The reporters that are notified about saved artifacts
+    reporters: Vec<Arc<dyn Reporter>>,
+}
+
+impl Snapshots {
+    /// Create the snapshot storage for a test
+    pub(crate) fn new(
+        test: impl Into<String>,
+        baselines: PathBuf,
+        artifacts: PathBuf,
+        update: bool,
+        reporters: Vec<Arc<dyn Reporter>>,
+    ) -> Self {
+        Self {
+            test: test.into(),
+            baselines,
+            artifacts,
+            update,
+            reporters,
+        }
+    }
+
+    /// Return the path of the baseline with the given file name
+    pub(crate) fn baseline(&self, file: &str) -> PathBuf {
+        self.baselines.join(file)
+    }
+
+    /// Return the path of the artifact with the given file name
+    pub(crate) fn artifact(&self, file: &str) -> PathBuf {
+        self.artifacts.join(file)
+    }
+
+    /// Read the baseline that a snapshot is compared against
+    ///
+    /// In update mode, the snapshot is written as the new baseline and `None` is returned, since
+    /// there is nothing to compare. A missing baseline is created from the snapshot, but an error
+    /// is returned so that new snapshots are reviewed before they are accepted. The description
+    /// names the snapshot in that error, e.g. ``screenshot `login` ``.
+    pub(crate) fn read_baseline(
+        &self,
+        description: &str,
+        file: &str,
+        snapshot: &[u8],
+    ) -> Result<Option<Vec<u8>>> {
+        let baseline = self.baseline(file);
+
+        if self.update {
+            write(&baseline, snapshot)?;
+            return Ok(None);
+        }
+
+        if !baseline.exists() {
+            write(&baseline, snapshot)?;
+            bail!(
+                "{description} has no baseline, saved it to {}",
+                baseline.display()
+            );
+        }
+
+        std::fs::read(&baseline)
+            .with_context(|| format!("failed to read baseline {}", baseline.display()))
+            .map(Some)
+    }
+
+    /// Save a file that helps to review a mismatch to the artifacts, and return its path
+    ///
+    /// The reporters are notified about the file, so that it can be attached to the test's results.
+    pub(crate) fn save_artifact(
+        &self,
+        kind: ArtifactKind,
+        file: &str,
+        content: &[u8],
+    ) -> Result<PathBuf> {
+        let path = self.artifact(file);
+        write(&path, content)?;
+
+        notify(&self.reporters, |reporter| {
+            reporter.artifact(&self.test, kind, &path)
+        });
+
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::Mutex;
+
+    use crate::test_utils::*;
+
+    use super::*;
+
+    #[test]
+    fn baseline_is_inside_baselines() {
+        let snapshots = Snapshots::new(
+            "login",
+            PathBuf::from("tests/snapshots/login"),
+            PathBuf::from("target/doco/login/snapshots"),
+            false,
+            Vec::new(),
+        );
+
+        assert_eq!(
+            PathBuf::from("tests/snapshots/login/form.png"),
+            snapshots.baseline("form.png")
+        );
+    }
+
+    #[test]
+    fn artifact_is_inside_artifacts() {
+        let snapshots = Snapshots::new(
+            "login",
+            PathBuf::from("tests/snapshots/login"),
+            PathBuf::from("target/doco/login/snapshots"),
+            false,
+            Vec::new(),
+        );
+
+        assert_eq!(
+            PathBuf::from("target/doco/login/snapshots/form.diff.png"),
+            snapshots.artifact("form.diff.png")
+        );
+    }
+
+    fn temporary(name: &str, update: bool) -> Snapshots {
+        let directory =
+            std::env::temp_dir().join(format!("doco-snapshots-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+
+        Snapshots::new(
+            "login",
+            directory.join("baselines"),
+            directory.join("artifacts"),
+            update,
+            Vec::new(),
+        )
+    }
+
+    /// Reporter that records the artifacts it is notified about
+    #[derive(Debug, Default)]
+    struct Artifacts(Mutex<Vec<(String, ArtifactKind, PathBuf)>>);
+
+    impl Reporter for Artifacts {
+        fn artifact(&self, test: &str, kind: ArtifactKind, path: &Path) {
+            self.0
+                .lock()
+                .unwrap()
+                .push((test.into(), kind, path.to_path_buf()));
+        }
+    }
+
+    #[test]
+    fn read_baseline_creates_missing_baseline() {
+        let snapshots = temporary("missing", false);
+
+        let error = snapshots
+            .read_baseline("snapshot `form`", "form.html", b"<form>")
+            .unwrap_err();
+
+        assert!(error
+            .to_string()
+            .starts_with("snapshot `form` has no baseline, saved it to"));
+        assert_eq!(
+            b"<form>".to_vec(),
+            std::fs::read(snapshots.baseline("form.html")).unwrap()
+        );
+    }
+
+    #[test]
+    fn read_baseline_returns_existing_baseline() {
+        let snapshots = temporary("existing", false);
+        write(&snapshots.baseline("form.html"), b"<form>").unwrap();
+
+        assert_eq!(
+            Some(b"<form>".to_vec()),
+            snapshots
+                .read_baseline("snapshot `form`", "form.html", b"<main>")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn read_baseline_rewrites_baseline_in_update_mode() {
+        let snapshots = temporary("update", true);
+        write(&snapshots.baseline("form.html"), b"<form>").unwrap();
+
+        assert_eq!(
+            None,
+            snapshots
+                .read_baseline("snapshot `form`", "form.html", b"<main>")
+                .unwrap()
+        );
+        assert_eq!(
+            b"<main>".to_vec(),
+            std::fs::read(snapshots.baseline("form.html")).unwrap()
+        );
+    }
+
+    #[test]
+    fn save_artifact_returns_path() {
+        let snapshots = temporary("artifact", false);
+
+        let path = snapshots
+            .save_artifact(ArtifactKind::HtmlSnapshot, "form.actual.html", b"<main>")
+            .unwrap();
+
+        assert_eq!(snapshots.artifact("form.actual.html"), path);
+        assert_eq!(b"<main>".to_vec(), std::fs::read(path).unwrap());
+    }
+
+    #[test]
+    fn save_artifact_notifies_reporters() {
+        let artifacts = Arc::new(Artifacts::default());
+        let snapshots = Snapshots {
+            reporters: vec![artifacts.clone()],
+            ..temporary("reported", false)
+        };
+
+        let path = snapshots
+            .save_artifact(ArtifactKind::Screenshot, "form.diff.png", b"png")
+            .unwrap();
+
+        assert_eq!(
+            vec![(String::from("login"), ArtifactKind::Screenshot, path)],
+            *artifacts.0.lock().unwrap()
+        );
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<Snapshots>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<Snapshots>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<Snapshots>();
+    }
+}
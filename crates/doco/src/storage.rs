@@ -0,0 +1,172 @@
+//! Helpers to read and write the cookies and storage of the browser
+
+use anyhow::Context;
+use fantoccini::cookies::Cookie;
+use getset::Getters;
+use serde_json::Value;
+
+use crate::{Client, Result};
+
+/// Script that reads all items from `localStorage` as a list of key-value pairs
+const READ_LOCAL_STORAGE: &str = "return Object.entries(window.localStorage);";
+
+/// Script that writes a list of key-value pairs into `localStorage`
+const WRITE_LOCAL_STORAGE: &str = r#"
+const [items] = arguments;
+for (const [key, value] of items) window.localStorage.setItem(key, value);
+"#;
+
+/// Cookies and local storage of a browser session
+///
+/// The storage state is captured by the setup function of the test suite, which is registered with
+/// [`Doco::setup`](crate::Doco::setup), and then restored in the browser of every test. This makes
+/// it possible to log in once instead of in every test, as long as the server accepts the session
+/// without having created it, e.g. a signed cookie. It can also be captured and restored manually
+/// with [`Client::storage_state`] and [`Client::restore_storage_state`].
+#[derive(Clone, Debug, Default, Getters)]
+pub struct StorageState {
+    /// The cookies of the browser session
+    #[getset(get = "pub")]
+    cookies: Vec<Cookie<'static>>,
+
+    /// The items in `localStorage` for the origin of the server
+    #[getset(get = "pub")]
+    local_storage: Vec<(String, String)>,
+}
+
+impl Client {
+    /// Return the value of the cookie with the given name, if it exists
+    pub async fn cookie(&self, name: &str) -> Result<Option<String>> {
+        let cookies = self.get_all_cookies().await?;
+
+        Ok(cookies
+            .into_iter()
+            .find(|cookie| cookie.name() == name)
+            .map(|cookie| cookie.value().to_string()))
+    }
+
+    /// Set a cookie for the current page
+    ///
+    /// Browsers only accept cookies for the site that they are currently on, so the client must
+    /// have navigated to a page on the server first. The cookie is valid for all paths.
+    pub async fn set_cookie(&self, name: &str, value: &str) -> Result<()> {
+        let mut cookie = Cookie::new(name.to_string(), value.to_string());
+        cookie.set_path("/");
+
+        self.add_cookie(cookie)
+            .await
+            .with_context(|| format!("failed to set cookie `{name}`"))
+    }
+
+    /// Return the item with the given key from `localStorage`, if it exists
+    pub async fn local_storage_item(&self, key: &str) -> Result<Option<String>> {
+        self.storage_item("localStorage", key).await
+    }
+
+    /// Set an item in `localStorage` for the current page
+    pub async fn set_local_storage_item(&self, key: &str, value: &str) -> Result<()> {
+        self.set_storage_item("localStorage", key, value).await
+    }
+
+    /// Return the item with the given key from `sessionStorage`, if it exists
+    pub async fn session_storage_item(&self, key: &str) -> Result<Option<String>> {
+        self.storage_item("sessionStorage", key).await
+    }
+
+    /// Set an item in `sessionStorage` for the current page
+    pub async fn set_session_storage_item(&self, key: &str, value: &str) -> Result<()> {
+        self.set_storage_item("sessionStorage", key, value).await
+    }
+
+    /// Capture the cookies and `localStorage` of the current page
+    pub async fn storage_state(&self) -> Result<StorageState> {
+        let cookies = self.get_all_cookies().await?;
+
+        let items = self.execute(READ_LOCAL_STORAGE, Vec::new()).await?;
+        let local_storage =
+            serde_json::from_value(items).context("failed to read the items in localStorage")?;
+
+        Ok(StorageState {
+            cookies,
+            local_storage,
+        })
+    }
+
+    /// Restore the cookies and `localStorage` from a captured storage state
+    ///
+    /// The client navigates to the root path of the server first, because browsers only accept
+    /// cookies and storage for the site that they are currently on. The domain of the cookies is
+    /// dropped so that they apply to the server of the current test.
+    pub async fn restore_storage_state(&self, state: &StorageState) -> Result<()> {
+        self.goto("/").await?;
+
+        for cookie in state.cookies() {
+            let mut cookie = cookie.clone();
+            cookie.unset_domain();
+
+            self.add_cookie(cookie).await?;
+        }
+
+        let items = serde_json::to_value(state.local_storage())?;
+        self.execute(WRITE_LOCAL_STORAGE, vec![items]).await?;
+
+        Ok(())
+    }
+
+    /// Read an item from the given storage area
+    async fn storage_item(&self, storage: &str, key: &str) -> Result<Option<String>> {
+        let script = format!("return window.{storage}.getItem(arguments[0]);");
+        let value = self
+            .execute(&script, vec![Value::String(key.to_string())])
+            .await?;
+
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Write an item into the given storage area
+    async fn set_storage_item(&self, storage: &str, key: &str, value: &str) -> Result<()> {
+        let script = format!("window.{storage}.setItem(arguments[0], arguments[1]);");
+
+        self.execute(
+            &script,
+            vec![
+                Value::String(key.to_string()),
+                Value::String(value.to_string()),
+            ],
+        )
+        .await
+        .with_context(|| format!("failed to set `{key}` in {storage}"))?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    use super::*;
+
+    #[test]
+    fn default_is_empty() {
+        let state = StorageState::default();
+
+        assert!(state.cookies().is_empty());
+        assert!(state.local_storage().is_empty());
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<StorageState>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<StorageState>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<StorageState>();
+    }
+}
@@ -0,0 +1,674 @@
+//! Tests and hooks that make up the test suite
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use getset::{CopyGetters, Getters};
+
+use crate::{ContainerTiming, Error, Provisioning, Result, TestContext};
+
+/// Test that has been annotated with [`doco::test`](crate::test)
+///
+/// Tests are collected with [`inventory`] and run by the [`TestRunner`](crate::TestRunner). It
+/// should not be necessary to create a test case manually.
+///
+/// Tests are identified by their [qualified name](TestCase::qualified_name), which includes the
+/// module in which they have been defined. This way, tests with the same name in different modules
+/// do not clash.
+#[derive(Copy, Clone, Debug)]
+pub struct TestCase {
+    /// The name of the test, e.g. `login` or `login[admin]` for a case of a parameterized test
+    pub name: &'static str,
+
+    /// The path of the module in which the test has been defined, as returned by `module_path!()`
+    pub module_path: &'static str,
+
+    /// The file in which the test has been defined, as returned by `file!()`
+    pub file: &'static str,
+
+    /// The line on which the test has been defined, as returned by `line!()`
+    pub line: u32,
+
+    /// Whether the storage state of the setup function is restored before the test
+    pub storage_state: bool,
+
+    /// The function that sets up the fixtures and runs the test
+    pub function: fn(TestContext) -> Result<()>,
+}
+
+inventory::collect!(TestCase);
+
+impl TestCase {
+    /// Return the name of the test including its module, e.g. `auth::login[admin]`
+    ///
+    /// The name of the crate is omitted, since all tests of a test suite are in the same crate.
+    /// The qualified name is used to filter and report tests.
+    pub fn qualified_name(&self) -> String {
+        match self.module_path.split_once("::") {
+            Some((_, module)) => format!("{module}::{}", self.name),
+            None => self.name.to_string(),
+        }
+    }
+
+    /// Return the location of the test in the source code, e.g. `tests/auth.rs:12`
+    pub fn location(&self) -> String {
+        format!("{}:{}", self.file, self.line)
+    }
+
+    /// Return the path of the directories for the artifacts and snapshots of the test
+    ///
+    /// Each module of the qualified name becomes a directory, e.g. `auth/login[admin]`.
+    pub(crate) fn path(&self) -> PathBuf {
+        self.qualified_name().split("::").collect()
+    }
+}
+
+/// Find the tests that share a qualified name, and return their locations keyed by the name
+///
+/// Tests with the same qualified name cannot be told apart when filtering or reporting, and they
+/// would overwrite each other's artifacts.
+pub(crate) fn find_duplicates(tests: &[&TestCase]) -> Vec<(String, Vec<String>)> {
+    let mut locations: HashMap<String, Vec<String>> = HashMap::new();
+    for test in tests {
+        locations
+            .entry(test.qualified_name())
+            .or_default()
+            .push(test.location());
+    }
+
+    let mut duplicates: Vec<(String, Vec<String>)> = locations
+        .into_iter()
+        .filter(|(_, locations)| locations.len() > 1)
+        .collect();
+    duplicates.sort();
+
+    duplicates
+}
+
+/// Hook that runs before or after the tests of the suite
+///
+/// Hooks are annotated with [`doco::before_all`](crate::before_all),
+/// [`doco::after_all`](crate::after_all), [`doco::before_each`](crate::before_each), or
+/// [`doco::after_each`](crate::after_each), and collected with [`inventory`] like tests. It should
+/// not be necessary to create a hook manually.
+///
+/// Hooks of the same kind run in the order in which they are defined, sorted by their file and
+/// line.
+#[derive(Copy, Clone, Debug)]
+pub struct Hook {
+    /// The name of the hook's function
+    pub name: &'static str,
+
+    /// The file in which the hook is defined
+    pub file: &'static str,
+
+    /// The line on which the hook is defined
+    pub line: u32,
+
+    /// The function that runs the hook
+    pub function: HookFunction,
+}
+
+inventory::collect!(Hook);
+
+impl Hook {
+    /// Return the point in the test suite at which the hook runs
+    pub fn kind(&self) -> HookKind {
+        match self.function {
+            HookFunction::BeforeAll(_) => HookKind::BeforeAll,
+            HookFunction::AfterAll(_) => HookKind::AfterAll,
+            HookFunction::BeforeEach(_) => HookKind::BeforeEach,
+            HookFunction::AfterEach(_) => HookKind::AfterEach,
+        }
+    }
+}
+
+/// Function of a hook
+///
+/// Hooks that run once for the suite do not have a test environment and take no arguments. Hooks
+/// that run for each test set up their fixtures from the test's context, just like tests.
+#[derive(Copy, Clone, Debug)]
+pub enum HookFunction {
+    /// Runs once before the first test
+    BeforeAll(fn() -> Result<()>),
+
+    /// Runs once after the last test
+    AfterAll(fn() -> Result<()>),
+
+    /// Runs before each test in the test's environment
+    BeforeEach(fn(TestContext) -> Result<()>),
+
+    /// Runs after each test in the test's environment
+    AfterEach(fn(TestContext) -> Result<()>),
+}
+
+/// Point in the test suite at which a hook runs
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum HookKind {
+    /// Once before the first test
+    BeforeAll,
+
+    /// Once after the last test
+    AfterAll,
+
+    /// Before each test
+    BeforeEach,
+
+    /// After each test
+    AfterEach,
+
+    /// Once before the first test when the setup function of the suite runs, and before each test
+    /// when its storage state is restored
+    ///
+    /// The setup function is not a [`Hook`], but its failures are reported like those of hooks.
+    Setup,
+}
+
+impl Display for HookKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            HookKind::BeforeAll => "before_all",
+            HookKind::AfterAll => "after_all",
+            HookKind::BeforeEach => "before_each",
+            HookKind::AfterEach => "after_each",
+            HookKind::Setup => "setup",
+        };
+
+        write!(f, "{kind}")
+    }
+}
+
+/// Hook that has returned an error
+#[derive(Debug, CopyGetters, Getters)]
+pub struct HookFailure {
+    /// The name of the hook's function
+    #[getset(get_copy = "pub")]
+    hook: &'static str,
+
+    /// The point in the test suite at which the hook ran
+    #[getset(get_copy = "pub")]
+    kind: HookKind,
+
+    /// The test for which the hook ran, or `None` for hooks of the suite
+    #[getset(get = "pub")]
+    test: Option<String>,
+
+    /// The error that the hook has returned
+    #[getset(get = "pub")]
+    error: Error,
+}
+
+impl HookFailure {
+    /// Create a new failure for the given hook
+    pub(crate) fn new(hook: &Hook, test: Option<&str>, error: Error) -> Self {
+        Self {
+            hook: hook.name,
+            kind: hook.kind(),
+            test: test.map(String::from),
+            error,
+        }
+    }
+
+    /// Create a new failure for the setup function of the suite, or for restoring its storage
+    /// state in the given test
+    pub(crate) fn setup(test: Option<&str>, error: Error) -> Self {
+        Self {
+            hook: "setup",
+            kind: HookKind::Setup,
+            test: test.map(String::from),
+            error,
+        }
+    }
+}
+
+impl Display for HookFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            HookKind::Setup => write!(f, "setup function")?,
+            _ => write!(f, "{} hook `{}`", self.kind, self.hook)?,
+        }
+
+        if let Some(test) = &self.test {
+            write!(f, " for {test}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Status of a test after it has been run
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub enum Status {
+    /// The test has passed
+    Passed,
+
+    /// The test or its environment has returned an error
+    Failed,
+
+    /// The test has not been run, because a hook before it has failed
+    Skipped,
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let status = match self {
+            Status::Passed => "ok",
+            Status::Failed => "FAILED",
+            Status::Skipped => "skipped",
+        };
+
+        write!(f, "{status}")
+    }
+}
+
+/// Outcome of a single test
+#[derive(Debug, CopyGetters, Getters)]
+pub struct TestOutcome {
+    /// The qualified name of the test
+    #[getset(get = "pub")]
+    name: String,
+
+    /// The file in which the test is defined
+    #[getset(get_copy = "pub")]
+    file: &'static str,
+
+    /// The line on which the test is defined
+    #[getset(get_copy = "pub")]
+    line: u32,
+
+    /// The status of the test
+    #[getset(get_copy = "pub")]
+    status: Status,
+
+    /// The error of a failed test
+    #[getset(get = "pub")]
+    error: Option<Error>,
+
+    /// The hooks that have failed before or after the test
+    #[getset(get = "pub")]
+    hook_failures: Vec<HookFailure>,
+
+    /// The time it took to run the test including its environment
+    #[getset(get_copy = "pub")]
+    duration: Duration,
+
+    /// The time it took to provision the environment, or `None` if it has not been provisioned
+    #[getset(get = "pub")]
+    provisioning: Option<Provisioning>,
+}
+
+impl TestOutcome {
+    /// Create the outcome of a test from its result, where `Ok(false)` means it was skipped
+    pub(crate) fn new(
+        test: &TestCase,
+        result: Result<bool>,
+        hook_failures: Vec<HookFailure>,
+        duration: Duration,
+        provisioning: Option<Provisioning>,
+    ) -> Self {
+        let (status, error) = match result {
+            Ok(true) => (Status::Passed, None),
+            Ok(false) => (Status::Skipped, None),
+            Err(error) => (Status::Failed, Some(error)),
+        };
+
+        Self {
+            name: test.qualified_name(),
+            file: test.file,
+            line: test.line,
+            status,
+            error,
+            hook_failures,
+            duration,
+            provisioning,
+        }
+    }
+
+    /// Return the location of the test in the source code, e.g. `tests/auth.rs:12`
+    pub fn location(&self) -> String {
+        format!("{}:{}", self.file, self.line)
+    }
+}
+
+/// Summary of the test suite after all tests and hooks have been run
+#[derive(Debug, CopyGetters, Getters)]
+pub struct Summary {
+    /// The outcomes of the tests
+    #[getset(get = "pub")]
+    outcomes: Vec<TestOutcome>,
+
+    /// The hooks of the suite that have failed, i.e. `before_all` and `after_all` hooks
+    #[getset(get = "pub")]
+    suite_hook_failures: Vec<HookFailure>,
+
+    /// The time it took to run the test suite
+    #[getset(get_copy = "pub")]
+    duration: Duration,
+}
+
+impl Summary {
+    /// Create the summary of a test suite
+    pub(crate) fn new(
+        outcomes: Vec<TestOutcome>,
+        suite_hook_failures: Vec<HookFailure>,
+        duration: Duration,
+    ) -> Self {
+        Self {
+            outcomes,
+            suite_hook_failures,
+            duration,
+        }
+    }
+
+    /// Return the number of tests with the given status
+    pub fn count(&self, status: Status) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.status == status)
+            .count()
+    }
+
+    /// Return all hooks that have failed, both of the suite and of each test
+    pub fn hook_failures(&self) -> Vec<&HookFailure> {
+        self.suite_hook_failures
+            .iter()
+            .chain(
+                self.outcomes
+                    .iter()
+                    .flat_map(|outcome| &outcome.hook_failures),
+            )
+            .collect()
+    }
+
+    /// Return up to `limit` tests that took the longest, starting with the slowest
+    pub fn slowest_tests(&self, limit: usize) -> Vec<&TestOutcome> {
+        let mut outcomes: Vec<&TestOutcome> = self.outcomes.iter().collect();
+        outcomes.sort_by(|a, b| b.duration.cmp(&a.duration));
+        outcomes.truncate(limit);
+
+        outcomes
+    }
+
+    /// Return up to `limit` containers that took the longest to start, starting with the slowest
+    ///
+    /// Each container is started once per test, so only its slowest start is returned.
+    pub fn slowest_containers(&self, limit: usize) -> Vec<&ContainerTiming> {
+        let mut slowest: HashMap<&str, &ContainerTiming> = HashMap::new();
+
+        for timing in self
+            .outcomes
+            .iter()
+            .filter_map(|outcome| outcome.provisioning.as_ref())
+            .flat_map(|provisioning| provisioning.containers())
+        {
+            slowest
+                .entry(timing.name())
+                .and_modify(|slowest| {
+                    if timing.duration() > slowest.duration() {
+                        *slowest = timing;
+                    }
+                })
+                .or_insert(timing);
+        }
+
+        let mut containers: Vec<&ContainerTiming> = slowest.into_values().collect();
+        containers.sort_by(|a, b| {
+            b.duration()
+                .cmp(&a.duration())
+                .then_with(|| a.name().cmp(b.name()))
+        });
+        containers.truncate(limit);
+
+        containers
+    }
+
+    /// Check if the test suite has passed, i.e. no test and no hook has failed
+    pub fn success(&self) -> bool {
+        self.count(Status::Failed) == 0 && self.hook_failures().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use crate::test_utils::*;
+
+    use super::*;
+
+    fn test_case(module_path: &'static str, name: &'static str) -> TestCase {
+        TestCase {
+            name,
+            module_path,
+            file: "tests/e2e.rs",
+            line: 12,
+            storage_state: true,
+            function: |_| Ok(()),
+        }
+    }
+
+    fn outcome(result: Result<bool>) -> TestOutcome {
+        TestOutcome::new(
+            &test_case("e2e", "a"),
+            result,
+            Vec::new(),
+            Duration::ZERO,
+            None,
+        )
+    }
+
+    fn before_each() -> Hook {
+        Hook {
+            name: "reset_database",
+            file: "tests/e2e.rs",
+            line: 20,
+            function: HookFunction::BeforeEach(|_| Ok(())),
+        }
+    }
+
+    #[test]
+    fn qualified_name_omits_crate() {
+        assert_eq!(
+            "auth::login[admin]",
+            test_case("e2e::auth", "login[admin]").qualified_name()
+        );
+    }
+
+    #[test]
+    fn qualified_name_at_crate_root() {
+        assert_eq!("login", test_case("e2e", "login").qualified_name());
+    }
+
+    #[test]
+    fn location() {
+        assert_eq!("tests/e2e.rs:12", test_case("e2e", "login").location());
+    }
+
+    #[test]
+    fn path_has_directory_per_module() {
+        assert_eq!(
+            PathBuf::from("auth").join("login"),
+            test_case("e2e::auth", "login").path()
+        );
+    }
+
+    #[test]
+    fn find_duplicates_by_qualified_name() {
+        let login = test_case("e2e::auth", "login");
+        let other = test_case("e2e::admin", "login");
+
+        assert_eq!(
+            vec![(
+                String::from("auth::login"),
+                vec![String::from("tests/e2e.rs:12"); 2]
+            )],
+            find_duplicates(&[&login, &other, &login])
+        );
+    }
+
+    #[test]
+    fn hook_kind() {
+        assert_eq!(HookKind::BeforeEach, before_each().kind());
+    }
+
+    #[test]
+    fn hook_failure_display() {
+        let failure = HookFailure::new(&before_each(), Some("login"), anyhow!("boom"));
+
+        assert_eq!(
+            "before_each hook `reset_database` for login",
+            failure.to_string()
+        );
+    }
+
+    #[test]
+    fn hook_failure_display_setup() {
+        let failure = HookFailure::setup(Some("login"), anyhow!("boom"));
+
+        assert_eq!(HookKind::Setup, failure.kind());
+        assert_eq!("setup function for login", failure.to_string());
+    }
+
+    #[test]
+    fn outcome_status() {
+        assert_eq!(Status::Passed, outcome(Ok(true)).status());
+        assert_eq!(Status::Skipped, outcome(Ok(false)).status());
+        assert_eq!(Status::Failed, outcome(Err(anyhow!("boom"))).status());
+    }
+
+    #[test]
+    fn summary_passes_without_failures() {
+        let summary = Summary::new(vec![outcome(Ok(true))], Vec::new(), Duration::ZERO);
+
+        assert!(summary.success());
+    }
+
+    #[test]
+    fn summary_counts_statuses() {
+        let outcomes = vec![outcome(Ok(true)), outcome(Ok(true)), outcome(Ok(false))];
+        let summary = Summary::new(outcomes, Vec::new(), Duration::ZERO);
+
+        assert_eq!(2, summary.count(Status::Passed));
+        assert_eq!(1, summary.count(Status::Skipped));
+    }
+
+    #[test]
+    fn summary_fails_with_hook_failures() {
+        let failure = HookFailure::new(&before_each(), Some("a"), anyhow!("boom"));
+        let outcomes = vec![TestOutcome::new(
+            &test_case("e2e", "a"),
+            Ok(true),
+            vec![failure],
+            Duration::ZERO,
+            None,
+        )];
+        let summary = Summary::new(outcomes, Vec::new(), Duration::ZERO);
+
+        assert!(!summary.success());
+        assert_eq!(1, summary.hook_failures().len());
+    }
+
+    #[test]
+    fn summary_lists_slowest_tests() {
+        let outcome = |name, secs| {
+            TestOutcome::new(
+                &test_case("e2e", name),
+                Ok(true),
+                Vec::new(),
+                Duration::from_secs(secs),
+                None,
+            )
+        };
+        let outcomes = vec![outcome("a", 1), outcome("b", 3), outcome("c", 2)];
+        let summary = Summary::new(outcomes, Vec::new(), Duration::ZERO);
+
+        let slowest: Vec<&str> = summary
+            .slowest_tests(2)
+            .iter()
+            .map(|outcome| outcome.name().as_str())
+            .collect();
+
+        assert_eq!(vec!["b", "c"], slowest);
+    }
+
+    #[test]
+    fn summary_lists_slowest_start_of_each_container() {
+        let timing = |name, secs| {
+            ContainerTiming::new(
+                name,
+                Duration::ZERO,
+                Duration::from_secs(secs),
+                Duration::ZERO,
+            )
+        };
+        let outcome = |containers| {
+            TestOutcome::new(
+                &test_case("e2e", "a"),
+                Ok(true),
+                Vec::new(),
+                Duration::ZERO,
+                Some(Provisioning::new(
+                    Duration::ZERO,
+                    containers,
+                    Duration::ZERO,
+                )),
+            )
+        };
+        let outcomes = vec![
+            outcome(vec![timing("postgres", 2), timing("server", 1)]),
+            outcome(vec![timing("postgres", 4), timing("server", 3)]),
+        ];
+        let summary = Summary::new(outcomes, Vec::new(), Duration::ZERO);
+
+        let slowest: Vec<(&str, Duration)> = summary
+            .slowest_containers(5)
+            .iter()
+            .map(|timing| (timing.name().as_str(), timing.duration()))
+            .collect();
+
+        assert_eq!(
+            vec![
+                ("postgres", Duration::from_secs(4)),
+                ("server", Duration::from_secs(3))
+            ],
+            slowest
+        );
+    }
+
+    #[test]
+    fn summary_fails_with_failed_tests() {
+        let summary = Summary::new(
+            vec![outcome(Err(anyhow!("boom")))],
+            Vec::new(),
+            Duration::ZERO,
+        );
+
+        assert!(!summary.success());
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<TestCase>();
+        assert_send::<Hook>();
+        assert_send::<TestOutcome>();
+        assert_send::<Summary>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<TestCase>();
+        assert_sync::<Hook>();
+        assert_sync::<TestOutcome>();
+        assert_sync::<Summary>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<TestCase>();
+        assert_unpin::<Hook>();
+        assert_unpin::<TestOutcome>();
+        assert_unpin::<Summary>();
+    }
+}
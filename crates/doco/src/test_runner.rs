@@ -1,18 +1,42 @@
 //! Test runner for Doco's end-to-end tests
 
-use anyhow::Context;
+use std::net::IpAddr;
+use std::path::Path;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context};
+use chrono::DateTime;
+use fantoccini::wd::Capabilities;
+use reqwest::Url;
+use testcontainers::bollard::errors::Error as DockerError;
+use testcontainers::bollard::models::ContainerInspectResponse;
+use testcontainers::bollard::Docker;
 use testcontainers::core::{Host, IntoContainerPort, WaitFor};
 use testcontainers::runners::AsyncRunner;
-use testcontainers::{ContainerAsync, GenericImage, ImageExt};
-
-use crate::{Client, Doco, Result};
+use testcontainers::{ContainerAsync, ContainerRequest, GenericImage, ImageExt};
+
+use crate::cli::Cli;
+use crate::console::Console;
+use crate::proxy::Proxy;
+use crate::reporter;
+use crate::reporters::{Json, Pretty};
+use crate::snapshot::Snapshots;
+use crate::suite::{find_duplicates, HookFailure, HookKind, TestOutcome};
+use crate::video::Recorder;
+use crate::{
+    ArtifactKind, Client, ContainerTiming, Doco, Error, Fixture, Hook, HookFunction, Logs, Network,
+    Provisioning, Reporter, Result, Setup, Status, StorageState, Summary, TestCase, TestContext,
+    Video,
+};
 
 /// The host name for Docker containers to access the host machine
 ///
 /// Docker containers can access the host using this internal hostname. The hostname is
 /// automatically set on macOS and Windows hosts, but on Linux hosts it must be set explicitly by
 /// Doco.
-const DOCKER_HOST: &str = "host.docker.internal";
+pub(crate) const DOCKER_HOST: &str = "host.docker.internal";
 
 /// Test runner for Doco's end-to-end tests
 ///
@@ -30,6 +54,42 @@ pub struct TestRunner {
 
     /// The running Selenium container to which the WebDriver client connects
     selenium: ContainerAsync<GenericImage>,
+
+    /// The client for the Docker daemon that is used to measure how long containers take to start
+    docker: Docker,
+
+    /// The gateway through which the Selenium container reaches the host, if Docker reports one
+    gateway: Option<IpAddr>,
+
+    /// The arguments that have been passed to the test binary
+    cli: Cli,
+
+    /// The reporters that are notified about the progress of the tests
+    reporters: Vec<Arc<dyn Reporter>>,
+}
+
+/// Ephemeral environment in which a test is run
+struct Environment {
+    /// The container that runs the server
+    server: Arc<ContainerAsync<GenericImage>>,
+
+    /// The URL under which the test process reaches the server
+    server_url: Url,
+
+    /// The containers that run the services, keyed by their hostname
+    services: Vec<(String, Arc<ContainerAsync<GenericImage>>)>,
+
+    /// The WebDriver client that is connected to a new browser session
+    client: Client,
+
+    /// The recording of the browser's network traffic, if enabled
+    network: Option<Network>,
+
+    /// The proxy through which the browser sends its traffic, which must live as long as the test
+    proxy: Option<Proxy>,
+
+    /// The time it took to start the containers and the browser session
+    provisioning: Provisioning,
 }
 
 impl TestRunner {
@@ -38,24 +98,346 @@ impl TestRunner {
     /// This method starts the Selenium container and returns a new `TestRunner` instance. Since
     /// starting the container can fail, this method returns a `Result` that must be handled.
     pub async fn init(doco: Doco) -> Result<Self> {
-        println!("Initializing ephemeral test environment...");
+        let cli = Cli::parse();
+        let reporters = reporters(&doco, &cli)?;
 
+        let docker =
+            Docker::connect_with_defaults().context("failed to connect to the Docker daemon")?;
         let selenium = start_selenium().await?;
+        let gateway = gateway(&docker, selenium.id()).await;
+
+        Ok(Self {
+            doco,
+            selenium,
+            docker,
+            gateway,
+            cli,
+            reporters,
+        })
+    }
+
+    /// Run all tests and hooks of the test suite and print a summary
+    ///
+    /// The tests and hooks are collected from the functions that have been annotated with
+    /// [`doco::test`] and the hook macros. Tests that do not match the filters on the command
+    /// line are not run. If two tests share the same qualified name, no test is run at all. The
+    /// `before_all` hooks run first, followed by the setup function of the suite, and if any of
+    /// them fails, the tests are skipped. The `after_all` hooks run last, even if tests have
+    /// failed. Hooks of the same kind run in the order in which they are defined, sorted by their
+    /// file and line.
+    ///
+    /// Failures of hooks are reported separately from failures of tests. The returned exit code
+    /// signals a failure if any test or hook has failed.
+    pub async fn run_all(&self) -> ExitCode {
+        let started = Instant::now();
+
+        let tests: Vec<&TestCase> = inventory::iter::<TestCase>.into_iter().collect();
+        let mut hooks: Vec<&Hook> = inventory::iter::<Hook>.into_iter().collect();
+        hooks.sort_by_key(|hook| (hook.file, hook.line));
+
+        let duplicates = find_duplicates(&tests);
+        if !duplicates.is_empty() {
+            for (name, locations) in duplicates {
+                let message = format!(
+                    "the test `{name}` is defined more than once: {}",
+                    locations.join(", ")
+                );
+                self.report(|reporter| reporter.warning(None, &message));
+            }
+
+            return ExitCode::FAILURE;
+        }
+
+        let (tests, filtered): (Vec<&TestCase>, Vec<&TestCase>) = tests
+            .into_iter()
+            .partition(|test| self.cli.matches(&test.qualified_name()));
+
+        self.report(|reporter| reporter.suite_started(tests.len(), filtered.len()));
+
+        let mut suite_hook_failures = Vec::new();
+        for hook in hooks
+            .iter()
+            .filter(|hook| hook.kind() == HookKind::BeforeAll)
+        {
+            if let HookFunction::BeforeAll(function) = hook.function {
+                if let Err(error) = call(function).await {
+                    suite_hook_failures.push(HookFailure::new(hook, None, error));
+                    break;
+                }
+            }
+        }
+
+        let mut storage_state = None;
+        if let (Some(setup), true) = (self.doco.setup(), suite_hook_failures.is_empty()) {
+            match self.capture_storage_state(setup).await {
+                Ok(state) => storage_state = Some(state),
+                Err(error) => suite_hook_failures.push(HookFailure::setup(None, error)),
+            }
+        }
 
-        Ok(Self { doco, selenium })
+        let mut outcomes = Vec::with_capacity(tests.len());
+        for test in tests {
+            if suite_hook_failures.is_empty() {
+                outcomes.push(self.run(test, &hooks, storage_state.as_ref()).await);
+            } else {
+                let outcome = TestOutcome::new(test, Ok(false), Vec::new(), Duration::ZERO, None);
+                self.report(|reporter| reporter.test_finished(&outcome));
+                outcomes.push(outcome);
+            }
+        }
+
+        for hook in hooks
+            .iter()
+            .filter(|hook| hook.kind() == HookKind::AfterAll)
+        {
+            if let HookFunction::AfterAll(function) = hook.function {
+                if let Err(error) = call(function).await {
+                    suite_hook_failures.push(HookFailure::new(hook, None, error));
+                }
+            }
+        }
+
+        let summary = Summary::new(outcomes, suite_hook_failures, started.elapsed());
+        self.report(|reporter| reporter.suite_finished(&summary));
+
+        if summary.success() {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        }
     }
 
     /// Run the given test in the ephemeral environment
     ///
     /// This method executes a test in a clean, ephemeral environment. First, it starts any
     /// auxiliary services like databases and waits for them to be ready. Then, it starts the
-    /// server, configures the WebDriver [`Client`], and calls the test function.
+    /// server, configures the WebDriver [`Client`], runs the `before_each` hooks, and calls the
+    /// test function. Unless the test has opted out, the storage state that the setup function of
+    /// the suite has captured is restored first. The `after_each` hooks run after the test, even if
+    /// it failed.
+    ///
+    /// After the test, the logs of the server and its services are saved to the artifacts
+    /// directory. If the test or a hook failed, the logs are also printed to help with debugging.
+    /// When video recording is enabled, the browser session is recorded and saved next to the logs.
+    /// Artifacts that cannot be saved only cause a warning, so that the result of the test is kept.
+    pub(crate) async fn run(
+        &self,
+        test: &TestCase,
+        hooks: &[&Hook],
+        storage_state: Option<&StorageState>,
+    ) -> TestOutcome {
+        let started = Instant::now();
+
+        self.report(|reporter| reporter.test_started(test));
+
+        let mut hook_failures = Vec::new();
+        let mut provisioning = None;
+        let result = self
+            .run_in_environment(
+                test,
+                hooks,
+                storage_state,
+                &mut hook_failures,
+                &mut provisioning,
+            )
+            .await;
+        let outcome =
+            TestOutcome::new(test, result, hook_failures, started.elapsed(), provisioning);
+
+        self.report(|reporter| reporter.test_finished(&outcome));
+
+        outcome
+    }
+
+    /// Run a test and its hooks, and return whether the test has been run
     ///
-    /// It should not be necessary to use this struct directly. Instead, use the [`doco::main`] and
-    /// [`doco::test`] macros to automatically set up the test runner, collect all tests, and pass
-    /// them to the runner.
-    pub async fn run(&self, name: &str, test: fn(Client) -> Result<()>) -> Result<()> {
+    /// If the storage state cannot be restored or a `before_each` hook fails, the remaining
+    /// `before_each` hooks and the test are skipped, but the `after_each` hooks still run. Logs and
+    /// videos that cannot be captured only cause a warning. Failures of hooks are added to the given list instead of being returned as an error, and
+    /// the time it took to provision the environment is stored in `provisioning`.
+    async fn run_in_environment(
+        &self,
+        test: &TestCase,
+        hooks: &[&Hook],
+        storage_state: Option<&StorageState>,
+        hook_failures: &mut Vec<HookFailure>,
+        provisioning: &mut Option<Provisioning>,
+    ) -> Result<bool> {
+        let name = &test.qualified_name();
+        let path = &test.path();
+
+        let Environment {
+            server,
+            server_url,
+            services,
+            client,
+            network,
+            proxy,
+            provisioning: timings,
+        } = self
+            .start_environment(Some(name), self.snapshots(name, path))
+            .await?;
+        *provisioning = Some(timings);
+
+        let started = Instant::now();
+        match restore_storage_state(storage_state, test, &client).await {
+            Ok(false) => {}
+            Ok(true) => self.report_step(name, "storage state", Status::Passed, started),
+            Err(error) => {
+                self.report_step(name, "storage state", Status::Failed, started);
+                hook_failures.push(HookFailure::setup(Some(name), error));
+            }
+        }
+
+        let context = TestContext::new(
+            name,
+            self.doco.clone(),
+            client.clone(),
+            server_url,
+            server,
+            services,
+            network.clone(),
+        );
+        let logs = match Logs::setup(&context).await {
+            Ok(logs) => Some(logs),
+            Err(error) => {
+                self.warn_artifact(name, ArtifactKind::Logs, &error);
+                None
+            }
+        };
+
+        let recorder = match self.doco.video() {
+            Video::Off => None,
+            _ => {
+                let directory = self.doco.artifacts().join(path).join("video");
+                match Recorder::start(&self.selenium, &directory).await {
+                    Ok(recorder) => Some(recorder),
+                    Err(error) => {
+                        self.warn_artifact(name, ArtifactKind::Video, &error);
+                        None
+                    }
+                }
+            }
+        };
+
+        for hook in hooks {
+            if !hook_failures.is_empty() {
+                break;
+            }
+
+            if let HookFunction::BeforeEach(function) = hook.function {
+                let context = context.clone();
+                let started = Instant::now();
+
+                let result = call(move || function(context)).await;
+                self.report_hook_step(name, hook, &result, started);
+
+                if let Err(error) = result {
+                    hook_failures.push(HookFailure::new(hook, Some(name), error));
+                    break;
+                }
+            }
+        }
+
+        let started = Instant::now();
+        let result = if hook_failures.is_empty() {
+            let function = test.function;
+            let context = context.clone();
+
+            match call(move || function(context)).await {
+                Ok(()) if self.doco.fail_on_console_errors() => {
+                    client.assert_no_console_errors().await.map(|()| true)
+                }
+                result => result.map(|()| true),
+            }
+        } else {
+            Ok(false)
+        };
+        let status = match result {
+            Ok(true) => Status::Passed,
+            Ok(false) => Status::Skipped,
+            Err(_) => Status::Failed,
+        };
+        self.report(|reporter| reporter.step_finished(name, "test", status, started.elapsed()));
+
+        for hook in hooks {
+            if let HookFunction::AfterEach(function) = hook.function {
+                let context = context.clone();
+                let started = Instant::now();
+
+                let result = call(move || function(context)).await;
+                self.report_hook_step(name, hook, &result, started);
+
+                if let Err(error) = result {
+                    hook_failures.push(HookFailure::new(hook, Some(name), error));
+                }
+            }
+        }
+
+        let failed = result.is_err() || !hook_failures.is_empty();
+
+        if let Some(logs) = logs {
+            if let Err(error) = self.collect_logs(name, path, &logs, failed).await {
+                self.warn_artifact(name, ArtifactKind::Logs, &error);
+            }
+        }
+
+        if let Some(network) = network {
+            drop(proxy);
+
+            let har = self.doco.artifacts().join(path).join("network.har");
+            match network.save_har(&har) {
+                Ok(()) => self.report(|reporter| reporter.artifact(name, ArtifactKind::Har, &har)),
+                Err(error) => self.warn_artifact(name, ArtifactKind::Har, &error),
+            }
+        }
+
+        if let Some(recorder) = recorder {
+            match recorder.finish(self.doco.video(), failed).await {
+                Ok(Some(video)) => {
+                    self.report(|reporter| reporter.artifact(name, ArtifactKind::Video, &video));
+                }
+                Ok(None) => {}
+                Err(error) => self.warn_artifact(name, ArtifactKind::Video, &error),
+            }
+        }
+
+        result
+    }
+
+    /// Report the step of a hook that has run for a test
+    fn report_hook_step(&self, test: &str, hook: &Hook, result: &Result<()>, started: Instant) {
+        let status = if result.is_ok() {
+            Status::Passed
+        } else {
+            Status::Failed
+        };
+        let step = format!("{} hook `{}`", hook.kind(), hook.name);
+
+        self.report_step(test, &step, status, started);
+    }
+
+    /// Report a step that has run for a test
+    fn report_step(&self, test: &str, step: &str, status: Status, started: Instant) {
+        self.report(|reporter| reporter.step_finished(test, step, status, started.elapsed()));
+    }
+
+    /// Start a clean instance of the server and its services, and connect a new browser session
+    ///
+    /// The time it takes to start each container and to create the browser session is reported,
+    /// together with the given test. The environment of the setup function is not reported.
+    async fn start_environment(
+        &self,
+        test: Option<&str>,
+        snapshots: Snapshots,
+    ) -> Result<Environment> {
+        let started = Instant::now();
+        if let Some(test) = test {
+            self.report(|reporter| reporter.provisioning_started(test));
+        }
+
         let mut services = Vec::with_capacity(self.doco.services().len());
+        let mut timings = Vec::with_capacity(self.doco.services().len() + 1);
 
         let mut server = GenericImage::new(self.doco.server().image(), self.doco.server().tag())
             .with_exposed_port(self.doco.server().port().tcp());
@@ -79,38 +461,287 @@ impl TestRunner {
                 image = image.with_env_var(env.name().clone(), env.value().clone());
             }
 
-            let container = image.start().await?;
+            let (container, timing) = self.start_container(service.host(), image).await?;
+            timings.push(timing);
 
             server = server.with_host(
-                service.image(),
+                service.host(),
                 Host::Addr(container.get_bridge_ip_address().await?),
             );
 
-            services.push(container);
+            services.push((service.host().to_string(), Arc::new(container)));
         }
 
-        let server = server.start().await?;
+        let (server, timing) = self.start_container("server", server).await?;
+        let server = Arc::new(server);
+        timings.push(timing);
         let port = server.get_host_port_ipv4(self.doco.server().port()).await?;
+        let server_url: Url = format!("http://{}:{port}", server.get_host().await?).parse()?;
+
+        let webdriver: Url = format!(
+            "http://{}:{}",
+            self.selenium.get_host().await?,
+            self.selenium.get_host_port_ipv4(4444).await?
+        )
+        .parse()?;
+
+        let mut capabilities = Capabilities::new();
+        capabilities.insert("webSocketUrl".into(), true.into());
+
+        let (network, proxy) = if self.doco.record_network() {
+            let network = Network::new(self.doco.timeout());
+            let proxy = Proxy::start(
+                network.clone(),
+                server.get_host().await?.to_string(),
+                self.gateway,
+            )
+            .await?;
+            capabilities.insert("proxy".into(), proxy.capability());
 
+            (Some(network), Some(proxy))
+        } else {
+            (None, None)
+        };
+
+        let session_started = Instant::now();
         let client = fantoccini::ClientBuilder::native()
-            .connect(&format!(
-                "http://{}:{}",
-                self.selenium.get_host().await?,
-                self.selenium.get_host_port_ipv4(4444).await?
-            ))
+            .capabilities(capabilities)
+            .connect(webdriver.as_str())
             .await
             .expect("failed to connect to WebDriver");
 
+        let console = match Console::capture(&client, &webdriver).await {
+            Ok(console) => console,
+            Err(error) => {
+                let message = format!(
+                    "failed to capture the browser console with WebDriver BiDi, messages logged \
+                     while a page is loading are missed: {error:#}"
+                );
+                self.report(|reporter| reporter.warning(test, &message));
+                Console::default()
+            }
+        };
+        let session = session_started.elapsed();
+
         let client = Client::builder()
             .base_url(format!("http://{DOCKER_HOST}:{port}").parse()?)
             .client(client)
+            .console(console)
+            .timeout(self.doco.timeout())
+            .test_id_attribute(self.doco.test_id_attribute())
+            .snapshots(snapshots)
             .build();
 
-        println!("{}...", name);
-        test(client)?;
+        let provisioning = Provisioning::new(started.elapsed(), timings, session);
+        if let Some(test) = test {
+            self.report(|reporter| reporter.provisioning_finished(test, &provisioning));
+        }
+
+        Ok(Environment {
+            server,
+            server_url,
+            services,
+            client,
+            network,
+            proxy,
+            provisioning,
+        })
+    }
+
+    /// Start a container and measure the time it takes to pull, start, and wait for it
+    ///
+    /// The image is only pulled if it does not exist locally. The time it takes to start the
+    /// container is measured between the moments at which Docker has created and started it, so
+    /// that it does not depend on the clock of the Docker daemon matching the local clock. The
+    /// rest of the time is spent waiting for the container to become ready. If Docker cannot be
+    /// asked for these moments, the time is counted towards starting the container instead.
+    async fn start_container(
+        &self,
+        name: &str,
+        request: ContainerRequest<GenericImage>,
+    ) -> Result<(ContainerAsync<GenericImage>, ContainerTiming)> {
+        let started = Instant::now();
+
+        let request = if self.image_exists(&request.descriptor()).await {
+            request
+        } else {
+            request.pull_image().await?
+        };
+        let pull = started.elapsed();
+
+        let container = request.start().await?;
+        let total = started.elapsed();
+
+        let start = self
+            .docker
+            .inspect_container(container.id(), None)
+            .await
+            .ok()
+            .and_then(startup)
+            .unwrap_or(total - pull)
+            .min(total - pull);
+        let ready = total - pull - start;
+
+        Ok((container, ContainerTiming::new(name, pull, start, ready)))
+    }
+
+    /// Check if the image exists locally, assuming that it does if Docker cannot be asked
+    async fn image_exists(&self, descriptor: &str) -> bool {
+        !matches!(
+            self.docker.inspect_image(descriptor).await,
+            Err(DockerError::DockerResponseServerError {
+                status_code: 404,
+                ..
+            })
+        )
+    }
+
+    /// Run the setup function of the test suite and capture the storage state of the browser
+    ///
+    /// The setup function runs once against its own instance of the server. Afterwards, the
+    /// cookies and `localStorage` of the browser are captured so that they can be restored in each
+    /// test.
+    async fn capture_storage_state(&self, setup: &Setup) -> Result<StorageState> {
+        let environment = self.start_environment(None, Snapshots::default()).await?;
+
+        capture_storage_state(setup, &environment.client).await
+    }
+
+    /// Return the storage for the snapshots of the test with the given name and path
+    fn snapshots(&self, name: &str, path: &Path) -> Snapshots {
+        Snapshots::new(
+            name,
+            self.doco.snapshots().join(path),
+            self.doco.artifacts().join(path).join("snapshots"),
+            self.cli.update_snapshots(),
+            self.reporters.clone(),
+        )
+    }
+
+    /// Collect the logs of the server and its services after a test
+    ///
+    /// The logs are saved to a `logs` directory inside the test's artifacts directory. If the test
+    /// has failed, the logs are also passed to the reporters so that errors on the server are
+    /// visible.
+    async fn collect_logs(&self, name: &str, path: &Path, logs: &Logs, failed: bool) -> Result<()> {
+        let directory = self.doco.artifacts().join(path).join("logs");
+
+        for (container, log) in logs.all().await? {
+            log.save(&directory, &container)?;
+
+            if failed {
+                self.report(|reporter| reporter.test_logs(name, &container, &log));
+            }
+        }
+
+        self.report(|reporter| reporter.artifact(name, ArtifactKind::Logs, &directory));
 
         Ok(())
     }
+
+    /// Warn that an artifact of a test could not be saved
+    ///
+    /// Artifacts help to debug a test, but failing to save them must not hide the result of the test.
+    fn warn_artifact(&self, test: &str, kind: ArtifactKind, error: &Error) {
+        let message = format!("failed to save the {kind}: {error:#}");
+        self.report(|reporter| reporter.warning(Some(test), &message));
+    }
+
+    /// Notify all reporters
+    fn report(&self, notify: impl Fn(&dyn Reporter)) {
+        reporter::notify(&self.reporters, notify);
+    }
+}
+
+/// Return the reporters for the test suite
+///
+/// The reporters that have been registered on [`Doco`] are used, or the [`Pretty`] reporter if
+/// none have been registered. A [`Json`] reporter is added for `--events`, which replaces the
+/// default reporter when it writes to stdout. In that case, no other reporter may write to stdout,
+/// since the event stream would be mixed with human-readable output.
+fn reporters(doco: &Doco, cli: &Cli) -> Result<Vec<Arc<dyn Reporter>>> {
+    let mut reporters = doco.reporters().clone();
+
+    let events_to_stdout = cli.events().as_deref().is_some_and(Json::is_stdout);
+    if events_to_stdout {
+        if let Some(reporter) = reporters
+            .iter()
+            .find(|reporter| reporter.writes_to_stdout())
+        {
+            bail!("cannot write events to stdout, since the reporter {reporter:?} writes to stdout as well");
+        }
+    } else if reporters.is_empty() {
+        reporters.push(Arc::new(Pretty));
+    }
+
+    if let Some(target) = cli.events() {
+        reporters.push(Arc::new(Json::open(target)?));
+    }
+
+    Ok(reporters)
+}
+
+/// Return the time between the creation and the start of a container as reported by Docker
+fn startup(container: ContainerInspectResponse) -> Option<Duration> {
+    let created = DateTime::parse_from_rfc3339(&container.created?).ok()?;
+    let started = DateTime::parse_from_rfc3339(&container.state?.started_at?).ok()?;
+
+    (started - created).to_std().ok()
+}
+
+/// Return the gateway through which the container with the given id reaches the host
+async fn gateway(docker: &Docker, id: &str) -> Option<IpAddr> {
+    let container = docker.inspect_container(id, None).await.ok()?;
+
+    container
+        .network_settings?
+        .networks?
+        .into_values()
+        .find_map(|network| network.gateway.filter(|gateway| !gateway.is_empty()))?
+        .parse()
+        .ok()
+}
+
+/// Run the setup function of the test suite with the given client and capture its storage state
+async fn capture_storage_state(setup: &Setup, client: &Client) -> Result<StorageState> {
+    setup
+        .run(client.clone())
+        .await
+        .context("failed to run the setup function of the test suite")?;
+
+    client
+        .storage_state()
+        .await
+        .context("failed to capture the storage state of the setup function")
+}
+
+/// Restore the storage state of the setup function in the browser of a test, unless it has opted out
+///
+/// Returns whether the storage state has been restored.
+async fn restore_storage_state(
+    state: Option<&StorageState>,
+    test: &TestCase,
+    client: &Client,
+) -> Result<bool> {
+    match state {
+        Some(state) if test.storage_state => client
+            .restore_storage_state(state)
+            .await
+            .context("failed to restore the storage state of the setup function")
+            .map(|()| true),
+        _ => Ok(false),
+    }
+}
+
+/// Call a test or hook function on a blocking thread
+///
+/// The functions that the macros generate block on Doco's asynchronous runtime, which is not
+/// allowed on the threads of the runtime itself. A panic in the function is returned as an error.
+async fn call(function: impl FnOnce() -> Result<()> + Send + 'static) -> Result<()> {
+    tokio::task::spawn_blocking(function)
+        .await
+        .map_err(|_| anyhow!("failed to run test in isolated thread"))
+        .and_then(|result| result)
 }
 
 /// Start the Selenium container
@@ -130,8 +761,10 @@ async fn start_selenium() -> Result<ContainerAsync<GenericImage>> {
 
 #[cfg(test)]
 mod tests {
+    use axum::http::{header, HeaderMap};
     use axum::routing::get;
     use axum::Router;
+    use testcontainers::bollard::models::ContainerState;
     use tokio::net::TcpListener;
 
     use crate::test_utils::*;
@@ -168,6 +801,193 @@ mod tests {
         Ok(())
     }
 
+    /// The session cookie that every instance of the application accepts, like a signed cookie
+    const SIGNED_SESSION: &str = "user.signature";
+
+    /// Serve an application with stateless sessions, and return its port
+    async fn serve_sessions() -> Result<u16> {
+        async fn login() -> ([(header::HeaderName, String); 1], &'static str) {
+            (
+                [(
+                    header::SET_COOKIE,
+                    format!("session={SIGNED_SESSION}; Path=/"),
+                )],
+                "logged in",
+            )
+        }
+
+        async fn dashboard(headers: HeaderMap) -> &'static str {
+            let authenticated = headers
+                .get_all(header::COOKIE)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split("; "))
+                .any(|cookie| cookie.strip_prefix("session=") == Some(SIGNED_SESSION));
+
+            if authenticated {
+                "welcome"
+            } else {
+                "login required"
+            }
+        }
+
+        let listener = TcpListener::bind("0.0.0.0:0").await?;
+        let port = listener.local_addr()?.port();
+
+        let app = Router::new()
+            .route("/login", get(login))
+            .route("/dashboard", get(dashboard));
+        tokio::spawn(async { axum::serve(listener, app).await });
+
+        Ok(port)
+    }
+
+    fn test_case(storage_state: bool) -> TestCase {
+        TestCase {
+            name: "shows_dashboard",
+            module_path: "e2e",
+            file: "tests/e2e.rs",
+            line: 12,
+            storage_state,
+            function: |_| Ok(()),
+        }
+    }
+
+    async fn login(client: Client) -> Result<()> {
+        client.goto("/login").await?;
+
+        Ok(())
+    }
+
+    /// Connect a new browser session to a new instance of the application
+    async fn client(webdriver: &str) -> Result<Client> {
+        let port = serve_sessions().await?;
+
+        Ok(Client::builder()
+            .base_url(format!("http://{DOCKER_HOST}:{port}").parse()?)
+            .client(
+                fantoccini::ClientBuilder::native()
+                    .connect(webdriver)
+                    .await
+                    .expect("failed to connect to WebDriver"),
+            )
+            .build())
+    }
+
+    #[tokio::test]
+    async fn storage_state_is_captured_once_and_restored_in_each_test() -> Result<()> {
+        let selenium = start_selenium().await?;
+        let webdriver = format!(
+            "http://{}:{}",
+            selenium.get_host().await?,
+            selenium.get_host_port_ipv4(4444).await?
+        );
+
+        let state = capture_storage_state(&Setup::from(login), &client(&webdriver).await?).await?;
+
+        // Every test has its own server and browser, which start without the session
+        for storage_state in [true, true, false] {
+            let client = client(&webdriver).await?;
+
+            let restored =
+                restore_storage_state(Some(&state), &test_case(storage_state), &client).await?;
+            assert_eq!(storage_state, restored);
+
+            client.goto("/dashboard").await?;
+            let body = client.source().await?;
+            let expected = if storage_state {
+                "welcome"
+            } else {
+                "login required"
+            };
+
+            assert!(body.contains(expected), "{body}");
+        }
+
+        Ok(())
+    }
+
+    fn doco(reporter: Option<Arc<dyn Reporter>>) -> Doco {
+        let server = crate::Server::builder()
+            .image("crccheck/hello-world")
+            .tag("v1.0.0")
+            .port(8000)
+            .build();
+
+        let mut doco = Doco::builder().server(server).build();
+        doco.reporters.extend(reporter);
+
+        doco
+    }
+
+    fn cli(args: &[&str]) -> Cli {
+        Cli::parse_from(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn reporters_default_to_pretty() -> Result<()> {
+        let reporters = reporters(&doco(None), &cli(&[]))?;
+
+        assert_eq!(vec![String::from("Pretty")], names(&reporters));
+
+        Ok(())
+    }
+
+    #[test]
+    fn reporters_replace_pretty_with_events_to_stdout() -> Result<()> {
+        let reporters = reporters(&doco(None), &cli(&["--events", "-"]))?;
+
+        assert_eq!(1, reporters.len());
+        assert!(reporters[0].writes_to_stdout());
+        assert!(names(&reporters)[0].starts_with("Json"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn reporters_refuse_human_output_with_events_to_stdout() {
+        let doco = doco(Some(Arc::new(crate::reporters::Terse)));
+
+        let error = reporters(&doco, &cli(&["--events", "-"])).unwrap_err();
+
+        assert!(error.to_string().contains("Terse"), "{error}");
+    }
+
+    fn names(reporters: &[Arc<dyn Reporter>]) -> Vec<String> {
+        reporters
+            .iter()
+            .map(|reporter| format!("{reporter:?}"))
+            .collect()
+    }
+
+    fn inspected(created: &str, started_at: &str) -> ContainerInspectResponse {
+        ContainerInspectResponse {
+            created: Some(created.into()),
+            state: Some(ContainerState {
+                started_at: Some(started_at.into()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn startup_between_creation_and_start() {
+        let container = inspected(
+            "2024-10-27T12:30:44.900000000Z",
+            "2024-10-27T12:30:45.123456789Z",
+        );
+
+        assert_eq!(Some(Duration::from_nanos(223_456_789)), startup(container));
+    }
+
+    #[test]
+    fn startup_without_start() {
+        let container = inspected("2024-10-27T12:30:44.9Z", "0001-01-01T00:00:00Z");
+
+        assert_eq!(None, startup(container));
+    }
+
     #[test]
     fn trait_send() {
         assert_send::<TestRunner>();
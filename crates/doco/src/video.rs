@@ -0,0 +1,177 @@
+//! Video recordings of the browser session in each test
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use testcontainers::core::{Host, Mount, WaitFor};
+use testcontainers::runners::AsyncRunner;
+use testcontainers::{ContainerAsync, GenericImage, ImageExt};
+
+use crate::Result;
+
+/// The hostname under which the recorder reaches the Selenium container
+const SELENIUM_HOST: &str = "selenium";
+
+/// The name of the file that the recorder writes
+const VIDEO_FILE: &str = "video.mp4";
+
+/// Option to record videos of the browser session in each test
+///
+/// Screenshots only show the final state of the web application, while a video shows the
+/// interactions that have led to it. When recording is enabled, Doco attaches a recorder to the
+/// Selenium container for each test and saves the video as `video/video.mp4` in the test's
+/// artifacts directory.
+///
+/// # Example
+///
+/// ```rust
+/// use doco::{Doco, Server, Video};
+///
+/// fn doco() -> Doco {
+///     let server = Server::builder()
+///         .image("crccheck/hello-world")
+///         .tag("v1.0.0")
+///         .port(8000)
+///         .build();
+///
+///     Doco::builder()
+///         .server(server)
+///         .video(Video::RetainOnFailure)
+///         .build()
+/// }
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub enum Video {
+    /// Do not record any videos
+    #[default]
+    Off,
+
+    /// Record every test, but only keep the videos of failed tests
+    RetainOnFailure,
+
+    /// Record every test and keep all videos
+    On,
+}
+
+impl Video {
+    /// Check if the video of a test should be kept
+    fn keep(&self, failed: bool) -> bool {
+        match self {
+            Video::Off => false,
+            Video::RetainOnFailure => failed,
+            Video::On => true,
+        }
+    }
+}
+
+/// Recorder that captures the display of the Selenium container
+///
+/// The recorder runs the official `selenium/video` image in a sidecar container, which connects to
+/// the X display of the Selenium container and encodes it with `ffmpeg`. The video is written to
+/// a directory on the host that is mounted into the container.
+#[derive(Debug)]
+pub(crate) struct Recorder {
+    /// The running recorder container
+    container: ContainerAsync<GenericImage>,
+
+    /// The directory on the host to which the video is written
+    directory: PathBuf,
+}
+
+impl Recorder {
+    /// Start recording the display of the Selenium container
+    pub(crate) async fn start(
+        selenium: &ContainerAsync<GenericImage>,
+        directory: &Path,
+    ) -> Result<Self> {
+        std::fs::create_dir_all(directory)
+            .with_context(|| format!("failed to create directory {}", directory.display()))?;
+
+        // The recorder runs as an unprivileged user and must be able to write to the directory
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            std::fs::set_permissions(directory, std::fs::Permissions::from_mode(0o777))?;
+        }
+
+        let directory = directory.canonicalize()?;
+
+        let container = GenericImage::new("selenium/video", "latest")
+            .with_wait_for(WaitFor::message_on_stderr("Output #0"))
+            .with_host(
+                SELENIUM_HOST,
+                Host::Addr(selenium.get_bridge_ip_address().await?),
+            )
+            .with_env_var("DISPLAY_CONTAINER_NAME", SELENIUM_HOST)
+            .with_env_var("SE_VIDEO_FILE_NAME", VIDEO_FILE)
+            .with_mount(Mount::bind_mount(directory.to_string_lossy(), "/videos"))
+            .start()
+            .await
+            .context("failed to start video recorder")?;
+
+        Ok(Self {
+            container,
+            directory,
+        })
+    }
+
+    /// Stop recording and keep or discard the video
+    ///
+    /// Stopping the container signals `ffmpeg` to finalize the video. Afterwards, the video is
+    /// deleted unless the `video` option asks for it to be kept. The path of a kept video is
+    /// returned.
+    pub(crate) async fn finish(self, video: Video, failed: bool) -> Result<Option<PathBuf>> {
+        self.container
+            .stop()
+            .await
+            .context("failed to stop video recorder")?;
+
+        if !video.keep(failed) {
+            std::fs::remove_dir_all(&self.directory).with_context(|| {
+                format!("failed to delete video in {}", self.directory.display())
+            })?;
+
+            return Ok(None);
+        }
+
+        Ok(Some(self.directory.join(VIDEO_FILE)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_utils::*;
+
+    use super::*;
+
+    #[test]
+    fn keep_retain_on_failure() {
+        assert!(Video::RetainOnFailure.keep(true));
+        assert!(!Video::RetainOnFailure.keep(false));
+    }
+
+    #[test]
+    fn keep_on() {
+        assert!(Video::On.keep(true));
+        assert!(Video::On.keep(false));
+    }
+
+    #[test]
+    fn trait_send() {
+        assert_send::<Video>();
+        assert_send::<Recorder>();
+    }
+
+    #[test]
+    fn trait_sync() {
+        assert_sync::<Video>();
+        assert_sync::<Recorder>();
+    }
+
+    #[test]
+    fn trait_unpin() {
+        assert_unpin::<Video>();
+        assert_unpin::<Recorder>();
+    }
+}
@@ -0,0 +1,209 @@
+//! Locators that wait for elements to reach a certain state
+
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use anyhow::anyhow;
+use fantoccini::elements::Element;
+use fantoccini::error::CmdError;
+use fantoccini::Locator;
+
+use crate::{Client, Result};
+
+/// The default time that locators wait for an element
+pub(crate) const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The interval in which locators check the state of an element
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+impl Client {
+    /// Wait until an element exists
+    ///
+    /// This method waits until an element matching the locator exists in the DOM, and returns it.
+    /// It fails with a descriptive error if no such element appears within the timeout of the
+    /// client, which can be configured with [`Doco::timeout`](crate::Doco::timeout).
+    ///
+    /// Like all waiting locators, this method accepts both a [`Locator`] and a reference to a
+    /// semantic [`By`](crate::By) locator.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use doco::{Client, Locator, Result};
+    ///
+    /// async fn shows_greeting(client: Client) -> Result<()> {
+    ///     client.goto("/").await?;
+    ///
+    ///     let greeting = client.wait_for_element(Locator::Css("h1")).await?;
+    ///
+    ///     assert_eq!("Hello World", greeting.text().await?);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn wait_for_element<'l>(&self, locator: impl Into<Locator<'l>>) -> Result<Element> {
+        self.wait_until(locator, "to exist", |_| async { Ok(true) })
+            .await
+    }
+
+    /// Wait until an element is visible
+    pub async fn wait_for_visible<'l>(&self, locator: impl Into<Locator<'l>>) -> Result<Element> {
+        self.wait_until(locator, "to be visible", |element| async move {
+            element.is_displayed().await
+        })
+        .await
+    }
+
+    /// Wait until an element is enabled
+    pub async fn wait_for_enabled<'l>(&self, locator: impl Into<Locator<'l>>) -> Result<Element> {
+        self.wait_until(locator, "to be enabled", |element| async move {
+            element.is_enabled().await
+        })
+        .await
+    }
+
+    /// Wait until the text of an element contains the given text
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use doco::{Client, Locator, Result};
+    ///
+    /// async fn increments_counter(client: Client) -> Result<()> {
+    ///     client.goto("/").await?;
+    ///
+    ///     client.find(Locator::Css("button")).await?.click().await?;
+    ///     client.wait_for_text(Locator::Css("button"), "Clicks: 1").await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn wait_for_text<'l>(
+        &self,
+        locator: impl Into<Locator<'l>>,
+        text: &str,
+    ) -> Result<Element> {
+        let description = format!("to contain the text {text:?}");
+
+        self.wait_until(locator, &description, |element| async move {
+            Ok(element.text().await?.contains(text))
+        })
+        .await
+    }
+
+    /// Wait until an element matches the given predicate
+    ///
+    /// This method repeatedly looks up the element and calls the predicate with it until the
+    /// predicate returns `true`. The element is looked up again in every iteration, so that the
+    /// method keeps working when the web application replaces the element in the DOM. The
+    /// description is used in the error message when the timeout is reached.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use doco::{Client, Locator, Result};
+    ///
+    /// async fn accepts_terms(client: Client) -> Result<()> {
+    ///     client.goto("/signup").await?;
+    ///
+    ///     client.find(Locator::Id("terms")).await?.click().await?;
+    ///     client
+    ///         .wait_until(Locator::Id("terms"), "to be checked", |element| async move {
+    ///             element.is_selected().await
+    ///         })
+    ///         .await?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn wait_until<'l, F, Fut>(
+        &self,
+        locator: impl Into<Locator<'l>>,
+        description: &str,
+        mut predicate: F,
+    ) -> Result<Element>
+    where
+        F: FnMut(Element) -> Fut,
+        Fut: Future<Output = Result<bool, CmdError>>,
+    {
+        let locator = locator.into();
+        let timeout = self.timeout();
+        let start = Instant::now();
+
+        loop {
+            let remaining = timeout.saturating_sub(start.elapsed());
+
+            let element = match self
+                .wait()
+                .at_most(remaining)
+                .every(POLL_INTERVAL)
+                .for_element(locator)
+                .await
+            {
+                Ok(element) => element,
+                Err(CmdError::WaitTimeout) => {
+                    return Err(timeout_error(locator, "to exist", timeout));
+                }
+                Err(error) => return Err(error.into()),
+            };
+
+            match predicate(element.clone()).await {
+                Ok(true) => return Ok(element),
+                Ok(false) => {}
+                Err(error) if error.is_stale_element_reference() => {}
+                Err(error) => return Err(error.into()),
+            }
+
+            if start.elapsed() >= timeout {
+                return Err(timeout_error(locator, description, timeout));
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Describe a locator in a human-readable way for error messages
+pub(crate) fn describe(locator: Locator<'_>) -> String {
+    match locator {
+        Locator::Css(selector) => format!("css `{selector}`"),
+        Locator::Id(id) => format!("id `{id}`"),
+        Locator::LinkText(text) => format!("link text `{text}`"),
+        Locator::XPath(xpath) => format!("xpath `{xpath}`"),
+    }
+}
+
+/// Create the error for a locator that has reached its timeout
+fn timeout_error(locator: Locator<'_>, description: &str, timeout: Duration) -> anyhow::Error {
+    anyhow!(
+        "timed out after {timeout:?} waiting for element with {} {description}",
+        describe(locator)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describe_locators() {
+        assert_eq!("css `main > h1`", describe(Locator::Css("main > h1")));
+        assert_eq!("id `title`", describe(Locator::Id("title")));
+        assert_eq!("link text `Home`", describe(Locator::LinkText("Home")));
+        assert_eq!("xpath `//button`", describe(Locator::XPath("//button")));
+    }
+
+    #[test]
+    fn timeout_error_is_descriptive() {
+        let error = timeout_error(
+            Locator::Css("button"),
+            "to be visible",
+            Duration::from_secs(5),
+        );
+
+        assert_eq!(
+            "timed out after 5s waiting for element with css `button` to be visible",
+            error.to_string()
+        );
+    }
+}
@@ -1,4 +1,5 @@
-use doco::{Client, Doco, Result, Server, Service, WaitFor};
+use doco::services::Postgres;
+use doco::{Client, Doco, Result, Server};
 
 #[doco::test]
 async fn reads_from_database(client: Client) -> Result<()> {
@@ -13,21 +14,13 @@ async fn reads_from_database(client: Client) -> Result<()> {
 
 #[doco::main]
 async fn main() -> Doco {
+    let postgres = Postgres::new();
+
     let server = Server::builder()
         .image("doco")
         .tag("axum-postgres")
         .port(3000)
-        .build();
-
-    let postgres = Service::builder()
-        .image("postgres")
-        .tag("latest")
-        .env("POSTGRES_PASSWORD", "password")
-        .env("POSTGRES_USER", "postgres")
-        .env("POSTGRES_PASSWORD", "postgres")
-        .wait(WaitFor::message_on_stdout(
-            "database system is ready to accept connections",
-        ))
+        .env("DATABASE_URL", postgres.url())
         .build();
 
     Doco::builder().server(server).service(postgres).build()
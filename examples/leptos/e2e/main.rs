@@ -1,14 +1,11 @@
-use std::time::Duration;
-
-use doco::{Client, Doco, Locator, Result, Server};
-use tokio::time::sleep;
+use doco::{By, Client, Doco, Result, Server};
 
 #[doco::test]
 async fn has_title(client: Client) -> Result<()> {
     client.goto("/").await?;
 
     let title = client
-        .find(Locator::XPath("/html/body/main/h1"))
+        .find(By::role("heading").locator())
         .await?
         .text()
         .await?;
@@ -22,20 +19,16 @@ async fn has_title(client: Client) -> Result<()> {
 async fn clicking_button_increases_counter(client: Client) -> Result<()> {
     client.goto("/").await?;
 
-    let button = client
-        .find(Locator::XPath("/html/body/main/button"))
-        .await?;
+    let button = client.find(By::role("button").locator()).await?;
 
     let before = button.text().await?;
     assert_eq!("Click Me: 0", before);
 
     button.click().await?;
 
-    // Wait for the button to update
-    sleep(Duration::from_secs(1)).await;
-
-    let after = button.text().await?;
-    assert_eq!("Click Me: 1", after);
+    client
+        .wait_for_text(&By::role("button"), "Click Me: 1")
+        .await?;
 
     Ok(())
 }